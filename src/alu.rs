@@ -1,133 +1,1198 @@
+use std::io;
+
+use crate::bcd::{DIGIT_BITS, DecimalAdjuster};
+use crate::carry_lookahead::CarryLookaheadAdder;
+use crate::divider::Divider;
+use crate::faults::{FaultInjection, child_paths};
 use crate::full_adder::FullAdder;
+use crate::gate_factory::{GateFactory, GateSource, ReferenceGates};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate};
+use crate::incrementer::Incrementer;
+use crate::multiplier::ArrayMultiplier;
+use crate::mux::Mux2;
+use crate::netlist::{Netlist, NetlistBuilder};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+use crate::shifter::{ShiftDirection, ShiftFill, Shifter};
+
+pub use crate::alu_detail::{ALUDetail, ALUResult, AdderDetail, Comparison};
+
+/// Width of the ALU used by the CPU
+pub const DEFAULT_WIDTH: usize = 4;
+
+/// Widest ALU supported (operands and results are `u64`)
+pub const MAX_WIDTH: usize = 64;
+
+/// Bits in each carry-lookahead block; wider adders chain blocks
+pub const LOOKAHEAD_BLOCK: usize = 4;
+
+/// Widest ALU that can decimal adjust: the flags only hold the carries out of bit 3
+/// (half-carry) and the MSB (carry), so every digit's carry is known up to 8 bits
+pub const MAX_DECIMAL_WIDTH: usize = 8;
 
-/// 4-bit ALU operations
+/// ALU operations
 #[derive(Debug, Clone, PartialEq)]
 pub enum ALUOperation {
     Add,      // A + B
-    Subtract, // A - B (using two's complement)
+    Subtract, // A - B (using two's complement, B inverted by neural NOT gates)
+    And,      // A AND B, bit by bit
+    Or,       // A OR B, bit by bit
+    Xor,      // A XOR B, bit by bit
+    Not,      // NOT A, bit by bit (B is ignored)
+    Compare,  // A - B for the flags and comparison outputs only
+    Multiply, // A * B: low word as the result, high word in `ALUDetail::high_word`
+    Divide,   // A / B: quotient as the result, remainder in `ALUDetail::remainder`
+    Modulo,   // A % B: remainder as the result (both set overflow when B is zero)
+
+    // Counting by one on the half adder chain (B is ignored); carry when the count wraps
+    Increment, // A + 1
+    Decrement, // A - 1
+
+    // Multi-word arithmetic: the carry in is the carry out of the previous word
+    AddWithCarry(bool),       // A + B + carry in
+    SubtractWithBorrow(bool), // A - B - 1 + carry in (carry clear means a borrow)
+
+    // Shifts and rotates of A by one bit (B is ignored); the bit shifted out goes to carry
+    ShiftLeft,                     // A << 1, zero shifted in
+    ShiftRight,                    // A >> 1, zero shifted in
+    RotateLeft,                    // MSB moves to bit 0
+    RotateRight,                   // LSB moves to the MSB
+    RotateLeftThroughCarry(bool),  // Carry in moves to bit 0
+    RotateRightThroughCarry(bool), // Carry in moves to the MSB
+
+    // Correct A, the binary sum or difference of two BCD numbers, back to BCD (B is ignored).
+    // ALUs wider than MAX_DECIMAL_WIDTH leave A unchanged and set overflow
+    DecimalAdjust {
+        carry: bool,      // Carry flag left by the addition or subtraction
+        half_carry: bool, // Half-carry flag (the carry between the digits of a byte)
+        subtract: bool,   // A is a difference rather than a sum
+    },
+}
+
+/// How the ALU adds its operands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdderArchitecture {
+    RippleCarry,    // Chain of full adders, each waiting for the previous carry
+    CarryLookahead, // Propagate/generate logic computing every carry in parallel
+}
+
+impl AdderArchitecture {
+    /// Gate count and depth of an adder of the given width
+    pub fn stats(&self, width: usize) -> AdderStats {
+        match self {
+            AdderArchitecture::RippleCarry => {
+                // Each full adder: XOR/AND, then XOR/AND with Cin, then OR
+                let mut carry_depth = 0;
+                let mut gate_depth = 0;
+                for _ in 0..width {
+                    let sum_depth = carry_depth.max(1) + 1;
+                    carry_depth = carry_depth.max(1) + 2;
+                    gate_depth = gate_depth.max(sum_depth).max(carry_depth);
+                }
+                AdderStats {
+                    gate_count: 5 * width,
+                    gate_depth,
+                }
+            }
+            AdderArchitecture::CarryLookahead => {
+                // Per block: P/G, product terms, carry ORs, sum XORs; blocks chain their carries
+                let mut carry_depth = 0;
+                let mut gate_depth = 0;
+                let mut gate_count = 0;
+                for block in block_widths(width) {
+                    let first_sum_depth = carry_depth.max(1) + 1;
+                    carry_depth = (carry_depth + 1).max(2) + 1;
+                    let sum_depth = if block > 1 {
+                        carry_depth + 1
+                    } else {
+                        first_sum_depth
+                    };
+                    gate_depth = gate_depth.max(sum_depth).max(carry_depth);
+                    gate_count += 4 * block + block * (block + 1) / 2;
+                }
+                AdderStats {
+                    gate_count,
+                    gate_depth,
+                }
+            }
+        }
+    }
+}
+
+/// Size and speed of an adder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdderStats {
+    pub gate_count: usize, // Number of gates
+    pub gate_depth: usize, // Gates on the longest path from an input to an output
+}
+
+/// Units an ALU builds beyond its adder, logic gates and shifter
+///
+/// An ALU without the unit an operation needs leaves A unchanged and sets
+/// overflow, like a decimal adjust on an ALU that is too wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ALUUnits {
+    pub multiplier: bool,  // Array multiplier for Multiply
+    pub divider: bool,     // Restoring divider for Divide and Modulo
+    pub decimal: bool,     // Digit decision gates for DecimalAdjust
+    pub incrementer: bool, // Half adder chain for Increment and Decrement
+}
+
+impl ALUUnits {
+    /// Every unit, so the ALU runs every operation
+    pub const ALL: ALUUnits = ALUUnits {
+        multiplier: true,
+        divider: true,
+        decimal: true,
+        incrementer: true,
+    };
+
+    /// No unit: only arithmetic, logic, comparisons, shifts and rotates
+    pub const NONE: ALUUnits = ALUUnits {
+        multiplier: false,
+        divider: false,
+        decimal: false,
+        incrementer: false,
+    };
+
+    /// Names of the units, as written in weights files
+    fn names(&self) -> Vec<&'static str> {
+        [
+            (self.multiplier, "multiplier"),
+            (self.divider, "divider"),
+            (self.decimal, "decimal"),
+            (self.incrementer, "incrementer"),
+        ]
+        .into_iter()
+        .filter_map(|(built, name)| built.then_some(name))
+        .collect()
+    }
 }
 
-/// 4-bit ALU result
-#[derive(Debug, PartialEq)]
-pub struct ALUResult {
-    pub result: u8,  // 4-bit result (0-15)
-    pub carry: bool, // Carry/overflow flag
-    pub zero: bool,  // Zero flag
+/// Adder hardware of an ALU
+#[derive(Debug)]
+enum Adder<G: Gate> {
+    RippleCarry(Vec<FullAdder<G>>), // One full adder per bit, LSB first
+    CarryLookahead(Vec<CarryLookaheadAdder<G>>), // Lookahead blocks, LSB first
 }
 
-/// 4-bit Arithmetic Logic Unit built from full adders
+/// Arithmetic Logic Unit of any width from 1 to 64 bits, built from an adder and logic gates
 #[derive(Debug)]
-pub struct ALU {
-    // Four full adders for arithmetic operations
-    adder0: FullAdder, // LSB
-    adder1: FullAdder,
-    adder2: FullAdder,
-    adder3: FullAdder, // MSB
+pub struct ALU<G: Gate = LogicGate> {
+    width: usize,
+    adder: Adder<G>,
+
+    // One NOT gate per bit to invert the subtrahend (and for NOT)
+    inverters: Vec<G>,
+
+    // One gate per bit for each bitwise logic operation
+    and_gates: Vec<G>,
+    or_gates: Vec<G>,
+    xor_gates: Vec<G>,
+
+    // Multiplexers for shifts and rotates
+    shifter: Shifter<G>,
+
+    // AND gates and full adders for multiplication
+    multiplier: Option<ArrayMultiplier<G>>,
+
+    // Quotient and restore logic driving the subtractor for division
+    divider: Option<Divider<G>>,
+
+    // Digit decision gates for decimal adjust (one set per whole BCD digit)
+    decimal: Option<DecimalAdjuster<G>>,
+
+    // Half adder chain counting by one (also drives the program counter)
+    incrementer: Option<Incrementer<G>>,
 }
 
 impl ALU {
-    /// Create a new 4-bit ALU with trained neural components
+    /// Create a new 4-bit ALU with trained neural components (one network per gate type, shared)
     pub fn new() -> Self {
-        // Create full adders for arithmetic
-        // A FullAdder has 2 XOR gates, 2 AND gates, and 1 OR gate
-        let adder0 = FullAdder::new();
-        let adder1 = FullAdder::new();
-        let adder2 = FullAdder::new();
-        let adder3 = FullAdder::new();
+        Self::with_factory(&mut GateFactory::shared())
+    }
+
+    /// Gate-level description of a ripple-carry ALU of the given width with every unit
+    ///
+    /// See `to_netlist` for the ports.
+    pub fn netlist(width: usize) -> Netlist {
+        ALU::with_width(&mut ReferenceGates, width, AdderArchitecture::RippleCarry).to_netlist()
+    }
+}
+
+impl<G: Gate> ALU<G> {
+    /// Create a 4-bit ripple-carry ALU using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S) -> Self {
+        Self::with_architecture(factory, AdderArchitecture::RippleCarry)
+    }
+
+    /// Create a 4-bit ALU with the given adder architecture
+    pub fn with_architecture<S: GateSource<Gate = G>>(
+        factory: &mut S,
+        architecture: AdderArchitecture,
+    ) -> Self {
+        Self::with_width(factory, DEFAULT_WIDTH, architecture)
+    }
+
+    /// Create an ALU of the given width (1 to 64 bits) with the given adder architecture
+    pub fn with_width<S: GateSource<Gate = G>>(
+        factory: &mut S,
+        width: usize,
+        architecture: AdderArchitecture,
+    ) -> Self {
+        Self::with_units(factory, width, architecture, ALUUnits::ALL)
+    }
+
+    /// Create an ALU that only builds the given units, saving their gates and training
+    pub fn with_units<S: GateSource<Gate = G>>(
+        factory: &mut S,
+        width: usize,
+        architecture: AdderArchitecture,
+        units: ALUUnits,
+    ) -> Self {
+        assert!(
+            (1..=MAX_WIDTH).contains(&width),
+            "ALU width must be between 1 and {} bits",
+            MAX_WIDTH
+        );
+
+        let adder = match architecture {
+            // A FullAdder has 2 XOR gates, 2 AND gates, and 1 OR gate
+            AdderArchitecture::RippleCarry => Adder::RippleCarry(
+                (0..width)
+                    .map(|_| FullAdder::with_factory(factory))
+                    .collect(),
+            ),
+            AdderArchitecture::CarryLookahead => Adder::CarryLookahead(
+                block_widths(width)
+                    .map(|block| CarryLookaheadAdder::with_factory(factory, block))
+                    .collect(),
+            ),
+        };
+        let inverters = (0..width).map(|_| factory.gate(GateType::NOT)).collect();
+        let and_gates = (0..width).map(|_| factory.gate(GateType::AND)).collect();
+        let or_gates = (0..width).map(|_| factory.gate(GateType::OR)).collect();
+        let xor_gates = (0..width).map(|_| factory.gate(GateType::XOR)).collect();
+        let shifter = Shifter::with_factory(factory, width);
+        let multiplier = units
+            .multiplier
+            .then(|| ArrayMultiplier::with_factory(factory, width));
+        let divider = units.divider.then(|| Divider::with_factory(factory, width));
+        let decimal = units
+            .decimal
+            .then(|| DecimalAdjuster::with_factory(factory, decimal_digits(width)));
+        let incrementer = units
+            .incrementer
+            .then(|| Incrementer::with_factory(factory, width));
 
         ALU {
-            adder0,
-            adder1,
-            adder2,
-            adder3,
+            width,
+            adder,
+            inverters,
+            and_gates,
+            or_gates,
+            xor_gates,
+            shifter,
+            multiplier,
+            divider,
+            decimal,
+            incrementer,
+        }
+    }
+
+    /// Number of bits in each operand
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Largest value the ALU can hold (all bits set)
+    pub fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.width)
+    }
+
+    /// Adder architecture chosen at construction
+    pub fn architecture(&self) -> AdderArchitecture {
+        match self.adder {
+            Adder::RippleCarry(_) => AdderArchitecture::RippleCarry,
+            Adder::CarryLookahead(_) => AdderArchitecture::CarryLookahead,
+        }
+    }
+
+    /// Gate count and depth of the adder
+    pub fn adder_stats(&self) -> AdderStats {
+        self.architecture().stats(self.width)
+    }
+
+    /// Units built at construction
+    pub fn units(&self) -> ALUUnits {
+        ALUUnits {
+            multiplier: self.multiplier.is_some(),
+            divider: self.divider.is_some(),
+            decimal: self.decimal.is_some(),
+            incrementer: self.incrementer.is_some(),
+        }
+    }
+
+    /// Whether the ALU has the hardware to run the operation
+    pub fn supports(&self, operation: &ALUOperation) -> bool {
+        match operation {
+            ALUOperation::Multiply => self.multiplier.is_some(),
+            ALUOperation::Divide | ALUOperation::Modulo => self.divider.is_some(),
+            ALUOperation::Increment | ALUOperation::Decrement => self.incrementer.is_some(),
+            ALUOperation::DecimalAdjust { .. } => {
+                self.decimal.is_some() && self.width <= MAX_DECIMAL_WIDTH
+            }
+            _ => true,
+        }
+    }
+
+    /// Number of gates in every unit of the ALU
+    pub fn gate_count(&self) -> usize {
+        let adder = match &self.adder {
+            Adder::RippleCarry(adders) => adders.iter().map(FullAdder::gate_count).sum(),
+            Adder::CarryLookahead(blocks) => blocks
+                .iter()
+                .map(CarryLookaheadAdder::gate_count)
+                .sum::<usize>(),
+        };
+        let logic = self.inverters.len()
+            + self.and_gates.len()
+            + self.or_gates.len()
+            + self.xor_gates.len();
+        adder
+            + logic
+            + self.shifter.gate_count()
+            + self
+                .multiplier
+                .as_ref()
+                .map_or(0, ArrayMultiplier::gate_count)
+            + self.divider.as_ref().map_or(0, Divider::gate_count)
+            + self.decimal.as_ref().map_or(0, DecimalAdjuster::gate_count)
+            + self.incrementer.as_ref().map_or(0, Incrementer::gate_count)
+    }
+
+    /// Gate-level description of this ALU, generated from its units
+    ///
+    /// Every unit sees the operands at once, so one evaluation is one pass
+    /// through the hardware; the controls `compute` derives from the operation
+    /// are inputs. Inputs are `a0..`, `b0..`, `carry_in`, `subtract` and the
+    /// shifter controls `shift_left`, `shift_rotating`, `shift_through_carry`,
+    /// then `decrement` for the incrementer, `shifted_out` for the divider and
+    /// each digit's `digit_flag0..` and `digit_rippled0..` for decimal adjust,
+    /// when the ALU has those units.
+    ///
+    /// Outputs are the adder's `sum0..` and `carry`, the bitwise `and0..`,
+    /// `or0..`, `xor0..` and `not0..` (the inverters, fed from B), the
+    /// shifter's `shift0..` and `shift_carry`, then `product0..`, the division
+    /// step's `quotient`, `remainder0..` and `divide_by_zero` (A is the partial
+    /// remainder, the adder subtracts B), `adjust0..` per digit, and `count0..` with
+    /// `count_carry`. Where `compute` hands the adder B or its inverse, the
+    /// netlist picks one with a `Mux2` per bit (`select0..`), the only cells
+    /// that are not gates of the ALU. Division and decimal adjust
+    /// repeat their pass once per quotient bit and digit in `compute`.
+    pub fn to_netlist(&self) -> Netlist {
+        let width = self.width;
+        let mux = Mux2::netlist();
+        let mut builder = NetlistBuilder::new("alu");
+        let a = builder.inputs("a", width);
+        let b = builder.inputs("b", width);
+        let carry_in = builder.input("carry_in");
+        let subtract = builder.input("subtract");
+        let shift_left = builder.input("shift_left");
+        let shift_rotating = builder.input("shift_rotating");
+        let shift_through_carry = builder.input("shift_through_carry");
+        let decrement = self
+            .incrementer
+            .is_some()
+            .then(|| builder.input("decrement"));
+        let shifted_out = self.divider.is_some().then(|| builder.input("shifted_out"));
+        let decimal = self.decimal.as_ref().filter(|decimal| decimal.digits() > 0);
+        let digit_lines = decimal.map(|decimal| {
+            let flags = builder.inputs("digit_flag", decimal.digits());
+            let rippled = builder.inputs("digit_rippled", decimal.digits());
+            (flags, rippled)
+        });
+
+        // B or its inverse into the adder
+        let mut inverted = Vec::new();
+        let mut addend = Vec::new();
+        for (bit, inverter) in self.inverters.iter().enumerate() {
+            let not = builder.gate(
+                &format!("not{}", bit),
+                inverter.gate_type().clone(),
+                &[b[bit]],
+            );
+            let select =
+                builder.instance(&format!("select{}", bit), &mux, &[subtract, b[bit], not]);
+            inverted.push(not);
+            addend.push(select[0]);
+        }
+
+        let mut carry = carry_in;
+        let mut sums = Vec::new();
+        match &self.adder {
+            Adder::RippleCarry(adders) => {
+                for (bit, adder) in adders.iter().enumerate() {
+                    let stage = builder.instance(
+                        &format!("adder{}", bit),
+                        &adder.to_netlist(),
+                        &[a[bit], addend[bit], carry],
+                    );
+                    sums.push(stage[0]);
+                    carry = stage[1];
+                }
+            }
+            Adder::CarryLookahead(blocks) => {
+                let mut low = 0;
+                for (index, block) in blocks.iter().enumerate() {
+                    let high = low + block.width();
+                    let mut inputs = a[low..high].to_vec();
+                    inputs.extend(&addend[low..high]);
+                    inputs.push(carry);
+                    let outputs = builder.instance(
+                        &format!("lookahead{}", index),
+                        &block.to_netlist(),
+                        &inputs,
+                    );
+                    sums.extend(&outputs[..block.width()]);
+                    carry = outputs[block.width()];
+                    low = high;
+                }
+            }
+        }
+        builder.outputs("sum", &sums);
+        builder.output("carry", carry);
+
+        for (name, gates) in [
+            ("and", &self.and_gates),
+            ("or", &self.or_gates),
+            ("xor", &self.xor_gates),
+        ] {
+            let outputs: Vec<_> = gates
+                .iter()
+                .enumerate()
+                .map(|(bit, gate)| {
+                    builder.gate(
+                        &format!("{}{}", name, bit),
+                        gate.gate_type().clone(),
+                        &[a[bit], b[bit]],
+                    )
+                })
+                .collect();
+            builder.outputs(name, &outputs);
+        }
+        builder.outputs("not", &inverted);
+
+        let mut inputs = a.clone();
+        inputs.extend([shift_left, shift_rotating, shift_through_carry, carry_in]);
+        let shifted = builder.instance("shifter", &self.shifter.to_netlist(), &inputs);
+        builder.outputs("shift", &shifted[..width]);
+        builder.output("shift_carry", shifted[width]);
+
+        if let Some(multiplier) = &self.multiplier {
+            let mut inputs = a.clone();
+            inputs.extend(&b);
+            let product = builder.instance("multiplier", &multiplier.to_netlist(), &inputs);
+            builder.outputs("product", &product);
+        }
+
+        if let (Some(divider), Some(shifted_out)) = (&self.divider, shifted_out) {
+            let mut inputs = a.clone();
+            inputs.extend(&sums);
+            inputs.extend([carry, shifted_out]);
+            inputs.extend(&b);
+            let step = builder.instance("divider", &divider.to_netlist(), &inputs);
+            builder.output("quotient", step[0]);
+            builder.outputs("remainder", &step[1..=width]);
+            builder.output("divide_by_zero", step[width + 1]);
+        }
+
+        if let (Some(decimal), Some((flags, rippled))) = (decimal, digit_lines) {
+            let mut inputs = a[..decimal.digits() * DIGIT_BITS].to_vec();
+            inputs.extend(flags);
+            inputs.extend(rippled);
+            inputs.push(subtract);
+            let adjust = builder.instance("decimal", &decimal.to_netlist(), &inputs);
+            builder.outputs("adjust", &adjust);
         }
+
+        if let (Some(incrementer), Some(decrement)) = (&self.incrementer, decrement) {
+            let mut inputs = a.clone();
+            inputs.push(decrement);
+            let count = builder.instance("incrementer", &incrementer.to_netlist(), &inputs);
+            builder.outputs("count", &count[..width]);
+            builder.output("count_carry", count[width]);
+        }
+
+        builder.build().expect("ALU netlist is well formed")
     }
 
-    /// Perform ALU operation on two 4-bit numbers
-    pub fn compute(&self, a: u8, b: u8, operation: ALUOperation) -> ALUResult {
-        // Ensure inputs are 4-bit
-        // u8 are masked to 4-bit
+    /// Perform ALU operation on two numbers of the ALU width
+    pub fn compute(&self, a: u64, b: u64, operation: ALUOperation) -> ALUResult {
+        self.compute_detailed(a, b, operation).result
+    }
+
+    /// Perform ALU operation keeping the raw activations of every gate
+    pub fn compute_detailed(&self, a: u64, b: u64, operation: ALUOperation) -> ALUDetail {
+        // Ensure inputs fit the ALU width
+        // For a 4-bit ALU the mask is 0x0F:
         // 0001 0100 -> 0000 0100
         // 15 -> 0000 1111 -> &0x0F -> 0000 1111
-        let a = a & 0x0F;
-        let b = b & 0x0F;
+        let a = a & self.mask();
+        let b = b & self.mask();
 
         match operation {
-            ALUOperation::Add => self.add(a, b),
-            ALUOperation::Subtract => self.subtract(a, b),
+            ALUOperation::Add => self.add(a, b, false),
+            ALUOperation::Subtract => self.subtract(a, b, true),
+            ALUOperation::AddWithCarry(carry) => self.add(a, b, carry),
+            ALUOperation::SubtractWithBorrow(carry) => self.subtract(a, b, carry),
+            ALUOperation::And => self.bitwise(&self.and_gates, a, b),
+            ALUOperation::Or => self.bitwise(&self.or_gates, a, b),
+            ALUOperation::Xor => self.bitwise(&self.xor_gates, a, b),
+            ALUOperation::Not => self.complement(a),
+            ALUOperation::Compare => self.comparison(a, b),
+            ALUOperation::Multiply => self.multiply(a, b),
+            ALUOperation::Divide => self.divide(a, b, false),
+            ALUOperation::Modulo => self.divide(a, b, true),
+            ALUOperation::Increment => self.count(a, false),
+            ALUOperation::Decrement => self.count(a, true),
+            ALUOperation::DecimalAdjust {
+                carry,
+                half_carry,
+                subtract,
+            } => self.decimal_adjust(a, carry, half_carry, subtract),
+            ALUOperation::ShiftLeft => self.shift(a, ShiftDirection::Left, ShiftFill::Zero, false),
+            ALUOperation::ShiftRight => {
+                self.shift(a, ShiftDirection::Right, ShiftFill::Zero, false)
+            }
+            ALUOperation::RotateLeft => {
+                self.shift(a, ShiftDirection::Left, ShiftFill::Rotate, false)
+            }
+            ALUOperation::RotateRight => {
+                self.shift(a, ShiftDirection::Right, ShiftFill::Rotate, false)
+            }
+            ALUOperation::RotateLeftThroughCarry(carry) => {
+                self.shift(a, ShiftDirection::Left, ShiftFill::Carry, carry)
+            }
+            ALUOperation::RotateRightThroughCarry(carry) => {
+                self.shift(a, ShiftDirection::Right, ShiftFill::Carry, carry)
+            }
         }
     }
 
-    /// 4-bit addition using chain of full adders
-    fn add(&self, a: u8, b: u8) -> ALUResult {
-        let a_bits = self.to_bits(a); // Convert to array of 4 booleans
+    /// Compare two numbers of the ALU width as unsigned and as two's complement numbers
+    pub fn compare(&self, a: u64, b: u64) -> Comparison {
+        self.compute_detailed(a, b, ALUOperation::Compare)
+            .comparison
+            .expect("compare always reports a comparison")
+    }
+
+    /// Addition on the adder hardware
+    fn add(&self, a: u64, b: u64, carry_in: bool) -> ALUDetail {
+        let a_bits = self.to_bits(a); // Convert to one boolean per bit
         let b_bits = self.to_bits(b);
 
-        // Chain full adders for ripple carry addition
-        let result0 = self.adder0.compute(a_bits[0], b_bits[0], false); // A, B, Cin = 0
-        let result1 = self.adder1.compute(a_bits[1], b_bits[1], result0.carry); // A, B, Cin = Cout1
-        let result2 = self.adder2.compute(a_bits[2], b_bits[2], result1.carry); // A, B, Cin = Cout2
-        let result3 = self.adder3.compute(a_bits[3], b_bits[3], result2.carry); // A, B, Cin = Cout3
+        self.sum(&a_bits, &b_bits, carry_in, Vec::new()) // Cin = 0 for a plain add
+    }
+
+    /// Subtraction using two's complement (A - B = A + (~B + 1))
+    fn subtract(&self, a: u64, b: u64, carry_in: bool) -> ALUDetail {
+        // Two's complement: invert bits with neural NOT gates and add 1
+        let a_bits = self.to_bits(a);
+        let inverted = self.invert(&self.to_bits(b));
+        let b_inv_bits: Vec<bool> = inverted.iter().map(|output| output.bit).collect();
+
+        // Add with initial carry = 1 (for +1 in two's complement), or 0 to take a borrow
+        self.sum(&a_bits, &b_inv_bits, carry_in, inverted)
+    }
 
-        let result_bits = [result0.sum, result1.sum, result2.sum, result3.sum];
-        let result_value = self.bits_to_u8(result_bits);
+    /// Comparison read off the flags of the neural subtraction A - B
+    fn comparison(&self, a: u64, b: u64) -> ALUDetail {
+        let mut detail = self.subtract(a, b, true);
+        let result = &detail.result;
 
-        ALUResult {
-            result: result_value,
-            carry: result3.carry,
-            zero: result_value == 0,
+        // A < B as signed numbers when the difference is negative, unless it overflowed
+        let signed_less = result.negative != result.overflow;
+
+        detail.comparison = Some(Comparison {
+            equal: result.zero,
+            less: !result.carry,
+            greater: result.carry && !result.zero,
+            signed_less,
+            signed_greater: !signed_less && !result.zero,
+        });
+        detail
+    }
+
+    /// Multiplication on the array multiplier; carry is set when the product needs the high word
+    fn multiply(&self, a: u64, b: u64) -> ALUDetail {
+        let Some(multiplier) = &self.multiplier else {
+            return self.unsupported(a, false);
+        };
+        let multiplier = multiplier.compute_detailed(&self.to_bits(a), &self.to_bits(b));
+        let low = self.bits_to_value(multiplier.low_bits());
+        let high_set = multiplier.high_bits().iter().any(|&bit| bit);
+
+        let mut result = self.flags(low, high_set, false, false);
+        result.zero = low == 0 && !high_set; // The whole product is zero
+        ALUDetail {
+            result,
+            adder: None,
+            inverters: Vec::new(),
+            logic: Vec::new(),
+            shifter: None,
+            comparison: None,
+            multiplier: Some(multiplier),
+            divider: None,
+            decimal: None,
+            incrementer: None,
+        }
+    }
+
+    /// Restoring division driving the subtract path once per quotient bit
+    ///
+    /// Dividing by zero does not panic: the hardware gives a quotient of all
+    /// ones and the dividend as the remainder, and the divider's NOR over the
+    /// divisor bits sets the overflow flag.
+    fn divide(&self, a: u64, b: u64, modulo: bool) -> ALUDetail {
+        let Some(divider) = &self.divider else {
+            return self.unsupported(a, false);
+        };
+        let divider = divider.compute_detailed(&self.to_bits(a), &self.to_bits(b), |partial| {
+            self.subtract(self.bits_to_value(partial), b, true)
+        });
+        let value = if modulo {
+            self.bits_to_value(&divider.remainder_bits())
+        } else {
+            self.bits_to_value(&divider.quotient_bits())
+        };
+
+        ALUDetail {
+            result: self.flags(value, false, divider.divide_by_zero.bit, false),
+            adder: None,
+            inverters: Vec::new(),
+            logic: Vec::new(),
+            shifter: None,
+            comparison: None,
+            multiplier: None,
+            divider: Some(divider),
+            decimal: None,
+            incrementer: None,
+        }
+    }
+
+    /// Count up or down by one on the half adder chain
+    fn count(&self, a: u64, decrement: bool) -> ALUDetail {
+        let Some(incrementer) = &self.incrementer else {
+            return self.unsupported(a, false);
+        };
+        let incrementer = incrementer.compute_detailed(&self.to_bits(a), decrement);
+        let result_value = self.bits_to_value(&incrementer.result_bits());
+
+        // Signed overflow when the sign flips the wrong way: up into negative, down into positive
+        let msb = self.width - 1;
+        let was_negative = (a >> msb) & 1 != 0;
+        let is_negative = (result_value >> msb) & 1 != 0;
+        let overflow = was_negative != is_negative && is_negative != decrement;
+        let half_carry = self.width >= DIGIT_BITS && incrementer.carries()[DIGIT_BITS - 1];
+
+        ALUDetail {
+            result: self.flags(result_value, incrementer.carry(), overflow, half_carry),
+            adder: None,
+            inverters: Vec::new(),
+            logic: Vec::new(),
+            shifter: None,
+            comparison: None,
+            multiplier: None,
+            divider: None,
+            decimal: None,
+            incrementer: Some(incrementer),
+        }
+    }
+
+    /// Decimal adjust: each digit above 9 or with a carry gets 6 added, or 6
+    /// subtracted when it borrowed, on the adder
+    ///
+    /// The top digit's carry is the carry flag and the low digit's is the
+    /// half-carry flag, which covers every digit of an ALU up to
+    /// `MAX_DECIMAL_WIDTH` bits. Wider ALUs cannot know the carries between
+    /// their middle digits, so they leave A unchanged and set overflow.
+    fn decimal_adjust(&self, a: u64, carry: bool, half_carry: bool, subtract: bool) -> ALUDetail {
+        let Some(decimal) = self
+            .decimal
+            .as_ref()
+            .filter(|_| self.width <= MAX_DECIMAL_WIDTH)
+        else {
+            return self.unsupported(a, carry);
+        };
+
+        let digits = decimal.digits();
+        let carries: Vec<bool> = (0..digits)
+            .map(|digit| {
+                if (digit + 1) * DIGIT_BITS == self.width {
+                    carry
+                } else {
+                    half_carry
+                }
+            })
+            .collect();
+
+        let decimal = decimal.compute_detailed(a, &carries, subtract, |value, correction| {
+            if subtract {
+                self.subtract(value, correction, true)
+            } else {
+                self.add(value, correction, false)
+            }
+        });
+        let value = decimal
+            .digits
+            .last()
+            .map_or(a, |digit| digit.correction.result.result);
+
+        // A correction carrying out of the top bit after an addition is a decimal carry;
+        // a borrow stays a borrow
+        let decimal_carry = carry || (!subtract && decimal.carried_out());
+
+        ALUDetail {
+            result: self.flags(value, decimal_carry, false, false),
+            adder: None,
+            inverters: Vec::new(),
+            logic: Vec::new(),
+            shifter: None,
+            comparison: None,
+            multiplier: None,
+            divider: None,
+            decimal: Some(decimal),
+            incrementer: None,
         }
     }
 
-    /// 4-bit subtraction using two's complement (A - B = A + (~B + 1))
-    fn subtract(&self, a: u8, b: u8) -> ALUResult {
-        // Two's complement: invert bits and add 1
-        let b_inverted = (!b) & 0x0F;
+    /// Bitwise logic: each bit pair goes through its own gate
+    fn bitwise(&self, gates: &[G], a: u64, b: u64) -> ALUDetail {
         let a_bits = self.to_bits(a);
-        let b_inv_bits = self.to_bits(b_inverted);
+        let b_bits = self.to_bits(b);
+        let logic: Vec<GateOutput> = gates
+            .iter()
+            .enumerate()
+            .map(|(bit, gate)| {
+                let a_f = if a_bits[bit] { 1.0 } else { 0.0 };
+                let b_f = if b_bits[bit] { 1.0 } else { 0.0 };
+                gate.compute_detailed(vec![a_f, b_f])
+            })
+            .collect();
+
+        self.logic_result(Vec::new(), logic)
+    }
+
+    /// Bitwise NOT through the inverters
+    fn complement(&self, a: u64) -> ALUDetail {
+        let inverted = self.invert(&self.to_bits(a));
+        self.logic_result(inverted, Vec::new())
+    }
+
+    /// Result of a logic operation: the gate outputs are the result bits, carry is cleared
+    fn logic_result(&self, inverters: Vec<GateOutput>, logic: Vec<GateOutput>) -> ALUDetail {
+        let bits: Vec<bool> = inverters
+            .iter()
+            .chain(&logic)
+            .map(|output| output.bit)
+            .collect();
+        let result_value = self.bits_to_value(&bits);
+
+        ALUDetail {
+            result: self.flags(result_value, false, false, false),
+            adder: None,
+            inverters,
+            logic,
+            shifter: None,
+            comparison: None,
+            multiplier: None,
+            divider: None,
+            decimal: None,
+            incrementer: None,
+        }
+    }
+
+    /// Shift or rotate by one bit through the multiplexers
+    fn shift(&self, a: u64, direction: ShiftDirection, fill: ShiftFill, carry: bool) -> ALUDetail {
+        let shifter = self
+            .shifter
+            .compute_detailed(&self.to_bits(a), direction, fill, carry);
+        let result_value = self.bits_to_value(&shifter.result_bits());
+
+        ALUDetail {
+            result: self.flags(result_value, shifter.carry.output.bit, false, false),
+            adder: None,
+            inverters: Vec::new(),
+            logic: Vec::new(),
+            shifter: Some(shifter),
+            comparison: None,
+            multiplier: None,
+            divider: None,
+            decimal: None,
+            incrementer: None,
+        }
+    }
+
+    /// Add the bits on the adder hardware
+    fn sum(
+        &self,
+        a_bits: &[bool],
+        b_bits: &[bool],
+        carry_in: bool,
+        inverters: Vec<GateOutput>,
+    ) -> ALUDetail {
+        let adder = match &self.adder {
+            Adder::RippleCarry(adders) => {
+                // Cin of each stage is the carry out of the previous one
+                let mut carry = carry_in;
+                let stages = adders
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, adder)| {
+                        let stage = adder.compute_detailed(a_bits[bit], b_bits[bit], carry);
+                        carry = stage.carry.bit;
+                        stage
+                    })
+                    .collect();
+                AdderDetail::RippleCarry(stages)
+            }
+            Adder::CarryLookahead(blocks) => {
+                // Each block looks ahead internally; the carry passes between blocks
+                let mut carry = carry_in;
+                let mut low = 0;
+                let details = blocks
+                    .iter()
+                    .map(|block| {
+                        let high = low + block.width();
+                        let detail =
+                            block.compute_detailed(&a_bits[low..high], &b_bits[low..high], carry);
+                        carry = detail.carry();
+                        low = high;
+                        detail
+                    })
+                    .collect();
+                AdderDetail::CarryLookahead(details)
+            }
+        };
 
-        // Add with initial carry = 1 (for +1 in two's complement)
-        let result0 = self.adder0.compute(a_bits[0], b_inv_bits[0], true);
-        let result1 = self.adder1.compute(a_bits[1], b_inv_bits[1], result0.carry);
-        let result2 = self.adder2.compute(a_bits[2], b_inv_bits[2], result1.carry);
-        let result3 = self.adder3.compute(a_bits[3], b_inv_bits[3], result2.carry);
+        let result_value = self.bits_to_value(&adder.sum_bits());
 
-        let result_bits = [result0.sum, result1.sum, result2.sum, result3.sum];
-        let result_value = self.bits_to_u8(result_bits);
+        // Overflow compares the carry into the MSB (out of the stage below) with the carry out
+        let carries = adder.carries();
+        let msb = self.width - 1;
+        let carry_into_msb = if msb == 0 { carry_in } else { carries[msb - 1] };
+        let overflow = carry_into_msb != adder.carry();
+        let half_carry = self.width >= DIGIT_BITS && carries[DIGIT_BITS - 1];
+
+        ALUDetail {
+            result: self.flags(result_value, adder.carry(), overflow, half_carry),
+            adder: Some(adder),
+            inverters,
+            logic: Vec::new(),
+            shifter: None,
+            comparison: None,
+            multiplier: None,
+            divider: None,
+            decimal: None,
+            incrementer: None,
+        }
+    }
+
+    /// Result of an operation the ALU has no hardware for: A unchanged, overflow set
+    fn unsupported(&self, a: u64, carry: bool) -> ALUDetail {
+        ALUDetail {
+            result: self.flags(a, carry, true, false),
+            adder: None,
+            inverters: Vec::new(),
+            logic: Vec::new(),
+            shifter: None,
+            comparison: None,
+            multiplier: None,
+            divider: None,
+            decimal: None,
+            incrementer: None,
+        }
+    }
 
+    /// Result with the flags read off the value
+    fn flags(&self, result: u64, carry: bool, overflow: bool, half_carry: bool) -> ALUResult {
         ALUResult {
-            result: result_value,
-            carry: result3.carry,
-            zero: result_value == 0,
+            result,
+            carry,
+            zero: result == 0,
+            negative: (result >> (self.width - 1)) & 1 != 0,
+            overflow,
+            half_carry,
         }
     }
 
-    /// Convert u8 to array of 4 bits (LSB first)
-    fn to_bits(&self, value: u8) -> [bool; 4] {
-        [
-            (value & 0x01) != 0, // position 0
-            (value & 0x02) != 0, // position 1
-            (value & 0x04) != 0, // position 2
-            (value & 0x08) != 0, // position 3
-        ]
+    /// Invert each bit through its NOT gate
+    fn invert(&self, bits: &[bool]) -> Vec<GateOutput> {
+        self.inverters
+            .iter()
+            .zip(bits)
+            .map(|(inverter, &bit)| {
+                let input = if bit { 1.0 } else { 0.0 };
+                inverter.compute_detailed(vec![input])
+            })
+            .collect()
+    }
+
+    /// Convert a value to one bit per ALU bit (LSB first)
+    fn to_bits(&self, value: u64) -> Vec<bool> {
+        (0..self.width)
+            .map(|position| (value >> position) & 1 != 0)
+            .collect()
+    }
+
+    /// Convert bits (LSB first) back to a value
+    fn bits_to_value(&self, bits: &[bool]) -> u64 {
+        bits.iter()
+            .enumerate()
+            .filter(|&(_, &bit)| bit)
+            .fold(0, |result, (position, _)| result | (1 << position))
+    }
+}
+
+impl Persist for ALU {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        match &self.adder {
+            Adder::RippleCarry(adders) => {
+                writer.line(&format!("alu {} ripple_carry", self.width))?;
+                for adder in adders {
+                    adder.write_weights(writer)?;
+                }
+            }
+            Adder::CarryLookahead(blocks) => {
+                writer.line(&format!("alu {} carry_lookahead", self.width))?;
+                for block in blocks {
+                    block.write_weights(writer)?;
+                }
+            }
+        }
+        let gates = self
+            .inverters
+            .iter()
+            .chain(&self.and_gates)
+            .chain(&self.or_gates)
+            .chain(&self.xor_gates);
+        for gate in gates {
+            gate.write_weights(writer)?;
+        }
+        self.shifter.write_weights(writer)?;
+
+        let mut units = vec!["units"];
+        units.extend(self.units().names());
+        writer.line(&units.join(" "))?;
+        if let Some(multiplier) = &self.multiplier {
+            multiplier.write_weights(writer)?;
+        }
+        if let Some(divider) = &self.divider {
+            divider.write_weights(writer)?;
+        }
+        if let Some(decimal) = &self.decimal {
+            decimal.write_weights(writer)?;
+        }
+        if let Some(incrementer) = &self.incrementer {
+            incrementer.write_weights(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        let fields = reader.tagged("alu")?;
+        let (width, architecture) = match fields.as_slice() {
+            [width, architecture] => (width.parse::<usize>().ok(), architecture.as_str()),
+            _ => return Err(reader.error("expected an ALU width and adder architecture")),
+        };
+        let width = width
+            .filter(|width| (1..=MAX_WIDTH).contains(width))
+            .ok_or_else(|| reader.error("invalid ALU width"))?;
+
+        let adder = match architecture {
+            "ripple_carry" => Adder::RippleCarry(
+                (0..width)
+                    .map(|_| FullAdder::read_weights(reader))
+                    .collect::<io::Result<_>>()?,
+            ),
+            "carry_lookahead" => {
+                let mut blocks = Vec::new();
+                for expected in block_widths(width) {
+                    let block = CarryLookaheadAdder::read_weights(reader)?;
+                    if block.width() != expected {
+                        return Err(reader.error("carry-lookahead block has the wrong width"));
+                    }
+                    blocks.push(block);
+                }
+                Adder::CarryLookahead(blocks)
+            }
+            _ => return Err(reader.error("expected an adder architecture")),
+        };
+        let mut read_gates = |gate_type: GateType| {
+            (0..width)
+                .map(|_| LogicGate::read_typed(reader, &gate_type))
+                .collect::<io::Result<Vec<_>>>()
+        };
+        let inverters = read_gates(GateType::NOT)?;
+        let and_gates = read_gates(GateType::AND)?;
+        let or_gates = read_gates(GateType::OR)?;
+        let xor_gates = read_gates(GateType::XOR)?;
+        let shifter = Shifter::read_weights(reader)?;
+        if shifter.width() != width {
+            return Err(reader.error("shifter has the wrong width"));
+        }
+
+        let names = reader.tagged("units")?;
+        let units = ALUUnits {
+            multiplier: names.iter().any(|name| name == "multiplier"),
+            divider: names.iter().any(|name| name == "divider"),
+            decimal: names.iter().any(|name| name == "decimal"),
+            incrementer: names.iter().any(|name| name == "incrementer"),
+        };
+        if units.names() != names {
+            return Err(reader.error("expected unit names in order"));
+        }
+        let multiplier = units
+            .multiplier
+            .then(|| ArrayMultiplier::read_weights(reader))
+            .transpose()?;
+        if multiplier
+            .as_ref()
+            .is_some_and(|unit| unit.width() != width)
+        {
+            return Err(reader.error("multiplier has the wrong width"));
+        }
+        let divider = units
+            .divider
+            .then(|| Divider::read_weights(reader))
+            .transpose()?;
+        if divider.as_ref().is_some_and(|unit| unit.width() != width) {
+            return Err(reader.error("divider has the wrong width"));
+        }
+        let decimal = units
+            .decimal
+            .then(|| DecimalAdjuster::read_weights(reader))
+            .transpose()?;
+        if decimal
+            .as_ref()
+            .is_some_and(|unit| unit.digits() != decimal_digits(width))
+        {
+            return Err(reader.error("decimal adjust has the wrong number of digits"));
+        }
+        let incrementer = units
+            .incrementer
+            .then(|| Incrementer::read_weights(reader))
+            .transpose()?;
+        if incrementer
+            .as_ref()
+            .is_some_and(|unit| unit.width() != width)
+        {
+            return Err(reader.error("incrementer has the wrong width"));
+        }
+
+        Ok(ALU {
+            width,
+            adder,
+            inverters,
+            and_gates,
+            or_gates,
+            xor_gates,
+            shifter,
+            multiplier,
+            divider,
+            decimal,
+            incrementer,
+        })
+    }
+}
+
+/// Gates are addressed as `adder0`, `adder1`, ... (then the full adder path) for ripple carry,
+/// `lookahead0`, `lookahead1`, ... (then the block path) for carry lookahead, and
+/// `not0`, `and0`, `or0`, `xor0`, ... for the per-bit gates, and `shifter`, `multiplier`,
+/// `divider`, `decimal` or `incrementer` (then the component path)
+impl FaultInjection for ALU {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        let (name, rest) = path.split_first()?;
+        if *name == "shifter" {
+            return self.shifter.gate_mut(rest);
+        }
+        if *name == "multiplier" {
+            return self.multiplier.as_mut()?.gate_mut(rest);
+        }
+        if *name == "divider" {
+            return self.divider.as_mut()?.gate_mut(rest);
+        }
+        if *name == "decimal" {
+            return self.decimal.as_mut()?.gate_mut(rest);
+        }
+        if *name == "incrementer" {
+            return self.incrementer.as_mut()?.gate_mut(rest);
+        }
+        match &mut self.adder {
+            Adder::RippleCarry(adders) => {
+                if let Some(index) = name.strip_prefix("adder") {
+                    return adders.get_mut(index.parse::<usize>().ok()?)?.gate_mut(rest);
+                }
+            }
+            Adder::CarryLookahead(blocks) => {
+                if let Some(index) = name.strip_prefix("lookahead") {
+                    return blocks.get_mut(index.parse::<usize>().ok()?)?.gate_mut(rest);
+                }
+            }
+        }
+        let (gates, index) = if let Some(index) = name.strip_prefix("not") {
+            (&mut self.inverters, index)
+        } else if let Some(index) = name.strip_prefix("and") {
+            (&mut self.and_gates, index)
+        } else if let Some(index) = name.strip_prefix("xor") {
+            (&mut self.xor_gates, index)
+        } else if let Some(index) = name.strip_prefix("or") {
+            (&mut self.or_gates, index)
+        } else {
+            return None;
+        };
+        gates.get_mut(index.parse::<usize>().ok()?)?.gate_mut(rest)
     }
 
-    /// Convert array of 4 bits to u8 (LSB first)
-    fn bits_to_u8(&self, bits: [bool; 4]) -> u8 {
-        let mut result = 0u8;
-        if bits[0] {
-            result |= 0x01;
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        match &self.adder {
+            Adder::RippleCarry(adders) => {
+                for (index, adder) in adders.iter().enumerate() {
+                    paths.extend(child_paths(&format!("adder{}", index), adder));
+                }
+            }
+            Adder::CarryLookahead(blocks) => {
+                for (index, block) in blocks.iter().enumerate() {
+                    paths.extend(child_paths(&format!("lookahead{}", index), block));
+                }
+            }
+        }
+        let gates = [
+            ("not", &self.inverters),
+            ("and", &self.and_gates),
+            ("or", &self.or_gates),
+            ("xor", &self.xor_gates),
+        ];
+        for (name, gates) in gates {
+            for (index, gate) in gates.iter().enumerate() {
+                paths.extend(child_paths(&format!("{}{}", name, index), gate));
+            }
+        }
+        paths.extend(child_paths("shifter", &self.shifter));
+        if let Some(multiplier) = &self.multiplier {
+            paths.extend(child_paths("multiplier", multiplier));
         }
-        if bits[1] {
-            result |= 0x02;
+        if let Some(divider) = &self.divider {
+            paths.extend(child_paths("divider", divider));
         }
-        if bits[2] {
-            result |= 0x04;
+        if let Some(decimal) = &self.decimal {
+            paths.extend(child_paths("decimal", decimal));
         }
-        if bits[3] {
-            result |= 0x08;
+        if let Some(incrementer) = &self.incrementer {
+            paths.extend(child_paths("incrementer", incrementer));
         }
-        result
+        paths
     }
 }
 
@@ -136,3 +1201,19 @@ impl Default for ALU {
         Self::new()
     }
 }
+
+/// Digits the decimal adjust logic corrects: every whole digit, none above `MAX_DECIMAL_WIDTH`
+fn decimal_digits(width: usize) -> usize {
+    if width > MAX_DECIMAL_WIDTH {
+        0
+    } else {
+        width / DIGIT_BITS
+    }
+}
+
+/// Widths of the carry-lookahead blocks making up an adder, LSB first
+fn block_widths(width: usize) -> impl Iterator<Item = usize> {
+    (0..width)
+        .step_by(LOOKAHEAD_BLOCK)
+        .map(move |low| LOOKAHEAD_BLOCK.min(width - low))
+}
@@ -0,0 +1,169 @@
+use crate::bcd::DecimalDetail;
+use crate::carry_lookahead::CarryLookaheadDetail;
+use crate::divider::DividerDetail;
+use crate::full_adder::FullAdderDetail;
+use crate::gates::GateOutput;
+use crate::incrementer::IncrementerDetail;
+use crate::multiplier::MultiplierDetail;
+use crate::shifter::ShifterDetail;
+
+/// ALU result
+#[derive(Debug, Clone, PartialEq)]
+pub struct ALUResult {
+    pub result: u64,      // Result masked to the ALU width (0-15 for 4 bits)
+    pub carry: bool,      // Carry out of the MSB (no borrow for subtraction)
+    pub zero: bool,       // Zero flag
+    pub negative: bool,   // MSB of the result (sign in two's complement)
+    pub overflow: bool,   // Signed overflow: carry into the MSB differs from carry out
+    pub half_carry: bool, // Carry out of bit 3, the low BCD digit (clear below 4 bits)
+}
+
+/// Outputs of a comparison of A with B
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison {
+    pub equal: bool,          // A == B
+    pub less: bool,           // A < B as unsigned numbers (borrow out of A - B)
+    pub greater: bool,        // A > B as unsigned numbers
+    pub signed_less: bool,    // A < B as two's complement numbers
+    pub signed_greater: bool, // A > B as two's complement numbers
+}
+
+/// Adder outputs of either architecture
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdderDetail {
+    RippleCarry(Vec<FullAdderDetail>), // Full adder outputs, LSB first
+    CarryLookahead(Vec<CarryLookaheadDetail>), // Lookahead block outputs, LSB first
+}
+
+impl AdderDetail {
+    /// Thresholded sum bits, LSB first
+    pub fn sum_bits(&self) -> Vec<bool> {
+        match self {
+            AdderDetail::RippleCarry(stages) => stages.iter().map(|stage| stage.sum.bit).collect(),
+            AdderDetail::CarryLookahead(blocks) => blocks
+                .iter()
+                .flat_map(CarryLookaheadDetail::sum_bits)
+                .collect(),
+        }
+    }
+
+    /// Carry out of every bit, LSB first
+    pub fn carries(&self) -> Vec<bool> {
+        match self {
+            AdderDetail::RippleCarry(stages) => {
+                stages.iter().map(|stage| stage.carry.bit).collect()
+            }
+            AdderDetail::CarryLookahead(blocks) => blocks
+                .iter()
+                .flat_map(|block| block.carries.iter().map(|carry| carry.bit))
+                .collect(),
+        }
+    }
+
+    /// Carry out of the most significant bit
+    pub fn carry(&self) -> bool {
+        match self {
+            AdderDetail::RippleCarry(stages) => stages.last().is_some_and(|stage| stage.carry.bit),
+            AdderDetail::CarryLookahead(blocks) => {
+                blocks.last().is_some_and(CarryLookaheadDetail::carry)
+            }
+        }
+    }
+
+    /// Smallest distance from the decision threshold across every adder gate
+    pub fn min_margin(&self) -> f64 {
+        match self {
+            AdderDetail::RippleCarry(stages) => stages
+                .iter()
+                .map(FullAdderDetail::min_margin)
+                .fold(f64::INFINITY, f64::min),
+            AdderDetail::CarryLookahead(blocks) => blocks
+                .iter()
+                .map(CarryLookaheadDetail::min_margin)
+                .fold(f64::INFINITY, f64::min),
+        }
+    }
+}
+
+/// ALU result with the raw gate activations of every stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct ALUDetail {
+    pub result: ALUResult,
+    pub adder: Option<AdderDetail>, // Adder gate outputs (arithmetic only)
+    pub inverters: Vec<GateOutput>, // NOT gate outputs, LSB first (subtraction and NOT only)
+    pub logic: Vec<GateOutput>,     // AND/OR/XOR gate outputs, LSB first (bitwise logic only)
+    pub shifter: Option<ShifterDetail>, // Multiplexer outputs (shifts and rotates only)
+    pub comparison: Option<Comparison>, // Comparison outputs (compare only)
+    pub multiplier: Option<MultiplierDetail>, // Array multiplier outputs (multiply only)
+    pub divider: Option<DividerDetail>, // Divider steps (divide and modulo only)
+    pub decimal: Option<DecimalDetail>, // Digit corrections (decimal adjust only)
+    pub incrementer: Option<IncrementerDetail>, // Half adder chain (increment and decrement only)
+}
+
+impl ALUDetail {
+    /// Smallest distance from the decision threshold across every gate used
+    pub fn min_margin(&self) -> f64 {
+        let adder = self
+            .adder
+            .as_ref()
+            .map_or(f64::INFINITY, AdderDetail::min_margin);
+        let shifter = self
+            .shifter
+            .as_ref()
+            .map_or(f64::INFINITY, ShifterDetail::min_margin);
+        let multiplier = self
+            .multiplier
+            .as_ref()
+            .map_or(f64::INFINITY, MultiplierDetail::min_margin);
+        let divider = self
+            .divider
+            .as_ref()
+            .map_or(f64::INFINITY, DividerDetail::min_margin);
+        let decimal = self
+            .decimal
+            .as_ref()
+            .map_or(f64::INFINITY, DecimalDetail::min_margin);
+        let incrementer = self
+            .incrementer
+            .as_ref()
+            .map_or(f64::INFINITY, IncrementerDetail::min_margin);
+        self.inverters
+            .iter()
+            .chain(&self.logic)
+            .map(|output| output.margin)
+            .fold(adder.min(shifter), f64::min)
+            .min(multiplier)
+            .min(divider)
+            .min(decimal)
+            .min(incrementer)
+    }
+
+    /// High word of a multiplication product (zero for every other operation)
+    pub fn high_word(&self) -> u64 {
+        self.multiplier.as_ref().map_or(0, |multiplier| {
+            multiplier
+                .high_bits()
+                .iter()
+                .rev()
+                .fold(0, |value, &bit| (value << 1) | bit as u64)
+        })
+    }
+
+    /// Remainder of a division (zero for every other operation)
+    pub fn remainder(&self) -> u64 {
+        self.divider.as_ref().map_or(0, |divider| {
+            divider
+                .remainder_bits()
+                .iter()
+                .rev()
+                .fold(0, |value, &bit| (value << 1) | bit as u64)
+        })
+    }
+
+    /// Whether the operation was a division by zero
+    pub fn divide_by_zero(&self) -> bool {
+        self.divider
+            .as_ref()
+            .is_some_and(|divider| divider.divide_by_zero.bit)
+    }
+}
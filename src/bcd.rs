@@ -0,0 +1,368 @@
+use std::io;
+
+use crate::alu_detail::ALUDetail;
+use crate::faults::{FaultInjection, child_paths};
+use crate::gate_factory::{GateFactory, GateSource};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate, to_signal};
+use crate::mux::{Mux2, Mux2Detail};
+use crate::netlist::{Netlist, NetlistBuilder, Wire};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+
+/// Bits in one BCD digit
+pub const DIGIT_BITS: usize = 4;
+
+/// Decision gates and correction of one BCD digit
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigitDetail {
+    pub carry: GateOutput,     // Digit carry flag OR carry from a lower correction
+    pub middle: GateOutput,    // Bit 2 OR bit 1
+    pub over_nine: GateOutput, // Bit 3 AND (bit 2 OR bit 1): the digit is above 9
+    pub add: GateOutput,       // Above 9 OR carry (correction after an addition)
+    pub borrow: GateOutput,    // NOT carry (correction after a subtraction)
+    pub adjust: Mux2Detail,    // Add or borrow correction, selected by the subtract flag
+    pub correction: ALUDetail, // Adding or subtracting 6 (or 0) on the ALU adder
+}
+
+impl DigitDetail {
+    /// Smallest distance from the decision threshold across every gate
+    pub fn min_margin(&self) -> f64 {
+        [
+            &self.carry,
+            &self.middle,
+            &self.over_nine,
+            &self.add,
+            &self.borrow,
+        ]
+        .iter()
+        .map(|output| output.margin)
+        .fold(self.correction.min_margin(), f64::min)
+        .min(self.adjust.min_margin())
+    }
+}
+
+/// Decimal adjust result with the raw output of every digit
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecimalDetail {
+    pub digits: Vec<DigitDetail>, // One entry per digit, least significant first
+}
+
+impl DecimalDetail {
+    /// Whether a correction carried out of the most significant bit
+    pub fn carried_out(&self) -> bool {
+        self.digits
+            .iter()
+            .any(|digit| digit.correction.result.carry)
+    }
+
+    /// Smallest distance from the decision threshold across every gate
+    pub fn min_margin(&self) -> f64 {
+        self.digits
+            .iter()
+            .map(DigitDetail::min_margin)
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// Neural gates deciding whether one BCD digit needs correcting
+#[derive(Debug)]
+pub struct DigitCorrector<G: Gate = LogicGate> {
+    carry: G,        // Carry flag OR carry out of the digit during a lower correction
+    middle: G,       // Bit 2 OR bit 1
+    over_nine: G,    // Bit 3 AND middle
+    add: G,          // Over nine OR carry
+    borrow: G,       // NOT carry
+    select: Mux2<G>, // Add correction or borrow correction
+}
+
+impl<G: Gate> DigitCorrector<G> {
+    /// Create the decision gates using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S) -> Self {
+        DigitCorrector {
+            carry: factory.gate(GateType::OR),
+            middle: factory.gate(GateType::OR),
+            over_nine: factory.gate(GateType::AND),
+            add: factory.gate(GateType::OR),
+            borrow: factory.gate(GateType::NOT),
+            select: Mux2::with_factory(factory),
+        }
+    }
+
+    /// Number of gates: four decisions, the borrow inverter and the select multiplexer
+    fn gate_count(&self) -> usize {
+        5 + self.select.gate_count()
+    }
+
+    /// Decide whether the digit (LSB first) needs correcting: carry, middle, over nine, add,
+    /// borrow, and the selected adjust
+    fn decide(
+        &self,
+        digit: &[bool],
+        flag: bool,
+        rippled: bool,
+        subtract: bool,
+    ) -> ([GateOutput; 5], Mux2Detail) {
+        let carry = self
+            .carry
+            .compute_detailed(vec![to_signal(flag), to_signal(rippled)]);
+        let middle = self
+            .middle
+            .compute_detailed(vec![to_signal(digit[2]), to_signal(digit[1])]);
+        let over_nine = self
+            .over_nine
+            .compute_detailed(vec![to_signal(digit[3]), to_signal(middle.bit)]);
+        let add = self
+            .add
+            .compute_detailed(vec![to_signal(over_nine.bit), to_signal(carry.bit)]);
+        let borrow = self.borrow.compute_detailed(vec![to_signal(carry.bit)]);
+        let adjust = self.select.compute_detailed(subtract, add.bit, borrow.bit);
+        ([carry, middle, over_nine, add, borrow], adjust)
+    }
+
+    /// Gate-level description: inputs `bit0..bit3`, `flag`, `rippled`, `subtract`, output `adjust`
+    fn to_netlist(&self) -> Netlist {
+        let mut builder = NetlistBuilder::new("digit_corrector");
+        let digit = builder.inputs("bit", DIGIT_BITS);
+        let flag = builder.input("flag");
+        let rippled = builder.input("rippled");
+        let subtract = builder.input("subtract");
+
+        let mut gate = |name: &str, gate: &G, inputs: &[Wire]| {
+            builder.gate(name, gate.gate_type().clone(), inputs)
+        };
+        let carry = gate("carry", &self.carry, &[flag, rippled]);
+        let middle = gate("middle", &self.middle, &[digit[2], digit[1]]);
+        let over_nine = gate("over_nine", &self.over_nine, &[digit[3], middle]);
+        let add = gate("add", &self.add, &[over_nine, carry]);
+        let borrow = gate("borrow", &self.borrow, &[carry]);
+        let adjust = builder.instance(
+            "select",
+            &self.select.to_netlist(),
+            &[subtract, add, borrow],
+        );
+
+        builder.output("adjust", adjust[0]);
+        builder
+            .build()
+            .expect("digit corrector netlist is well formed")
+    }
+}
+
+/// Decimal adjust logic turning a binary sum or difference of BCD numbers back into BCD
+///
+/// Digits are corrected least significant first. After an addition a digit
+/// above 9, or one that carried out, gets 6 added; after a subtraction a
+/// digit that borrowed gets 6 subtracted. The correction itself runs on the
+/// ALU adder, so a carry out of a corrected digit reaches the next digit
+/// before that digit is checked, and counts as that digit's carry if it
+/// wrapped it past 15.
+#[derive(Debug)]
+pub struct DecimalAdjuster<G: Gate = LogicGate> {
+    digits: Vec<DigitCorrector<G>>, // One per whole digit, least significant first
+}
+
+impl DecimalAdjuster {
+    /// Create decimal adjust logic for the given number of digits with trained neural gates
+    pub fn new(digits: usize) -> Self {
+        Self::with_factory(&mut GateFactory::shared(), digits)
+    }
+}
+
+impl<G: Gate> DecimalAdjuster<G> {
+    /// Create decimal adjust logic using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S, digits: usize) -> Self {
+        DecimalAdjuster {
+            digits: (0..digits)
+                .map(|_| DigitCorrector::with_factory(factory))
+                .collect(),
+        }
+    }
+
+    /// Number of digits corrected
+    pub fn digits(&self) -> usize {
+        self.digits.len()
+    }
+
+    /// Number of decision gates, five per digit plus its select multiplexer
+    ///
+    /// The corrections run on the ALU's adder and are not counted here.
+    pub fn gate_count(&self) -> usize {
+        self.digits.iter().map(DigitCorrector::gate_count).sum()
+    }
+
+    /// Gate-level description of the decisions, one instance per digit
+    ///
+    /// Inputs are the value `value0..`, each digit's carry `flag0..` and carry
+    /// from a lower correction `rippled0..`, and `subtract`; outputs are
+    /// `adjust0..`, set for each digit that needs 6 added or subtracted.
+    /// `compute_detailed` runs the corrections on the adder digit by digit.
+    pub fn to_netlist(&self) -> Netlist {
+        let digits = self.digits();
+        let mut builder = NetlistBuilder::new("decimal_adjust");
+        let value = builder.inputs("value", digits * DIGIT_BITS);
+        let flags = builder.inputs("flag", digits);
+        let rippled = builder.inputs("rippled", digits);
+        let subtract = builder.input("subtract");
+
+        let adjust: Vec<Wire> = self
+            .digits
+            .iter()
+            .enumerate()
+            .map(|(index, corrector)| {
+                let shift = index * DIGIT_BITS;
+                let mut inputs = value[shift..shift + DIGIT_BITS].to_vec();
+                inputs.extend([flags[index], rippled[index], subtract]);
+                let outputs =
+                    builder.instance(&format!("digit{}", index), &corrector.to_netlist(), &inputs);
+                outputs[0]
+            })
+            .collect();
+
+        builder.outputs("adjust", &adjust);
+        builder
+            .build()
+            .expect("decimal adjust netlist is well formed")
+    }
+
+    /// Correct `value` digit by digit, running `correct(value, correction)` on the adder
+    ///
+    /// `carries` holds the carry out of each digit (no borrow after a
+    /// subtraction); `correct` adds the correction to the value, or subtracts
+    /// it when `subtract` is set.
+    pub fn compute_detailed<F>(
+        &self,
+        value: u64,
+        carries: &[bool],
+        subtract: bool,
+        mut correct: F,
+    ) -> DecimalDetail
+    where
+        F: FnMut(u64, u64) -> ALUDetail,
+    {
+        assert_eq!(carries.len(), self.digits(), "one carry per digit");
+
+        let mut value = value;
+        let mut rippled = vec![false; self.digits()]; // Carried out of each digit by a correction
+        let digits = self
+            .digits
+            .iter()
+            .zip(carries)
+            .enumerate()
+            .map(|(index, (corrector, &carry))| {
+                let shift = index * DIGIT_BITS;
+                let bits: Vec<bool> = (0..DIGIT_BITS)
+                    .map(|bit| value >> (shift + bit) & 1 == 1)
+                    .collect();
+                let ([carry, middle, over_nine, add, borrow], adjust) =
+                    corrector.decide(&bits, carry, rippled[index], subtract);
+
+                let amount = if adjust.output.bit { 6 } else { 0 };
+                let correction = correct(value, amount << shift);
+                value = correction.result.result;
+
+                // Adding 6 can carry on through the digits above (a subtraction never borrows)
+                if let (false, Some(adder)) = (subtract, &correction.adder) {
+                    let carries = adder.carries();
+                    for (digit, rippled) in rippled.iter_mut().enumerate().skip(index + 1) {
+                        *rippled |= carries[(digit + 1) * DIGIT_BITS - 1];
+                    }
+                }
+
+                DigitDetail {
+                    carry,
+                    middle,
+                    over_nine,
+                    add,
+                    borrow,
+                    adjust,
+                    correction,
+                }
+            })
+            .collect();
+
+        DecimalDetail { digits }
+    }
+}
+
+impl Persist for DecimalAdjuster {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line(&format!("decimal {}", self.digits()))?;
+        for digit in &self.digits {
+            for gate in [
+                &digit.carry,
+                &digit.middle,
+                &digit.over_nine,
+                &digit.add,
+                &digit.borrow,
+            ] {
+                gate.write_weights(writer)?;
+            }
+            digit.select.write_weights(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        let fields = reader.tagged("decimal")?;
+        let digits: usize = match fields.as_slice() {
+            [digits] => digits
+                .parse()
+                .map_err(|_| reader.error("invalid decimal digit count"))?,
+            _ => return Err(reader.error("expected a decimal digit count")),
+        };
+
+        let digits = (0..digits)
+            .map(|_| {
+                Ok(DigitCorrector {
+                    carry: LogicGate::read_typed(reader, &GateType::OR)?,
+                    middle: LogicGate::read_typed(reader, &GateType::OR)?,
+                    over_nine: LogicGate::read_typed(reader, &GateType::AND)?,
+                    add: LogicGate::read_typed(reader, &GateType::OR)?,
+                    borrow: LogicGate::read_typed(reader, &GateType::NOT)?,
+                    select: Mux2::read_weights(reader)?,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(DecimalAdjuster { digits })
+    }
+}
+
+/// Gates are addressed as `digit0.carry`, `digit0.middle`, `digit0.over_nine`, `digit0.add`,
+/// `digit0.borrow`, and through the select multiplexer as `digit0.select.and_a`, ...
+impl FaultInjection for DecimalAdjuster {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        let (name, rest) = path.split_first()?;
+        let index: usize = name.strip_prefix("digit")?.parse().ok()?;
+        let digit = self.digits.get_mut(index)?;
+        let (gate, rest) = rest.split_first()?;
+        match *gate {
+            "carry" => digit.carry.gate_mut(rest),
+            "middle" => digit.middle.gate_mut(rest),
+            "over_nine" => digit.over_nine.gate_mut(rest),
+            "add" => digit.add.gate_mut(rest),
+            "borrow" => digit.borrow.gate_mut(rest),
+            "select" => digit.select.gate_mut(rest),
+            _ => None,
+        }
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for (index, digit) in self.digits.iter().enumerate() {
+            let gates = [
+                ("carry", &digit.carry),
+                ("middle", &digit.middle),
+                ("over_nine", &digit.over_nine),
+                ("add", &digit.add),
+                ("borrow", &digit.borrow),
+            ];
+            for (name, gate) in gates {
+                paths.extend(child_paths(&format!("digit{}.{}", index, name), gate));
+            }
+            paths.extend(child_paths(
+                &format!("digit{}.select", index),
+                &digit.select,
+            ));
+        }
+        paths
+    }
+}
@@ -0,0 +1,373 @@
+use std::io;
+
+use crate::faults::{FaultInjection, child_paths};
+use crate::gate_factory::{GateFactory, GateSource};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate, to_signal};
+use crate::netlist::{Netlist, NetlistBuilder, Wire};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+
+/// Widest carry-lookahead adder; the carry out of the top bit needs an OR of width + 1 inputs
+pub const MAX_LOOKAHEAD_WIDTH: usize = 8;
+
+/// Carry-lookahead result with the raw output of every gate
+#[derive(Debug, Clone, PartialEq)]
+pub struct CarryLookaheadDetail {
+    pub sums: Vec<GateOutput>,       // Sum bits (P XOR Cin), LSB first
+    pub carries: Vec<GateOutput>,    // Carry out of each bit, LSB first
+    pub propagate: Vec<GateOutput>,  // A XOR B per bit
+    pub generate: Vec<GateOutput>,   // A AND B per bit
+    pub terms: Vec<Vec<GateOutput>>, // Product terms of each carry equation
+}
+
+impl CarryLookaheadDetail {
+    /// Thresholded sum bits, LSB first
+    pub fn sum_bits(&self) -> Vec<bool> {
+        self.sums.iter().map(|output| output.bit).collect()
+    }
+
+    /// Carry out of the most significant bit
+    pub fn carry(&self) -> bool {
+        self.carries.last().is_some_and(|output| output.bit)
+    }
+
+    /// Smallest distance from the decision threshold across every gate
+    pub fn min_margin(&self) -> f64 {
+        self.sums
+            .iter()
+            .chain(&self.carries)
+            .chain(&self.propagate)
+            .chain(&self.generate)
+            .chain(self.terms.iter().flatten())
+            .map(|output| output.margin)
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// A carry-lookahead adder built from neural AND, OR and XOR gates
+///
+/// Every bit computes propagate `P = A XOR B` and generate `G = A AND B`. The
+/// carry out of bit i is then computed directly as a sum of products,
+/// `C(i+1) = G(i) + P(i)G(i-1) + ... + P(i)...P(0)C(0)`, using one wide AND
+/// gate per product term and one wide OR gate per carry, so no carry ripples
+/// from bit to bit. Gates grow with the width, so a single adder is at most
+/// `MAX_LOOKAHEAD_WIDTH` bits; the ALU chains narrow adders for wider words.
+#[derive(Debug)]
+pub struct CarryLookaheadAdder<G: Gate = LogicGate> {
+    propagate: Vec<G>,  // XOR per bit
+    generate: Vec<G>,   // AND per bit
+    terms: Vec<Vec<G>>, // Product term ANDs for the carry out of each bit
+    carries: Vec<G>,    // OR of the generate and product terms per bit
+    sums: Vec<G>,       // XOR of propagate and carry in per bit
+}
+
+impl CarryLookaheadAdder {
+    /// Create a carry-lookahead adder of the given width with trained neural gates (shared)
+    pub fn new(width: usize) -> Self {
+        Self::with_factory(&mut GateFactory::shared(), width)
+    }
+}
+
+impl<G: Gate> CarryLookaheadAdder<G> {
+    /// Create a carry-lookahead adder using gates from the given source
+    ///
+    /// The carry out of bit i needs AND and OR gates of up to i + 2 inputs.
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S, width: usize) -> Self {
+        assert!(
+            (1..=MAX_LOOKAHEAD_WIDTH).contains(&width),
+            "carry-lookahead width must be between 1 and {}",
+            MAX_LOOKAHEAD_WIDTH
+        );
+
+        let propagate = (0..width).map(|_| factory.gate(GateType::XOR)).collect();
+        let generate = (0..width).map(|_| factory.gate(GateType::AND)).collect();
+        // Term k of the carry out of bit i ANDs k + 1 propagates with one generate (or Cin)
+        let terms = (0..width)
+            .map(|bit| {
+                (1..=bit + 1)
+                    .map(|k| factory.gate(GateType::wide_and(k + 1)))
+                    .collect()
+            })
+            .collect();
+        let carries = (0..width)
+            .map(|bit| factory.gate(GateType::wide_or(bit + 2)))
+            .collect();
+        let sums = (0..width).map(|_| factory.gate(GateType::XOR)).collect();
+
+        CarryLookaheadAdder {
+            propagate,
+            generate,
+            terms,
+            carries,
+            sums,
+        }
+    }
+
+    /// Number of bits added
+    pub fn width(&self) -> usize {
+        self.sums.len()
+    }
+
+    /// Number of gates, including every product term
+    pub fn gate_count(&self) -> usize {
+        self.propagate.len()
+            + self.generate.len()
+            + self.terms.iter().map(Vec::len).sum::<usize>()
+            + self.carries.len()
+            + self.sums.len()
+    }
+
+    /// Gate-level description: inputs `a0..`, `b0..`, `carry_in`, outputs `sum0..`, `carry`
+    ///
+    /// Cells are named like the gate paths, so `term2_1` is the second product
+    /// term of the carry out of bit 2.
+    pub fn to_netlist(&self) -> Netlist {
+        let width = self.width();
+        let mut builder = NetlistBuilder::new(&format!("carry_lookahead{}", width));
+        let a = builder.inputs("a", width);
+        let b = builder.inputs("b", width);
+        let carry_in = builder.input("carry_in");
+
+        let level = |builder: &mut NetlistBuilder, name: &str, gates: &[G]| -> Vec<Wire> {
+            gates
+                .iter()
+                .enumerate()
+                .map(|(bit, gate)| {
+                    let name = format!("{}{}", name, bit);
+                    builder.gate(&name, gate.gate_type().clone(), &[a[bit], b[bit]])
+                })
+                .collect()
+        };
+        let propagate = level(&mut builder, "propagate", &self.propagate);
+        let generate = level(&mut builder, "generate", &self.generate);
+
+        let mut carries = Vec::new();
+        for (bit, (gates, carry)) in self.terms.iter().zip(&self.carries).enumerate() {
+            let mut carry_inputs = vec![generate[bit]];
+            for (index, gate) in gates.iter().enumerate() {
+                let k = index + 1;
+                let mut inputs: Vec<Wire> =
+                    (bit + 1 - k..=bit).rev().map(|p| propagate[p]).collect();
+                inputs.push(bit.checked_sub(k).map_or(carry_in, |g| generate[g]));
+                carry_inputs.push(builder.gate(
+                    &format!("term{}_{}", bit, index),
+                    gate.gate_type().clone(),
+                    &inputs,
+                ));
+            }
+            carries.push(builder.gate(
+                &format!("carry{}", bit),
+                carry.gate_type().clone(),
+                &carry_inputs,
+            ));
+        }
+
+        let sums: Vec<Wire> = self
+            .sums
+            .iter()
+            .enumerate()
+            .map(|(bit, gate)| {
+                let carry = if bit == 0 { carry_in } else { carries[bit - 1] };
+                builder.gate(
+                    &format!("sum{}", bit),
+                    gate.gate_type().clone(),
+                    &[propagate[bit], carry],
+                )
+            })
+            .collect();
+        builder.outputs("sum", &sums);
+        builder.output("carry", carries[width - 1]);
+        builder
+            .build()
+            .expect("carry-lookahead netlist is well formed")
+    }
+
+    /// Add two numbers given as bits (LSB first) and a carry in
+    pub fn compute(&self, a: &[bool], b: &[bool], carry_in: bool) -> (Vec<bool>, bool) {
+        let detail = self.compute_detailed(a, b, carry_in);
+        (detail.sum_bits(), detail.carry())
+    }
+
+    /// Add two numbers keeping the raw gate activations
+    pub fn compute_detailed(&self, a: &[bool], b: &[bool], carry_in: bool) -> CarryLookaheadDetail {
+        assert_eq!(a.len(), self.width(), "operand A has the wrong width");
+        assert_eq!(b.len(), self.width(), "operand B has the wrong width");
+
+        // Level 1: propagate and generate for every bit
+        let propagate: Vec<GateOutput> = self
+            .propagate
+            .iter()
+            .enumerate()
+            .map(|(bit, gate)| gate.compute_detailed(vec![to_signal(a[bit]), to_signal(b[bit])]))
+            .collect();
+        let generate: Vec<GateOutput> = self
+            .generate
+            .iter()
+            .enumerate()
+            .map(|(bit, gate)| gate.compute_detailed(vec![to_signal(a[bit]), to_signal(b[bit])]))
+            .collect();
+
+        // Level 2: product terms P(i)...P(i-k+1) G(i-k), with Cin below bit 0
+        let terms: Vec<Vec<GateOutput>> = self
+            .terms
+            .iter()
+            .enumerate()
+            .map(|(bit, gates)| {
+                gates
+                    .iter()
+                    .enumerate()
+                    .map(|(index, gate)| {
+                        let k = index + 1;
+                        let mut inputs: Vec<f64> = (bit + 1 - k..=bit)
+                            .rev()
+                            .map(|p| to_signal(propagate[p].bit))
+                            .collect();
+                        inputs.push(match bit.checked_sub(k) {
+                            Some(g) => to_signal(generate[g].bit),
+                            None => to_signal(carry_in),
+                        });
+                        gate.compute_detailed(inputs)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Level 3: carry out of each bit
+        let carries: Vec<GateOutput> = self
+            .carries
+            .iter()
+            .enumerate()
+            .map(|(bit, gate)| {
+                let mut inputs = vec![to_signal(generate[bit].bit)];
+                inputs.extend(terms[bit].iter().map(|term| to_signal(term.bit)));
+                gate.compute_detailed(inputs)
+            })
+            .collect();
+
+        // Level 4: sum bits
+        let sums = self
+            .sums
+            .iter()
+            .enumerate()
+            .map(|(bit, gate)| {
+                let carry = if bit == 0 {
+                    carry_in
+                } else {
+                    carries[bit - 1].bit
+                };
+                gate.compute_detailed(vec![to_signal(propagate[bit].bit), to_signal(carry)])
+            })
+            .collect();
+
+        CarryLookaheadDetail {
+            sums,
+            carries,
+            propagate,
+            generate,
+            terms,
+        }
+    }
+}
+
+impl Persist for CarryLookaheadAdder {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line(&format!("carry_lookahead {}", self.width()))?;
+        let gates = self
+            .propagate
+            .iter()
+            .chain(&self.generate)
+            .chain(self.terms.iter().flatten())
+            .chain(&self.carries)
+            .chain(&self.sums);
+        for gate in gates {
+            gate.write_weights(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        let fields = reader.tagged("carry_lookahead")?;
+        let width: usize = match fields.as_slice() {
+            [width] => width
+                .parse()
+                .ok()
+                .filter(|width| (1..=MAX_LOOKAHEAD_WIDTH).contains(width))
+                .ok_or_else(|| reader.error("invalid carry-lookahead width"))?,
+            _ => return Err(reader.error("expected a carry-lookahead width")),
+        };
+
+        let mut read_gate = |gate_type: GateType| LogicGate::read_typed(reader, &gate_type);
+        let propagate = (0..width)
+            .map(|_| read_gate(GateType::XOR))
+            .collect::<io::Result<_>>()?;
+        let generate = (0..width)
+            .map(|_| read_gate(GateType::AND))
+            .collect::<io::Result<_>>()?;
+        let terms = (0..width)
+            .map(|bit| {
+                (1..=bit + 1)
+                    .map(|k| read_gate(GateType::wide_and(k + 1)))
+                    .collect()
+            })
+            .collect::<io::Result<_>>()?;
+        let carries = (0..width)
+            .map(|bit| read_gate(GateType::wide_or(bit + 2)))
+            .collect::<io::Result<_>>()?;
+        let sums = (0..width)
+            .map(|_| read_gate(GateType::XOR))
+            .collect::<io::Result<_>>()?;
+
+        Ok(CarryLookaheadAdder {
+            propagate,
+            generate,
+            terms,
+            carries,
+            sums,
+        })
+    }
+}
+
+/// Gates are addressed as `propagate0`, `generate0`, `term2_1`, `carry3`, `sum0`, ...
+impl FaultInjection for CarryLookaheadAdder {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        let (name, rest) = path.split_first()?;
+        let gate = if let Some(index) = name.strip_prefix("propagate") {
+            self.propagate.get_mut(index.parse::<usize>().ok()?)?
+        } else if let Some(index) = name.strip_prefix("generate") {
+            self.generate.get_mut(index.parse::<usize>().ok()?)?
+        } else if let Some(indices) = name.strip_prefix("term") {
+            let (bit, term) = indices.split_once('_')?;
+            self.terms
+                .get_mut(bit.parse::<usize>().ok()?)?
+                .get_mut(term.parse::<usize>().ok()?)?
+        } else if let Some(index) = name.strip_prefix("carry") {
+            self.carries.get_mut(index.parse::<usize>().ok()?)?
+        } else if let Some(index) = name.strip_prefix("sum") {
+            self.sums.get_mut(index.parse::<usize>().ok()?)?
+        } else {
+            return None;
+        };
+        gate.gate_mut(rest)
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for (bit, gate) in self.propagate.iter().enumerate() {
+            paths.extend(child_paths(&format!("propagate{}", bit), gate));
+        }
+        for (bit, gate) in self.generate.iter().enumerate() {
+            paths.extend(child_paths(&format!("generate{}", bit), gate));
+        }
+        for (bit, gates) in self.terms.iter().enumerate() {
+            for (term, gate) in gates.iter().enumerate() {
+                paths.extend(child_paths(&format!("term{}_{}", bit, term), gate));
+            }
+        }
+        for (bit, gate) in self.carries.iter().enumerate() {
+            paths.extend(child_paths(&format!("carry{}", bit), gate));
+        }
+        for (bit, gate) in self.sums.iter().enumerate() {
+            paths.extend(child_paths(&format!("sum{}", bit), gate));
+        }
+        paths
+    }
+}
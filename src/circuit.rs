@@ -0,0 +1,264 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::faults::FaultInjection;
+use crate::gate_factory::{GateFactory, GateMode, GateSource};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate, to_signal};
+use crate::import::{self, ParseError};
+use crate::netlist::{CellKind, Netlist};
+use crate::training::{ConvergenceError, TrainingConfig};
+
+/// Extra training attempts for each gate type of an imported circuit
+const IMPORT_RETRIES: usize = 3;
+
+/// Error building a circuit from a BLIF or Verilog file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    Parse(ParseError),          // The file is not a supported netlist
+    Training(ConvergenceError), // A gate type did not learn its truth table
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportError::Parse(error) => write!(f, "{}", error),
+            ImportError::Training(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+/// Circuit outputs with the raw output of every gate
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitDetail {
+    pub outputs: Vec<bool>,     // One bit per output port
+    pub gates: Vec<GateOutput>, // Every gate in evaluation order, matching `Circuit::gate_paths`
+}
+
+impl CircuitDetail {
+    /// Smallest distance from the decision threshold across every gate
+    pub fn min_margin(&self) -> f64 {
+        self.gates
+            .iter()
+            .map(|output| output.margin)
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// One gate of a flattened netlist
+#[derive(Debug)]
+struct CircuitGate<G: Gate> {
+    path: String, // Dotted path through the instances, e.g. `adder0.half_adder1.xor`
+    gate: G,
+    inputs: Vec<usize>, // Flat wire of each input
+    output: usize,      // Flat wire driven
+}
+
+/// A netlist turned into gates and evaluated on boolean vectors
+///
+/// Instances are flattened, so every gate gets its own (possibly neural)
+/// gate from the factory, addressed by its dotted path through the
+/// instances just like the fault paths of the hand-wired components.
+#[derive(Debug)]
+pub struct Circuit<G: Gate = LogicGate> {
+    netlist: Netlist,
+    wires: usize,        // Number of flat wires
+    inputs: Vec<usize>,  // Flat wire of each input port
+    outputs: Vec<usize>, // Flat wire of each output port
+    constants: Vec<(usize, bool)>,
+    gates: Vec<CircuitGate<G>>, // In evaluation order
+}
+
+impl Circuit {
+    /// Build the netlist from trained neural gates (one network per gate type, shared)
+    pub fn new(netlist: Netlist) -> Self {
+        Self::with_factory(&mut GateFactory::shared(), netlist)
+    }
+
+    /// Build the netlist from trained neural gates, checking every gate type learned its
+    /// truth table
+    ///
+    /// Each gate type gets up to `IMPORT_RETRIES` more attempts from fresh weights.
+    pub fn try_new(netlist: Netlist) -> Result<Self, ConvergenceError> {
+        let config = TrainingConfig::default().with_retries(IMPORT_RETRIES);
+        let mut factory = GateFactory::with_config(GateMode::Shared, config);
+        for gate_type in gate_types(&netlist) {
+            factory.try_gate(gate_type)?;
+        }
+        Ok(Self::with_factory(&mut factory, netlist))
+    }
+
+    /// Build a combinational BLIF model from trained neural gates
+    pub fn from_blif(text: &str) -> Result<Self, ImportError> {
+        let netlist = import::parse_blif(text).map_err(ImportError::Parse)?;
+        Self::try_new(netlist).map_err(ImportError::Training)
+    }
+
+    /// Build the top module of a structural Verilog file from trained neural gates
+    pub fn from_verilog(text: &str) -> Result<Self, ImportError> {
+        let netlist = import::parse_verilog(text).map_err(ImportError::Parse)?;
+        Self::try_new(netlist).map_err(ImportError::Training)
+    }
+}
+
+impl<G: Gate> Circuit<G> {
+    /// Build the netlist from gates of the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S, netlist: Netlist) -> Self {
+        let mut circuit = Circuit {
+            netlist: netlist.clone(),
+            wires: 0,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            constants: Vec::new(),
+            gates: Vec::new(),
+        };
+        circuit.inputs = (0..netlist.inputs().len())
+            .map(|_| circuit.new_wire())
+            .collect();
+        let inputs = circuit.inputs.clone();
+        circuit.outputs = circuit.flatten(factory, &netlist, "", &inputs);
+        circuit
+    }
+
+    /// Netlist the circuit was built from
+    pub fn netlist(&self) -> &Netlist {
+        &self.netlist
+    }
+
+    /// Number of gates
+    pub fn gate_count(&self) -> usize {
+        self.gates.len()
+    }
+
+    /// Output bits for the given input bits, in port order
+    pub fn evaluate(&self, inputs: &[bool]) -> Vec<bool> {
+        self.evaluate_detailed(inputs).outputs
+    }
+
+    /// Evaluate keeping the raw activation of every gate
+    pub fn evaluate_detailed(&self, inputs: &[bool]) -> CircuitDetail {
+        assert_eq!(
+            inputs.len(),
+            self.inputs.len(),
+            "{} takes {} inputs",
+            self.netlist.name(),
+            self.inputs.len()
+        );
+
+        let mut values = vec![false; self.wires];
+        for (&wire, &value) in self.inputs.iter().zip(inputs) {
+            values[wire] = value;
+        }
+        for &(wire, value) in &self.constants {
+            values[wire] = value;
+        }
+        let gates = self
+            .gates
+            .iter()
+            .map(|gate| {
+                let signals = gate
+                    .inputs
+                    .iter()
+                    .map(|&wire| to_signal(values[wire]))
+                    .collect();
+                let output = gate.gate.compute_detailed(signals);
+                values[gate.output] = output.bit;
+                output
+            })
+            .collect();
+
+        CircuitDetail {
+            outputs: self.outputs.iter().map(|&wire| values[wire]).collect(),
+            gates,
+        }
+    }
+
+    fn new_wire(&mut self) -> usize {
+        self.wires += 1;
+        self.wires - 1
+    }
+
+    /// Add the gates of `netlist` with its input ports on the given flat wires;
+    /// returns the flat wires of its output ports
+    fn flatten<S: GateSource<Gate = G>>(
+        &mut self,
+        factory: &mut S,
+        netlist: &Netlist,
+        prefix: &str,
+        inputs: &[usize],
+    ) -> Vec<usize> {
+        // Cells are in evaluation order, so every wire is mapped before it is read
+        let mut flat = vec![usize::MAX; netlist.wire_names().len()];
+        for (wire, &input) in netlist.inputs().iter().zip(inputs) {
+            flat[wire.index()] = input;
+        }
+        for &(wire, value) in netlist.constants() {
+            flat[wire.index()] = self.new_wire();
+            self.constants.push((flat[wire.index()], value));
+        }
+
+        for cell in netlist.cells() {
+            let path = format!("{}{}", prefix, cell.name);
+            let cell_inputs: Vec<usize> =
+                cell.inputs.iter().map(|wire| flat[wire.index()]).collect();
+            match &cell.kind {
+                CellKind::Gate(gate_type) => {
+                    let output = self.new_wire();
+                    flat[cell.outputs[0].index()] = output;
+                    self.gates.push(CircuitGate {
+                        path,
+                        gate: factory.gate(gate_type.clone()),
+                        inputs: cell_inputs,
+                        output,
+                    });
+                }
+                CellKind::Instance(instance) => {
+                    let outputs =
+                        self.flatten(factory, instance, &format!("{}.", path), &cell_inputs);
+                    for (wire, output) in cell.outputs.iter().zip(outputs) {
+                        flat[wire.index()] = output;
+                    }
+                }
+            }
+        }
+
+        netlist
+            .outputs()
+            .iter()
+            .map(|(_, wire)| flat[wire.index()])
+            .collect()
+    }
+}
+
+/// Gates are addressed by their path through the instances, e.g. `half_adder1.xor`
+impl FaultInjection for Circuit {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        let path = path.join(".");
+        self.gates
+            .iter_mut()
+            .find(|gate| gate.path == path)
+            .map(|gate| &mut gate.gate)
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        self.gates.iter().map(|gate| gate.path.clone()).collect()
+    }
+}
+
+/// Every gate type used by the netlist and its instances, in first use order
+fn gate_types(netlist: &Netlist) -> Vec<GateType> {
+    let mut types = Vec::new();
+    for cell in netlist.cells() {
+        let used = match &cell.kind {
+            CellKind::Gate(gate_type) => vec![gate_type.clone()],
+            CellKind::Instance(instance) => gate_types(instance),
+        };
+        for gate_type in used {
+            if !types.contains(&gate_type) {
+                types.push(gate_type);
+            }
+        }
+    }
+    types
+}
@@ -1,36 +1,218 @@
-use crate::alu::{ALUOperation, ALU};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::alu::{ALU, ALUDetail, ALUOperation, ALUResult, ALUUnits, DEFAULT_WIDTH};
+use crate::decoder::Decoder;
+use crate::faults::{FaultInjection, child_paths};
+use crate::gate_factory::{GateSource, ReferenceGates};
+use crate::gates::{Gate, GateType, LogicGate};
 use crate::memory::Memory;
-use crate::registers::CPURegisters;
+use crate::netlist::{Netlist, NetlistBuilder};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+use crate::registers::{CPURegisters, StatusRegister};
+use crate::self_test::SelfTestReport;
 
 /// 4-bit CPU Instructions (simplified set)
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Instruction {
     NOP,       // 0x0: No operation
-    LOAD(u8),  // 0x1: Load memory[addr] into accumulator
+    LOAD(u8),  // 0x1: Load memory[addr] into accumulator (carry kept)
     STORE(u8), // 0x2: Store accumulator into memory[addr]
     ADD(u8),   // 0x3: Add memory[addr] to accumulator
     SUB(u8),   // 0x4: Subtract memory[addr] from accumulator
-    LDI(u8),   // 0x5: Load immediate value into accumulator
+    LDI(u8),   // 0x5: Load immediate value into accumulator (carry kept)
+    AND(u8),   // 0x6: Bitwise AND memory[addr] into accumulator
+    OR(u8),    // 0x7: Bitwise OR memory[addr] into accumulator
+    XOR(u8),   // 0x8: Bitwise XOR memory[addr] into accumulator
+    CMP(u8),   // 0x9: Compare accumulator with memory[addr], setting flags only
+    ADC(u8),   // 0xA: Add memory[addr] and the carry flag to accumulator
+    SBC(u8),   // 0xB: Subtract memory[addr] and the borrow (carry flag clear) from accumulator
+    MUL(u8),   // 0xC: Multiply accumulator by memory[addr], high nibble into the high register
+    DIV(u8),   // 0xD: Divide accumulator by memory[addr], remainder into the high register
+    SHL,       // 0xE 0x0: Shift accumulator left, MSB into carry
+    SHR,       // 0xE 0x1: Shift accumulator right, LSB into carry
+    ROL,       // 0xE 0x2: Rotate accumulator left
+    ROR,       // 0xE 0x3: Rotate accumulator right
+    RCL,       // 0xE 0x4: Rotate accumulator left through carry
+    RCR,       // 0xE 0x5: Rotate accumulator right through carry
+    MOD(u8),   // 0xE 0x6: Replace accumulator by its remainder modulo memory[addr]
+    DAA,       // 0xE 0x7: Decimal adjust the accumulator after a BCD addition or subtraction
+    INC,       // 0xE 0x8: Increment accumulator (carry kept)
+    DEC,       // 0xE 0x9: Decrement accumulator (carry kept)
     HALT,      // 0xF: Stop execution
 }
 
+impl Instruction {
+    /// Assembly mnemonic of the instruction
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::NOP => "NOP",
+            Instruction::LOAD(_) => "LOAD",
+            Instruction::STORE(_) => "STORE",
+            Instruction::ADD(_) => "ADD",
+            Instruction::SUB(_) => "SUB",
+            Instruction::LDI(_) => "LDI",
+            Instruction::AND(_) => "AND",
+            Instruction::OR(_) => "OR",
+            Instruction::XOR(_) => "XOR",
+            Instruction::CMP(_) => "CMP",
+            Instruction::ADC(_) => "ADC",
+            Instruction::SBC(_) => "SBC",
+            Instruction::MUL(_) => "MUL",
+            Instruction::DIV(_) => "DIV",
+            Instruction::SHL => "SHL",
+            Instruction::SHR => "SHR",
+            Instruction::ROL => "ROL",
+            Instruction::ROR => "ROR",
+            Instruction::RCL => "RCL",
+            Instruction::RCR => "RCR",
+            Instruction::MOD(_) => "MOD",
+            Instruction::DAA => "DAA",
+            Instruction::INC => "INC",
+            Instruction::DEC => "DEC",
+            Instruction::HALT => "HALT",
+        }
+    }
+}
+
+/// Error returned when a CPU cannot be built around an ALU
+#[derive(Debug, Clone, PartialEq)]
+pub enum CPUError {
+    ALUWidth(usize),        // The ALU is not 4 bits wide
+    MissingUnits(ALUUnits), // The ALU lacks a unit an instruction needs (the units it has)
+}
+
+impl fmt::Display for CPUError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CPUError::ALUWidth(width) => write!(
+                f,
+                "the CPU needs a {}-bit ALU but the ALU is {} bits wide",
+                DEFAULT_WIDTH, width
+            ),
+            CPUError::MissingUnits(units) => write!(
+                f,
+                "the CPU needs every ALU unit but the ALU only has {:?}",
+                units
+            ),
+        }
+    }
+}
+
+impl Error for CPUError {}
+
+/// How the program counter moves on to the next memory location
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramCounterMode {
+    Register, // `Register4Bit::increment`
+    Neural,   // The ALU's half adder incrementer, tracked as "PC" in the weakest margins
+}
+
 /// Simple 4-bit CPU with neural ALU
-pub struct CPU {
+#[derive(Debug)]
+pub struct CPU<G: Gate = LogicGate> {
     pub registers: CPURegisters,
     pub memory: Memory,
-    pub alu: ALU,
+    pub alu: ALU<G>,
     pub halted: bool,
+
+    /// How fetches advance the program counter
+    pub pc_mode: ProgramCounterMode,
+
+    /// Weakest gate margin seen per instruction mnemonic (only instructions that use the ALU),
+    /// and as "PC" for program counter updates in `Neural` mode
+    pub weakest_margins: BTreeMap<&'static str, f64>,
 }
 
 impl CPU {
     /// Create a new CPU instance
     pub fn new() -> Self {
+        Self::around(ALU::new())
+    }
+
+    /// Gate-level description of the combinational datapath of a CPU with reference gates
+    ///
+    /// See `to_netlist` for the ports.
+    pub fn netlist() -> Netlist {
+        CPU::with_factory(&mut ReferenceGates).to_netlist()
+    }
+}
+
+impl<G: Gate> CPU<G> {
+    /// Create a CPU whose ALU uses gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S) -> Self {
+        Self::around(ALU::with_factory(factory))
+    }
+
+    /// Create a CPU around an already built ALU, which must be 4 bits wide with every unit
+    pub fn with_alu(alu: ALU<G>) -> Result<Self, CPUError> {
+        if alu.width() != DEFAULT_WIDTH {
+            return Err(CPUError::ALUWidth(alu.width()));
+        }
+        if alu.units() != ALUUnits::ALL {
+            return Err(CPUError::MissingUnits(alu.units()));
+        }
+        Ok(Self::around(alu))
+    }
+
+    /// Create a CPU around an ALU known to be 4 bits wide
+    fn around(alu: ALU<G>) -> Self {
         CPU {
             registers: CPURegisters::new(),
             memory: Memory::new(),
-            alu: ALU::new(),
+            alu,
             halted: false,
+            pc_mode: ProgramCounterMode::Register,
+            weakest_margins: BTreeMap::new(),
+        }
+    }
+
+    /// Gate-level description of the combinational datapath
+    ///
+    /// The opcode `opcode0..` goes through a decoder (`decoder`), and a few
+    /// gates turn its lines into the ALU controls: `subtract` for SUB, CMP
+    /// and SBC, and the carry in (1 for SUB and CMP, the carry flag for ADC
+    /// and SBC). The accumulator `acc0..` and the memory operand `operand0..`
+    /// feed this CPU's ALU (`alu`, see `ALU::to_netlist`), whose outputs are
+    /// the outputs of the datapath; its other controls are tied to 0. The CPU
+    /// decodes in code, so the decoder is built for the netlist. Registers,
+    /// memory and the extended instructions are not included.
+    pub fn to_netlist(&self) -> Netlist {
+        let decoder = Decoder::with_factory(&mut ReferenceGates, DEFAULT_WIDTH).to_netlist();
+        let alu = self.alu.to_netlist();
+        let mut builder = NetlistBuilder::new("cpu_datapath");
+        let opcode = builder.inputs("opcode", DEFAULT_WIDTH);
+        let accumulator = builder.inputs("acc", DEFAULT_WIDTH);
+        let operand = builder.inputs("operand", DEFAULT_WIDTH);
+        let carry_flag = builder.input("carry_flag");
+        let enable = builder.constant(true);
+        let zero = builder.constant(false);
+
+        let mut decoder_inputs = opcode;
+        decoder_inputs.push(enable);
+        let lines = builder.instance("decoder", &decoder, &decoder_inputs);
+        let (sub, cmp, adc, sbc) = (lines[0x4], lines[0x9], lines[0xA], lines[0xB]);
+
+        let sub_or_cmp = builder.gate("sub_or_cmp", GateType::OR, &[sub, cmp]);
+        let subtract = builder.gate("subtract", GateType::OR, &[sub_or_cmp, sbc]);
+        let with_carry = builder.gate("with_carry", GateType::OR, &[adc, sbc]);
+        let carry_term = builder.gate("carry_term", GateType::AND, &[with_carry, carry_flag]);
+        let carry_in = builder.gate("carry_in", GateType::OR, &[sub_or_cmp, carry_term]);
+
+        // Operands, carry in and subtract come first; shift, count, divide and adjust follow
+        let mut alu_inputs = accumulator;
+        alu_inputs.extend(operand);
+        alu_inputs.extend([carry_in, subtract]);
+        alu_inputs.resize(alu.inputs().len(), zero);
+        let results = builder.instance("alu", &alu, &alu_inputs);
+        for (port, wire) in alu.output_names().into_iter().zip(results) {
+            builder.output(port, wire);
         }
+        builder
+            .build()
+            .expect("CPU datapath netlist is well formed")
     }
 
     /// Reset CPU to initial state
@@ -38,6 +220,108 @@ impl CPU {
         self.registers.reset();
         self.memory.clear();
         self.halted = false;
+        self.weakest_margins.clear();
+    }
+
+    /// Run the loaded program again from address 0, keeping memory, the accumulator and the flags
+    ///
+    /// This lets a short program run once per nibble of a wider number, with
+    /// the carry flag chaining the nibbles.
+    pub fn restart(&mut self) {
+        self.registers.program_counter.clear();
+        self.halted = false;
+    }
+
+    /// Smallest gate margin seen across every instruction executed so far
+    ///
+    /// A value close to 0.0 means some gate answered barely on the right side
+    /// of the threshold, so the program is running near a misclassification.
+    pub fn weakest_margin(&self) -> Option<f64> {
+        self.weakest_margins.values().copied().reduce(f64::min)
+    }
+
+    /// Check every ALU operation on every pair of nibbles before running anything
+    ///
+    /// Leaves the registers, memory and recorded margins untouched.
+    pub fn power_on_self_test(&self) -> SelfTestReport {
+        self.alu.self_test()
+    }
+
+    /// Run the ALU and keep track of the weakest margin for the instruction
+    fn compute(
+        &mut self,
+        instruction: Instruction,
+        a: u8,
+        b: u8,
+        operation: ALUOperation,
+    ) -> ALUDetail {
+        let detail = self.alu.compute_detailed(a.into(), b.into(), operation);
+        self.record_margin(instruction.mnemonic(), detail.min_margin());
+        detail
+    }
+
+    /// Keep the weakest margin seen under the given name
+    fn record_margin(&mut self, name: &'static str, margin: f64) {
+        self.weakest_margins
+            .entry(name)
+            .and_modify(|weakest| *weakest = weakest.min(margin))
+            .or_insert(margin);
+    }
+
+    /// Move the program counter on by one, through the neural incrementer in `Neural` mode
+    fn advance_program_counter(&mut self) {
+        match self.pc_mode {
+            ProgramCounterMode::Register => self.registers.program_counter.increment(),
+            ProgramCounterMode::Neural => {
+                let pc = self.registers.program_counter.read();
+                let detail = self
+                    .alu
+                    .compute_detailed(pc.into(), 0, ALUOperation::Increment);
+                self.record_margin("PC", detail.min_margin());
+                self.registers
+                    .program_counter
+                    .write(detail.result.result as u8);
+            }
+        }
+    }
+
+    /// Combine the accumulator with an operand in the ALU and store the result in the accumulator
+    fn accumulate(&mut self, instruction: Instruction, operand: u8, operation: ALUOperation) {
+        // Remember whether a following DAA corrects a difference or a sum
+        let subtract = matches!(
+            operation,
+            ALUOperation::Subtract | ALUOperation::SubtractWithBorrow(_)
+        );
+
+        let a = self.registers.accumulator.read();
+        let result = self.compute(instruction, a, operand, operation).result;
+        let value = result.result as u8; // 4-bit ALU
+        self.registers.accumulator.write(value);
+        self.set_status(&result, subtract);
+    }
+
+    /// Copy every flag of an ALU result into the status register
+    ///
+    /// Every instruction that writes the flags says whether it subtracted, so
+    /// a following DAA never sees a stale subtract flag.
+    fn set_status(&mut self, result: &ALUResult, subtract: bool) {
+        let status = &mut self.registers.status;
+        status.set(StatusRegister::SUBTRACT, subtract);
+        status.set(StatusRegister::ZERO, result.zero);
+        status.set(StatusRegister::CARRY, result.carry);
+        status.set(StatusRegister::NEGATIVE, result.negative);
+        status.set(StatusRegister::OVERFLOW, result.overflow);
+        status.set(StatusRegister::HALF_CARRY, result.half_carry);
+    }
+
+    /// Report a division by zero: the accumulator and high register are left
+    /// unchanged, carry and subtract are cleared and the overflow flag is set
+    fn divide_by_zero(&mut self) {
+        let value = self.registers.accumulator.read();
+        self.registers.update_flags(value, false);
+        self.registers.status.set(StatusRegister::OVERFLOW, true);
+        self.registers.status.set(StatusRegister::HALF_CARRY, false);
+        self.registers.status.set(StatusRegister::SUBTRACT, false);
     }
 
     /// Load a program into memory
@@ -50,7 +334,7 @@ impl CPU {
         let pc = self.registers.program_counter.read();
         let instruction = self.memory.read(pc);
         self.registers.instruction_register.write(instruction);
-        self.registers.program_counter.increment();
+        self.advance_program_counter();
         instruction
     }
 
@@ -83,6 +367,66 @@ impl CPU {
                 let operand = self.fetch();
                 Instruction::LDI(operand)
             }
+            0x6 => {
+                // AND needs operand from next memory location
+                let operand = self.fetch();
+                Instruction::AND(operand)
+            }
+            0x7 => {
+                // OR needs operand from next memory location
+                let operand = self.fetch();
+                Instruction::OR(operand)
+            }
+            0x8 => {
+                // XOR needs operand from next memory location
+                let operand = self.fetch();
+                Instruction::XOR(operand)
+            }
+            0x9 => {
+                // CMP needs operand from next memory location
+                let operand = self.fetch();
+                Instruction::CMP(operand)
+            }
+            0xA => {
+                // ADC needs operand from next memory location
+                let operand = self.fetch();
+                Instruction::ADC(operand)
+            }
+            0xB => {
+                // SBC needs operand from next memory location
+                let operand = self.fetch();
+                Instruction::SBC(operand)
+            }
+            0xC => {
+                // MUL needs operand from next memory location
+                let operand = self.fetch();
+                Instruction::MUL(operand)
+            }
+            0xD => {
+                // DIV needs operand from next memory location
+                let operand = self.fetch();
+                Instruction::DIV(operand)
+            }
+            0xE => {
+                // Extended instructions: the next memory location selects the operation
+                match self.fetch() {
+                    0x0 => Instruction::SHL,
+                    0x1 => Instruction::SHR,
+                    0x2 => Instruction::ROL,
+                    0x3 => Instruction::ROR,
+                    0x4 => Instruction::RCL,
+                    0x5 => Instruction::RCR,
+                    0x6 => {
+                        // MOD needs operand from the memory location after the selector
+                        let operand = self.fetch();
+                        Instruction::MOD(operand)
+                    }
+                    0x7 => Instruction::DAA,
+                    0x8 => Instruction::INC,
+                    0x9 => Instruction::DEC,
+                    _ => Instruction::NOP,
+                }
+            }
             0xF => Instruction::HALT,
             _ => Instruction::NOP,
         }
@@ -95,31 +439,128 @@ impl CPU {
                 // Do nothing
             }
             Instruction::LOAD(addr) => {
+                // Loads keep the carry flag so it can chain multi-nibble arithmetic
                 let value = self.memory.read(addr);
                 self.registers.accumulator.write(value);
-                self.registers.update_flags(value, false);
+                self.registers
+                    .update_flags(value, self.registers.status.carry());
             }
             Instruction::STORE(addr) => {
                 let value = self.registers.accumulator.read();
                 self.memory.write(addr, value);
             }
             Instruction::ADD(addr) => {
+                self.accumulate(instruction, self.memory.read(addr), ALUOperation::Add)
+            }
+            Instruction::SUB(addr) => {
+                self.accumulate(instruction, self.memory.read(addr), ALUOperation::Subtract)
+            }
+            Instruction::AND(addr) => {
+                self.accumulate(instruction, self.memory.read(addr), ALUOperation::And)
+            }
+            Instruction::OR(addr) => {
+                self.accumulate(instruction, self.memory.read(addr), ALUOperation::Or)
+            }
+            Instruction::XOR(addr) => {
+                self.accumulate(instruction, self.memory.read(addr), ALUOperation::Xor)
+            }
+            Instruction::ADC(addr) => {
+                let carry = self.registers.status.carry();
+                let operand = self.memory.read(addr);
+                self.accumulate(instruction, operand, ALUOperation::AddWithCarry(carry))
+            }
+            Instruction::SBC(addr) => {
+                let carry = self.registers.status.carry();
+                let operand = self.memory.read(addr);
+                self.accumulate(
+                    instruction,
+                    operand,
+                    ALUOperation::SubtractWithBorrow(carry),
+                )
+            }
+            Instruction::MUL(addr) => {
+                // 8-bit product: low nibble in the accumulator, high nibble in the high register
                 let a = self.registers.accumulator.read();
                 let b = self.memory.read(addr);
-                let result = self.alu.compute(a, b, ALUOperation::Add);
-                self.registers.accumulator.write(result.result);
-                self.registers.update_flags(result.result, result.carry);
+                let detail = self.compute(instruction, a, b, ALUOperation::Multiply);
+                self.registers.accumulator.write(detail.result.result as u8);
+                self.registers.high.write(detail.high_word() as u8);
+                self.set_status(&detail.result, false);
             }
-            Instruction::SUB(addr) => {
+            Instruction::DIV(addr) => {
+                // Quotient in the accumulator, remainder in the high register
                 let a = self.registers.accumulator.read();
                 let b = self.memory.read(addr);
-                let result = self.alu.compute(a, b, ALUOperation::Subtract);
-                self.registers.accumulator.write(result.result);
-                self.registers.update_flags(result.result, result.carry);
+                let detail = self.compute(instruction, a, b, ALUOperation::Divide);
+                if detail.divide_by_zero() {
+                    self.divide_by_zero();
+                } else {
+                    self.registers.accumulator.write(detail.result.result as u8);
+                    self.registers.high.write(detail.remainder() as u8);
+                    self.set_status(&detail.result, false);
+                }
+            }
+            Instruction::MOD(addr) => {
+                let a = self.registers.accumulator.read();
+                let b = self.memory.read(addr);
+                let detail = self.compute(instruction, a, b, ALUOperation::Modulo);
+                if detail.divide_by_zero() {
+                    self.divide_by_zero();
+                } else {
+                    self.registers.accumulator.write(detail.result.result as u8);
+                    self.set_status(&detail.result, false);
+                }
+            }
+            Instruction::CMP(addr) => {
+                // Subtract for the flags only; the accumulator keeps its value
+                let a = self.registers.accumulator.read();
+                let b = self.memory.read(addr);
+                let result = self
+                    .compute(instruction, a, b, ALUOperation::Compare)
+                    .result;
+                self.set_status(&result, true);
+            }
+            Instruction::DAA => {
+                // Correct the last BCD sum or difference, which stays a sum or difference
+                let status = self.registers.status;
+                let operation = ALUOperation::DecimalAdjust {
+                    carry: status.carry(),
+                    half_carry: status.half_carry(),
+                    subtract: status.subtract(),
+                };
+                let a = self.registers.accumulator.read();
+                let result = self.compute(instruction, a, 0, operation).result;
+                self.registers.accumulator.write(result.result as u8);
+                self.set_status(&result, status.subtract());
+            }
+            Instruction::INC | Instruction::DEC => {
+                // Counting keeps the carry flag so loops can run between multi-nibble arithmetic
+                let carry = self.registers.status.carry();
+                let operation = if instruction == Instruction::INC {
+                    ALUOperation::Increment
+                } else {
+                    ALUOperation::Decrement
+                };
+                self.accumulate(instruction, 0, operation);
+                self.registers.status.set(StatusRegister::CARRY, carry);
+            }
+            Instruction::SHL => self.accumulate(instruction, 0, ALUOperation::ShiftLeft),
+            Instruction::SHR => self.accumulate(instruction, 0, ALUOperation::ShiftRight),
+            Instruction::ROL => self.accumulate(instruction, 0, ALUOperation::RotateLeft),
+            Instruction::ROR => self.accumulate(instruction, 0, ALUOperation::RotateRight),
+            Instruction::RCL => {
+                let carry = self.registers.status.carry();
+                self.accumulate(instruction, 0, ALUOperation::RotateLeftThroughCarry(carry))
+            }
+            Instruction::RCR => {
+                let carry = self.registers.status.carry();
+                self.accumulate(instruction, 0, ALUOperation::RotateRightThroughCarry(carry))
             }
             Instruction::LDI(value) => {
+                // Like LOAD, an immediate load keeps the carry flag for the next ADC or SBC
                 self.registers.accumulator.write(value);
-                self.registers.update_flags(value, false);
+                self.registers
+                    .update_flags(value, self.registers.status.carry());
             }
             Instruction::HALT => {
                 self.halted = true;
@@ -146,8 +587,36 @@ impl CPU {
     }
 }
 
+/// Only the neural ALU is persisted; registers and memory start cleared on load
+impl Persist for CPU {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line("cpu")?;
+        self.alu.write_weights(writer)
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        reader.section("cpu")?;
+        let alu = ALU::read_weights(reader)?;
+        CPU::with_alu(alu).map_err(|error| reader.error(&error.to_string()))
+    }
+}
+
+/// Gates are addressed from the ALU down, e.g. `alu.adder2.half_adder1.xor`
+impl FaultInjection for CPU {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        match path.split_first()? {
+            (&"alu", rest) => self.alu.gate_mut(rest),
+            _ => None,
+        }
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        child_paths("alu", &self.alu)
+    }
+}
+
 impl Default for CPU {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
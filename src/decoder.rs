@@ -0,0 +1,313 @@
+use std::io;
+
+use crate::faults::{FaultInjection, child_paths};
+use crate::gate_factory::{GateFactory, GateSource, ReferenceGates};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate, to_signal};
+use crate::netlist::{Netlist, NetlistBuilder, Wire};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+
+/// Widest select input supported (each output is an AND of this many inputs plus the enable)
+pub const MAX_SELECT_BITS: usize = 6;
+
+/// Decoder result with the raw output of every gate
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecoderDetail {
+    pub outputs: Vec<GateOutput>,   // One line per select value
+    pub inverters: Vec<GateOutput>, // NOT of each select bit, LSB first
+}
+
+impl DecoderDetail {
+    /// Thresholded output lines, indexed by select value
+    pub fn output_bits(&self) -> Vec<bool> {
+        self.outputs.iter().map(|output| output.bit).collect()
+    }
+
+    /// Index of the single active output line, if exactly one is active
+    pub fn selected(&self) -> Option<usize> {
+        let mut active = self
+            .outputs
+            .iter()
+            .enumerate()
+            .filter(|(_, output)| output.bit);
+        match (active.next(), active.next()) {
+            (Some((index, _)), None) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Smallest distance from the decision threshold across every gate
+    pub fn min_margin(&self) -> f64 {
+        self.outputs
+            .iter()
+            .chain(&self.inverters)
+            .map(|output| output.margin)
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// An N-to-2^N decoder with an enable input, built from neural NOT and AND gates
+///
+/// Each select bit is inverted once, and output line i is a single wide AND of
+/// the enable with every select bit (or its inverse) matching the bits of i.
+/// With the enable low every output is low.
+#[derive(Debug)]
+pub struct Decoder<G: Gate = LogicGate> {
+    inverters: Vec<G>, // NOT per select bit
+    outputs: Vec<G>,   // AND of the enable and the select literals per output line
+}
+
+impl Decoder {
+    /// Create a decoder with the given number of select bits and trained neural gates (shared)
+    pub fn new(select_bits: usize) -> Self {
+        Self::with_factory(&mut GateFactory::shared(), select_bits)
+    }
+
+    /// Gate-level description: inputs `select0..` and `enable`, outputs `out0..`
+    pub fn netlist(select_bits: usize) -> Netlist {
+        Decoder::with_factory(&mut ReferenceGates, select_bits).to_netlist()
+    }
+}
+
+impl<G: Gate> Decoder<G> {
+    /// Create a decoder using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S, select_bits: usize) -> Self {
+        assert!(
+            (1..=MAX_SELECT_BITS).contains(&select_bits),
+            "decoder select width must be between 1 and {}",
+            MAX_SELECT_BITS
+        );
+
+        let inverters = (0..select_bits)
+            .map(|_| factory.gate(GateType::NOT))
+            .collect();
+        let outputs = (0..1 << select_bits)
+            .map(|_| factory.gate(GateType::wide_and(select_bits + 1)))
+            .collect();
+
+        Decoder { inverters, outputs }
+    }
+
+    /// Number of select bits
+    pub fn select_bits(&self) -> usize {
+        self.inverters.len()
+    }
+
+    /// Number of output lines (2^select bits)
+    pub fn output_count(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Gate-level description of this decoder: inputs `select0..`, `enable`, outputs `out0..`
+    pub fn to_netlist(&self) -> Netlist {
+        let select_bits = self.select_bits();
+        let mut builder = NetlistBuilder::new(&format!("decoder{}", select_bits));
+        let select = builder.inputs("select", select_bits);
+        let enable = builder.input("enable");
+        let inverted: Vec<Wire> = self
+            .inverters
+            .iter()
+            .enumerate()
+            .map(|(bit, gate)| {
+                builder.gate(
+                    &format!("not{}", bit),
+                    gate.gate_type().clone(),
+                    &[select[bit]],
+                )
+            })
+            .collect();
+
+        for (line, gate) in self.outputs.iter().enumerate() {
+            let mut inputs = vec![enable];
+            inputs.extend((0..select_bits).map(|bit| {
+                if line >> bit & 1 == 1 {
+                    select[bit]
+                } else {
+                    inverted[bit]
+                }
+            }));
+            let output = builder.gate(&format!("out{}", line), gate.gate_type().clone(), &inputs);
+            builder.output(&format!("out{}", line), output);
+        }
+        builder.build().expect("decoder netlist is well formed")
+    }
+
+    /// Decode select bits (LSB first) into one-hot output lines
+    pub fn compute(&self, select: &[bool], enable: bool) -> Vec<bool> {
+        self.compute_detailed(select, enable).output_bits()
+    }
+
+    /// Decode select bits keeping the raw gate activations
+    pub fn compute_detailed(&self, select: &[bool], enable: bool) -> DecoderDetail {
+        assert_eq!(
+            select.len(),
+            self.select_bits(),
+            "select has the wrong width"
+        );
+
+        let inverters: Vec<GateOutput> = self
+            .inverters
+            .iter()
+            .zip(select)
+            .map(|(gate, &bit)| gate.compute_detailed(vec![to_signal(bit)]))
+            .collect();
+
+        let outputs = self
+            .outputs
+            .iter()
+            .enumerate()
+            .map(|(line, gate)| {
+                let mut inputs = vec![to_signal(enable)];
+                inputs.extend(select.iter().enumerate().map(|(bit, &value)| {
+                    if line >> bit & 1 == 1 {
+                        to_signal(value)
+                    } else {
+                        to_signal(inverters[bit].bit)
+                    }
+                }));
+                gate.compute_detailed(inputs)
+            })
+            .collect();
+
+        DecoderDetail { outputs, inverters }
+    }
+
+    /// Decode a select value into one-hot output lines
+    pub fn decode(&self, select: usize) -> Vec<bool> {
+        self.compute(&to_bits(select, self.select_bits()), true)
+    }
+}
+
+impl Persist for Decoder {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line(&format!("decoder {}", self.select_bits()))?;
+        for gate in self.inverters.iter().chain(&self.outputs) {
+            gate.write_weights(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        let fields = reader.tagged("decoder")?;
+        let select_bits: usize = match fields.as_slice() {
+            [bits] => bits
+                .parse()
+                .ok()
+                .filter(|bits| (1..=MAX_SELECT_BITS).contains(bits))
+                .ok_or_else(|| reader.error("invalid decoder select width"))?,
+            _ => return Err(reader.error("expected a decoder select width")),
+        };
+
+        let mut read_gates = |count: usize, gate_type: GateType| {
+            (0..count)
+                .map(|_| LogicGate::read_typed(reader, &gate_type))
+                .collect::<io::Result<Vec<_>>>()
+        };
+        let inverters = read_gates(select_bits, GateType::NOT)?;
+        let outputs = read_gates(1 << select_bits, GateType::wide_and(select_bits + 1))?;
+        Ok(Decoder { inverters, outputs })
+    }
+}
+
+/// Gates are addressed as `not0`, `not1`, ..., `out0`, `out1`, ...
+impl FaultInjection for Decoder {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        let (name, rest) = path.split_first()?;
+        let gate = if let Some(index) = name.strip_prefix("not") {
+            self.inverters.get_mut(index.parse::<usize>().ok()?)?
+        } else if let Some(index) = name.strip_prefix("out") {
+            self.outputs.get_mut(index.parse::<usize>().ok()?)?
+        } else {
+            return None;
+        };
+        gate.gate_mut(rest)
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for (bit, gate) in self.inverters.iter().enumerate() {
+            paths.extend(child_paths(&format!("not{}", bit), gate));
+        }
+        for (line, gate) in self.outputs.iter().enumerate() {
+            paths.extend(child_paths(&format!("out{}", line), gate));
+        }
+        paths
+    }
+}
+
+/// A 1-to-2^N demultiplexer routing a data bit to the output line picked by the select bits
+///
+/// This is a decoder whose enable input carries the data: the selected line
+/// follows the data bit and every other line stays low.
+#[derive(Debug)]
+pub struct Demux<G: Gate = LogicGate> {
+    decoder: Decoder<G>,
+}
+
+impl Demux {
+    /// Create a demultiplexer with the given number of select bits and trained neural gates (shared)
+    pub fn new(select_bits: usize) -> Self {
+        Self::with_factory(&mut GateFactory::shared(), select_bits)
+    }
+}
+
+impl<G: Gate> Demux<G> {
+    /// Create a demultiplexer using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S, select_bits: usize) -> Self {
+        Demux {
+            decoder: Decoder::with_factory(factory, select_bits),
+        }
+    }
+
+    /// Number of select bits
+    pub fn select_bits(&self) -> usize {
+        self.decoder.select_bits()
+    }
+
+    /// Number of output lines (2^select bits)
+    pub fn output_count(&self) -> usize {
+        self.decoder.output_count()
+    }
+
+    /// Route the data bit to the line picked by the select bits (LSB first)
+    pub fn compute(&self, select: &[bool], data: bool) -> Vec<bool> {
+        self.decoder.compute(select, data)
+    }
+
+    /// Route the data bit keeping the raw gate activations
+    pub fn compute_detailed(&self, select: &[bool], data: bool) -> DecoderDetail {
+        self.decoder.compute_detailed(select, data)
+    }
+}
+
+impl Persist for Demux {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line("demux")?;
+        self.decoder.write_weights(writer)
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        reader.section("demux")?;
+        Ok(Demux {
+            decoder: Decoder::read_weights(reader)?,
+        })
+    }
+}
+
+/// Gates are addressed as `decoder.not0`, `decoder.out3`, ...
+impl FaultInjection for Demux {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        match path.split_first()? {
+            (&"decoder", rest) => self.decoder.gate_mut(rest),
+            _ => None,
+        }
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        child_paths("decoder", &self.decoder)
+    }
+}
+
+/// Bits of a value, LSB first
+fn to_bits(value: usize, width: usize) -> Vec<bool> {
+    (0..width).map(|bit| value >> bit & 1 == 1).collect()
+}
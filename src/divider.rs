@@ -0,0 +1,304 @@
+use std::io;
+
+use crate::alu_detail::ALUDetail;
+use crate::faults::{FaultInjection, child_paths};
+use crate::gate_factory::{GateFactory, GateSource};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate, to_signal};
+use crate::mux::{Mux2, Mux2Detail};
+use crate::netlist::{Netlist, NetlistBuilder, Wire};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+
+/// One step of a restoring division, settling one quotient bit
+#[derive(Debug, Clone, PartialEq)]
+pub struct DivisionStep {
+    pub subtraction: ALUDetail, // Trial subtraction of the divisor from the partial remainder
+    pub quotient: GateOutput,   // Quotient bit: no borrow OR the bit shifted out
+    pub remainder: Vec<Mux2Detail>, // Restore multiplexers, LSB first
+}
+
+/// Divider result with the raw output of every step
+#[derive(Debug, Clone, PartialEq)]
+pub struct DividerDetail {
+    pub steps: Vec<DivisionStep>,   // One step per quotient bit, MSB first
+    pub nonzero: Vec<GateOutput>,   // OR chain over the divisor bits, LSB first
+    pub divide_by_zero: GateOutput, // NOT of the chain (quotient all ones, remainder = dividend)
+}
+
+impl DividerDetail {
+    /// Thresholded quotient bits, LSB first
+    pub fn quotient_bits(&self) -> Vec<bool> {
+        self.steps
+            .iter()
+            .rev()
+            .map(|step| step.quotient.bit)
+            .collect()
+    }
+
+    /// Thresholded remainder bits left by the last step, LSB first
+    pub fn remainder_bits(&self) -> Vec<bool> {
+        self.steps.last().map_or(Vec::new(), |step| {
+            step.remainder.iter().map(|mux| mux.output.bit).collect()
+        })
+    }
+
+    /// Smallest distance from the decision threshold across every gate
+    pub fn min_margin(&self) -> f64 {
+        self.steps
+            .iter()
+            .map(|step| {
+                step.remainder
+                    .iter()
+                    .map(Mux2Detail::min_margin)
+                    .fold(step.subtraction.min_margin(), f64::min)
+                    .min(step.quotient.margin)
+            })
+            .chain(self.nonzero.iter().map(|output| output.margin))
+            .fold(self.divide_by_zero.margin, f64::min)
+    }
+}
+
+/// Restoring divider driving a subtractor with neural gates
+///
+/// Each step shifts the next dividend bit into the partial remainder and
+/// subtracts the divisor. An OR gate sets the quotient bit when there was no
+/// borrow, or when a one was shifted out of the top of the remainder (the
+/// remainder was then larger than any divisor). One 2:1 multiplexer per bit keeps
+/// the difference when the quotient bit is set and restores the partial
+/// remainder otherwise. A chain of OR gates over the divisor bits ending in a
+/// NOT (a NOR of the divisor) flags a division by zero.
+#[derive(Debug)]
+pub struct Divider<G: Gate = LogicGate> {
+    quotient: G,           // No borrow OR bit shifted out
+    restore: Vec<Mux2<G>>, // One multiplexer per remainder bit: partial remainder or difference
+    nonzero: Vec<G>,       // OR chain over the divisor bits
+    zero: G,               // NOT of the chain: the divisor is zero
+}
+
+impl Divider {
+    /// Create a divider of the given width with trained neural gates (shared)
+    pub fn new(width: usize) -> Self {
+        Self::with_factory(&mut GateFactory::shared(), width)
+    }
+}
+
+impl<G: Gate> Divider<G> {
+    /// Create a divider using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S, width: usize) -> Self {
+        assert!(width > 0, "divider needs at least one bit");
+
+        Divider {
+            quotient: factory.gate(GateType::OR),
+            restore: (0..width).map(|_| Mux2::with_factory(factory)).collect(),
+            nonzero: (1..width).map(|_| factory.gate(GateType::OR)).collect(),
+            zero: factory.gate(GateType::NOT),
+        }
+    }
+
+    /// Number of bits in the dividend, divisor, quotient and remainder
+    pub fn width(&self) -> usize {
+        self.restore.len()
+    }
+
+    /// Number of gates: the quotient OR, one restore multiplexer per bit and the divisor NOR
+    ///
+    /// The trial subtraction runs on the ALU's adder and is not counted here.
+    pub fn gate_count(&self) -> usize {
+        1 + self.restore.iter().map(Mux2::gate_count).sum::<usize>() + self.nonzero.len() + 1
+    }
+
+    /// Gate-level description of one division step
+    ///
+    /// Inputs are the shifted partial remainder `partial0..`, the result of the
+    /// trial subtraction `difference0..` with its carry `no_borrow`,
+    /// `shifted_out` and the divisor `divisor0..`; outputs are the `quotient`
+    /// bit, `remainder0..` and `divide_by_zero`. `compute_detailed` runs this
+    /// step once per quotient bit.
+    pub fn to_netlist(&self) -> Netlist {
+        let width = self.width();
+        let mut builder = NetlistBuilder::new("divider");
+        let partial = builder.inputs("partial", width);
+        let difference = builder.inputs("difference", width);
+        let no_borrow = builder.input("no_borrow");
+        let shifted_out = builder.input("shifted_out");
+        let divisor = builder.inputs("divisor", width);
+
+        let quotient = builder.gate(
+            "quotient",
+            self.quotient.gate_type().clone(),
+            &[no_borrow, shifted_out],
+        );
+        let remainder: Vec<Wire> = self
+            .restore
+            .iter()
+            .enumerate()
+            .map(|(bit, mux)| {
+                builder.instance(
+                    &format!("restore{}", bit),
+                    &mux.to_netlist(),
+                    &[quotient, partial[bit], difference[bit]],
+                )[0]
+            })
+            .collect();
+
+        let mut any = divisor[0];
+        for (index, gate) in self.nonzero.iter().enumerate() {
+            any = builder.gate(
+                &format!("nonzero{}", index),
+                gate.gate_type().clone(),
+                &[any, divisor[index + 1]],
+            );
+        }
+        let zero = builder.gate("zero", self.zero.gate_type().clone(), &[any]);
+
+        builder.output("quotient", quotient);
+        builder.outputs("remainder", &remainder);
+        builder.output("divide_by_zero", zero);
+        builder.build().expect("divider netlist is well formed")
+    }
+
+    /// Divide, running `subtract` for the trial subtraction of the divisor at every step
+    ///
+    /// `subtract` gets the partial remainder (LSB first) and returns the
+    /// subtraction of the divisor from it, with carry set when there was no borrow.
+    /// `divisor` (LSB first) only feeds the divide by zero gates.
+    pub fn compute_detailed<F>(
+        &self,
+        dividend: &[bool],
+        divisor: &[bool],
+        mut subtract: F,
+    ) -> DividerDetail
+    where
+        F: FnMut(&[bool]) -> ALUDetail,
+    {
+        assert_eq!(dividend.len(), self.width(), "dividend has the wrong width");
+        assert_eq!(divisor.len(), self.width(), "divisor has the wrong width");
+        let width = self.width();
+
+        // NOR of the divisor bits: OR them one after the other, then invert
+        let mut any = divisor[0];
+        let nonzero: Vec<GateOutput> = self
+            .nonzero
+            .iter()
+            .zip(&divisor[1..])
+            .map(|(gate, &bit)| {
+                let output = gate.compute_detailed(vec![to_signal(any), to_signal(bit)]);
+                any = output.bit;
+                output
+            })
+            .collect();
+        let divide_by_zero = self.zero.compute_detailed(vec![to_signal(any)]);
+
+        let mut remainder = vec![false; width];
+        let steps = (0..width)
+            .rev()
+            .map(|bit| {
+                // Shift the next dividend bit in; the top bit falls out
+                let shifted_out = remainder[width - 1];
+                remainder.rotate_right(1);
+                remainder[0] = dividend[bit];
+
+                let subtraction = subtract(&remainder);
+                let difference = subtraction.result.result;
+                let quotient = self.quotient.compute_detailed(vec![
+                    to_signal(subtraction.result.carry),
+                    to_signal(shifted_out),
+                ]);
+
+                let restored: Vec<Mux2Detail> = self
+                    .restore
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, mux)| {
+                        let kept = difference >> bit & 1 == 1;
+                        mux.compute_detailed(quotient.bit, remainder[bit], kept)
+                    })
+                    .collect();
+                remainder = restored.iter().map(|mux| mux.output.bit).collect();
+
+                DivisionStep {
+                    subtraction,
+                    quotient,
+                    remainder: restored,
+                }
+            })
+            .collect();
+
+        DividerDetail {
+            steps,
+            nonzero,
+            divide_by_zero,
+        }
+    }
+}
+
+impl Persist for Divider {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line(&format!("divider {}", self.width()))?;
+        self.quotient.write_weights(writer)?;
+        for mux in &self.restore {
+            mux.write_weights(writer)?;
+        }
+        for gate in self.nonzero.iter().chain([&self.zero]) {
+            gate.write_weights(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        let fields = reader.tagged("divider")?;
+        let width: usize = match fields.as_slice() {
+            [width] => width
+                .parse()
+                .ok()
+                .filter(|&width| width > 0)
+                .ok_or_else(|| reader.error("invalid divider width"))?,
+            _ => return Err(reader.error("expected a divider width")),
+        };
+
+        let quotient = LogicGate::read_typed(reader, &GateType::OR)?;
+        let restore = (0..width)
+            .map(|_| Mux2::read_weights(reader))
+            .collect::<io::Result<_>>()?;
+        let nonzero = (1..width)
+            .map(|_| LogicGate::read_typed(reader, &GateType::OR))
+            .collect::<io::Result<_>>()?;
+        let zero = LogicGate::read_typed(reader, &GateType::NOT)?;
+        Ok(Divider {
+            quotient,
+            restore,
+            nonzero,
+            zero,
+        })
+    }
+}
+
+/// Gates are addressed as `quotient`, by restore multiplexer (e.g. `restore0.and_b`),
+/// and as `nonzero0`, `nonzero1`, ... and `zero` for the divisor NOR
+impl FaultInjection for Divider {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        match path.split_first()? {
+            (&"quotient", rest) => self.quotient.gate_mut(rest),
+            (&"zero", rest) => self.zero.gate_mut(rest),
+            (name, rest) if name.starts_with("nonzero") => {
+                let index: usize = name.strip_prefix("nonzero")?.parse().ok()?;
+                self.nonzero.get_mut(index)?.gate_mut(rest)
+            }
+            (name, rest) => {
+                let index: usize = name.strip_prefix("restore")?.parse().ok()?;
+                self.restore.get_mut(index)?.gate_mut(rest)
+            }
+        }
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = child_paths("quotient", &self.quotient);
+        for (index, mux) in self.restore.iter().enumerate() {
+            paths.extend(child_paths(&format!("restore{}", index), mux));
+        }
+        for (index, gate) in self.nonzero.iter().enumerate() {
+            paths.extend(child_paths(&format!("nonzero{}", index), gate));
+        }
+        paths.extend(child_paths("zero", &self.zero));
+        paths
+    }
+}
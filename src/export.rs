@@ -0,0 +1,476 @@
+use std::fmt::Write;
+
+use crate::gates::{GateType, TruthTable};
+use crate::netlist::{CellKind, Netlist};
+
+/// Verilog keywords that cannot be used as plain identifiers
+pub(crate) const VERILOG_KEYWORDS: &str = "\
+    always and assign begin buf bufif0 bufif1 case casex casez cmos deassign default defparam \
+    disable edge else end endcase endfunction endmodule endprimitive endspecify endtable endtask \
+    event for force forever fork function highz0 highz1 if initial inout input integer join \
+    large macromodule medium module nand negedge nmos nor not notif0 notif1 or output parameter \
+    pmos posedge primitive pull0 pull1 pulldown pullup rcmos real realtime reg release repeat \
+    rnmos rpmos rtran rtranif0 rtranif1 scalared small specify specparam strong0 strong1 supply0 \
+    supply1 table task time tran tranif0 tranif1 tri tri0 tri1 triand trior trireg vectored wait \
+    wand weak0 weak1 while wire wor xnor xor";
+
+/// Graphviz DOT drawing of the netlist
+///
+/// Gates are boxes labelled with their name and type, and every instance is
+/// drawn as a cluster labelled with its name and circuit, nested like the
+/// hierarchy. Input ports are circles and output ports double circles.
+pub fn to_dot(netlist: &Netlist) -> String {
+    let mut dot = String::new();
+    let mut edges = Vec::new();
+    writeln!(dot, "digraph {} {{", quote(netlist.name())).unwrap();
+    writeln!(dot, "  rankdir=LR;").unwrap();
+    writeln!(dot, "  node [fontname=\"Helvetica\"];").unwrap();
+
+    let inputs: Vec<String> = netlist
+        .input_names()
+        .iter()
+        .map(|name| {
+            let node = format!("in:{}", name);
+            writeln!(
+                dot,
+                "  {} [label={}, shape=circle];",
+                quote(&node),
+                quote(name)
+            )
+            .unwrap();
+            node
+        })
+        .collect();
+    let outputs = dot_cells(&mut dot, &mut edges, netlist, "", &inputs, 1);
+    for ((name, _), source) in netlist.outputs().iter().zip(outputs) {
+        let node = format!("out:{}", name);
+        writeln!(
+            dot,
+            "  {} [label={}, shape=doublecircle];",
+            quote(&node),
+            quote(name)
+        )
+        .unwrap();
+        edges.push((source, node));
+    }
+
+    // Edges go last so that no node is pulled into the cluster of an edge that mentions it
+    for (from, to) in edges {
+        writeln!(dot, "  {} -> {};", quote(&from), quote(&to)).unwrap();
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Write the nodes of a netlist whose input ports come from the given nodes;
+/// returns the node driving each output port
+fn dot_cells(
+    dot: &mut String,
+    edges: &mut Vec<(String, String)>,
+    netlist: &Netlist,
+    prefix: &str,
+    inputs: &[String],
+    depth: usize,
+) -> Vec<String> {
+    let indent = "  ".repeat(depth);
+    let mut source = vec![String::new(); netlist.wire_names().len()];
+    for (wire, node) in netlist.inputs().iter().zip(inputs) {
+        source[wire.index()] = node.clone();
+    }
+    for &(wire, value) in netlist.constants() {
+        let node = format!("{}{}", prefix, netlist.wire_name(wire));
+        let label = if value { "1" } else { "0" };
+        writeln!(
+            dot,
+            "{}{} [label={}, shape=plaintext];",
+            indent,
+            quote(&node),
+            quote(label)
+        )
+        .unwrap();
+        source[wire.index()] = node;
+    }
+
+    for cell in netlist.cells() {
+        let path = format!("{}{}", prefix, cell.name);
+        let cell_inputs: Vec<String> = cell
+            .inputs
+            .iter()
+            .map(|wire| source[wire.index()].clone())
+            .collect();
+        match &cell.kind {
+            CellKind::Gate(gate_type) => {
+                let label = format!(
+                    "{}\\n{}",
+                    escape(&cell.name),
+                    escape(&gate_type.to_string())
+                );
+                writeln!(
+                    dot,
+                    "{}{} [label=\"{}\", shape=box];",
+                    indent,
+                    quote(&path),
+                    label
+                )
+                .unwrap();
+                edges.extend(cell_inputs.into_iter().map(|from| (from, path.clone())));
+                source[cell.outputs[0].index()] = path;
+            }
+            CellKind::Instance(instance) => {
+                writeln!(
+                    dot,
+                    "{}subgraph {} {{",
+                    indent,
+                    quote(&format!("cluster_{}", path))
+                )
+                .unwrap();
+                let label = format!("{} ({})", cell.name, instance.name());
+                writeln!(dot, "{}  label={};", indent, quote(&label)).unwrap();
+                let outputs = dot_cells(
+                    dot,
+                    edges,
+                    instance,
+                    &format!("{}.", path),
+                    &cell_inputs,
+                    depth + 1,
+                );
+                writeln!(dot, "{}}}", indent).unwrap();
+                for (wire, node) in cell.outputs.iter().zip(outputs) {
+                    source[wire.index()] = node;
+                }
+            }
+        }
+    }
+
+    netlist
+        .outputs()
+        .iter()
+        .map(|(_, wire)| source[wire.index()].clone())
+        .collect()
+}
+
+/// Structural Verilog with one module per circuit, sub-circuits first
+///
+/// Gates become Verilog primitives (`and`, `or`, `xor`, `not`, ...), with
+/// wide AND, OR and parity tables mapped to primitives with more inputs;
+/// any other truth table becomes a module of NOT, AND and OR primitives
+/// named after the table (`table3_00110101` for the 2:1 multiplexer).
+/// Names that are not plain Verilog identifiers, such as `half_adder1.sum`
+/// or `xor`, are written as escaped identifiers.
+pub fn to_verilog(netlist: &Netlist) -> String {
+    let mut modules = Modules::default();
+    modules.collect(netlist);
+
+    let mut verilog = String::new();
+    for table in &modules.tables {
+        verilog.push_str(&table_module(table));
+        verilog.push('\n');
+    }
+    for (netlist, name) in &modules.netlists {
+        verilog.push_str(&netlist_module(netlist, name, &modules));
+        verilog.push('\n');
+    }
+    verilog.pop();
+    verilog
+}
+
+/// Every module to write, in dependency order
+#[derive(Default)]
+struct Modules<'a> {
+    netlists: Vec<(&'a Netlist, String)>, // Circuits with their (unique) module names
+    tables: Vec<TruthTable>,              // Truth tables without a matching primitive
+}
+
+impl<'a> Modules<'a> {
+    fn collect(&mut self, netlist: &'a Netlist) {
+        if self.module_name(netlist).is_some() {
+            return;
+        }
+        for cell in netlist.cells() {
+            match &cell.kind {
+                CellKind::Instance(instance) => self.collect(instance),
+                CellKind::Gate(GateType::Custom(table)) => {
+                    if primitive(table).is_none() && !self.tables.contains(table) {
+                        self.tables.push(table.clone());
+                    }
+                }
+                CellKind::Gate(_) => {}
+            }
+        }
+
+        // Different circuits sharing a name get numbered module names
+        let taken = self
+            .netlists
+            .iter()
+            .filter(|(other, _)| other.name() == netlist.name())
+            .count();
+        let name = match taken {
+            0 => netlist.name().to_string(),
+            _ => format!("{}_{}", netlist.name(), taken + 1),
+        };
+        self.netlists.push((netlist, name));
+    }
+
+    fn module_name(&self, netlist: &Netlist) -> Option<&str> {
+        self.netlists
+            .iter()
+            .find(|(other, _)| *other == netlist)
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+/// Module for one circuit
+fn netlist_module(netlist: &Netlist, name: &str, modules: &Modules) -> String {
+    let wire = |wire| identifier(netlist.wire_name(wire));
+    let mut ports: Vec<String> = netlist
+        .input_names()
+        .iter()
+        .map(|name| identifier(name))
+        .collect();
+    ports.extend(netlist.output_names().iter().map(|name| identifier(name)));
+
+    let mut module = String::new();
+    writeln!(module, "module {}({});", identifier(name), ports.join(", ")).unwrap();
+    for input in netlist.inputs() {
+        writeln!(module, "  input {};", wire(*input)).unwrap();
+    }
+    for (port, _) in netlist.outputs() {
+        writeln!(module, "  output {};", identifier(port)).unwrap();
+    }
+
+    // Every wire that is neither an input nor an output port of the same name
+    for (index, wire_name) in netlist.wire_names().iter().enumerate() {
+        let is_port = netlist.inputs().iter().any(|input| input.index() == index)
+            || netlist
+                .outputs()
+                .iter()
+                .any(|(port, wire)| port == wire_name && wire.index() == index);
+        if is_port {
+            continue;
+        }
+        let kind = match netlist
+            .constants()
+            .iter()
+            .find(|(wire, _)| wire.index() == index)
+        {
+            Some((_, true)) => "supply1",
+            Some((_, false)) => "supply0",
+            None => "wire",
+        };
+        writeln!(module, "  {} {};", kind, identifier(wire_name)).unwrap();
+    }
+    module.push('\n');
+
+    for cell in netlist.cells() {
+        let inputs: Vec<String> = cell.inputs.iter().map(|&input| wire(input)).collect();
+        match &cell.kind {
+            CellKind::Gate(gate_type) => {
+                let output = wire(cell.outputs[0]);
+                match gate_primitive(gate_type) {
+                    Some(primitive) => {
+                        writeln!(
+                            module,
+                            "  {} ({}, {});",
+                            primitive,
+                            output,
+                            inputs.join(", ")
+                        )
+                    }
+                    None => {
+                        let GateType::Custom(table) = gate_type else {
+                            unreachable!("every fixed gate type is a primitive")
+                        };
+                        writeln!(
+                            module,
+                            "  {} {}({}, {});",
+                            table_name(table),
+                            identifier(&format!("{}_table", cell.name)),
+                            output,
+                            inputs.join(", ")
+                        )
+                    }
+                }
+                .unwrap();
+            }
+            CellKind::Instance(instance) => {
+                let connections: Vec<String> =
+                    instance
+                        .input_names()
+                        .iter()
+                        .zip(&inputs)
+                        .map(|(port, input)| format!(".{}({})", identifier(port), input))
+                        .chain(instance.output_names().iter().zip(&cell.outputs).map(
+                            |(port, &output)| format!(".{}({})", identifier(port), wire(output)),
+                        ))
+                        .collect();
+                let module_name = modules
+                    .module_name(instance)
+                    .expect("sub-circuits are collected first");
+                writeln!(
+                    module,
+                    "  {} {}({});",
+                    identifier(module_name),
+                    identifier(&cell.name),
+                    connections.join(", ")
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    // Output ports named differently from the wire driving them get a buffer
+    for (port, driver) in netlist.outputs() {
+        if port != netlist.wire_name(*driver) {
+            writeln!(module, "  buf ({}, {});", identifier(port), wire(*driver)).unwrap();
+        }
+    }
+    module.push_str("endmodule\n");
+    module
+}
+
+/// Module computing a truth table as a sum of products
+fn table_module(table: &TruthTable) -> String {
+    let arity = table.arity();
+    let inputs: Vec<String> = (0..arity).map(|input| format!("in{}", input)).collect();
+    let rows: Vec<usize> = (0..1 << arity)
+        .filter(|&row| table.outputs()[row])
+        .collect();
+
+    let mut module = String::new();
+    writeln!(
+        module,
+        "module {}(out, {});",
+        table_name(table),
+        inputs.join(", ")
+    )
+    .unwrap();
+    for input in &inputs {
+        writeln!(module, "  input {};", input).unwrap();
+    }
+    writeln!(module, "  output out;").unwrap();
+    for input in 0..arity {
+        writeln!(module, "  wire not{};", input).unwrap();
+    }
+    for row in &rows {
+        writeln!(module, "  wire row{};", row).unwrap();
+    }
+    module.push('\n');
+
+    for (input, name) in inputs.iter().enumerate() {
+        writeln!(module, "  not (not{}, {});", input, name).unwrap();
+    }
+    // The first input is the most significant bit of the row number
+    for row in &rows {
+        let literals: Vec<String> = (0..arity)
+            .map(|input| match row >> (arity - 1 - input) & 1 {
+                1 => format!("in{}", input),
+                _ => format!("not{}", input),
+            })
+            .collect();
+        let gate = if arity == 1 { "buf" } else { "and" };
+        writeln!(module, "  {} (row{}, {});", gate, row, literals.join(", ")).unwrap();
+    }
+    match rows.len() {
+        0 => writeln!(module, "  buf (out, 1'b0);"),
+        1 => writeln!(module, "  buf (out, row{});", rows[0]),
+        _ => {
+            let terms: Vec<String> = rows.iter().map(|row| format!("row{}", row)).collect();
+            writeln!(module, "  or (out, {});", terms.join(", "))
+        }
+    }
+    .unwrap();
+    module.push_str("endmodule\n");
+    module
+}
+
+/// Verilog primitive computing the gate type, if there is one
+fn gate_primitive(gate_type: &GateType) -> Option<&'static str> {
+    match gate_type {
+        GateType::AND => Some("and"),
+        GateType::OR => Some("or"),
+        GateType::XOR => Some("xor"),
+        GateType::NOT => Some("not"),
+        GateType::NAND => Some("nand"),
+        GateType::NOR => Some("nor"),
+        GateType::XNOR => Some("xnor"),
+        GateType::Custom(table) => primitive(table),
+    }
+}
+
+/// Verilog primitive computing a truth table of any width, if there is one
+fn primitive(table: &TruthTable) -> Option<&'static str> {
+    let arity = table.arity();
+    let outputs = table.outputs();
+    let inverted = |other: TruthTable| {
+        outputs
+            .iter()
+            .zip(other.outputs())
+            .all(|(output, other)| output != other)
+    };
+    if arity == 1 {
+        return match outputs {
+            [false, true] => Some("buf"),
+            [true, false] => Some("not"),
+            _ => None,
+        };
+    }
+    if *table == TruthTable::and(arity) {
+        Some("and")
+    } else if *table == TruthTable::or(arity) {
+        Some("or")
+    } else if *table == TruthTable::parity(arity) {
+        Some("xor")
+    } else if inverted(TruthTable::and(arity)) {
+        Some("nand")
+    } else if inverted(TruthTable::or(arity)) {
+        Some("nor")
+    } else if inverted(TruthTable::parity(arity)) {
+        Some("xnor")
+    } else {
+        None
+    }
+}
+
+/// Module name of a truth table, e.g. `table3_00110101`
+fn table_name(table: &TruthTable) -> String {
+    let outputs: String = table
+        .outputs()
+        .iter()
+        .map(|&bit| if bit { '1' } else { '0' })
+        .collect();
+    format!("table{}_{}", table.arity(), outputs)
+}
+
+/// Plain Verilog identifier, or an escaped one (`\name `) for anything else
+fn identifier(name: &str) -> String {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if plain
+        && !VERILOG_KEYWORDS
+            .split_whitespace()
+            .any(|keyword| keyword == name)
+    {
+        name.to_string()
+    } else {
+        // Escaped identifiers end at whitespace, so none may appear inside
+        let name: String = name
+            .chars()
+            .map(|c| if c.is_whitespace() { '_' } else { c })
+            .collect();
+        format!("\\{} ", name)
+    }
+}
+
+/// DOT string literal
+fn quote(text: &str) -> String {
+    format!("\"{}\"", escape(text))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,217 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use aspirina::matrix::Matrix;
+
+use crate::gates::LogicGate;
+use crate::network::Network;
+use crate::training::SeededRng;
+
+/// Hardware faults that can be injected into a neural gate
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    StuckAt0,                                // Output always 0.0
+    StuckAt1,                                // Output always 1.0
+    WeightNoise { std_dev: f64, seed: u64 }, // Gaussian noise added once to every weight
+    InputNoise { std_dev: f64, seed: u64 },  // Gaussian noise added to the inputs on every compute
+    DropNeuron(usize),                       // Hidden neuron no longer feeds the output
+}
+
+/// Error returned when a fault cannot be injected
+#[derive(Debug, Clone, PartialEq)]
+pub enum FaultError {
+    UnknownPath(String),                            // No gate at this path
+    InvalidNeuron { neuron: usize, hidden: usize }, // Hidden neuron index out of range
+}
+
+impl fmt::Display for FaultError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FaultError::UnknownPath(path) => write!(f, "no gate at path '{}'", path),
+            FaultError::InvalidNeuron { neuron, hidden } => write!(
+                f,
+                "gate has {} hidden neurons, cannot drop neuron {}",
+                hidden, neuron
+            ),
+        }
+    }
+}
+
+impl Error for FaultError {}
+
+/// A fault applied to a gate, with the state needed to evaluate it
+///
+/// The input noise generator state is atomic so faulty gates stay `Sync`.
+#[derive(Debug)]
+pub(crate) struct InjectedFault {
+    fault: Fault,
+    network: Option<Network>, // Faulted copy of the weights (weight noise, dropped neuron)
+    noise_state: AtomicU64,   // State of the input noise generator
+}
+
+impl Clone for InjectedFault {
+    fn clone(&self) -> Self {
+        InjectedFault {
+            fault: self.fault.clone(),
+            network: self.network.clone(),
+            noise_state: AtomicU64::new(self.noise_state.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl InjectedFault {
+    /// Prepare a fault for the given network, checking it applies
+    pub(crate) fn new(fault: Fault, network: &Network) -> Result<Self, FaultError> {
+        let (faulted, seed) = match fault {
+            Fault::WeightNoise { std_dev, seed } => {
+                (Some(with_weight_noise(network, std_dev, seed)), seed)
+            }
+            Fault::InputNoise { seed, .. } => (None, seed),
+            Fault::DropNeuron(neuron) => (Some(without_neuron(network, neuron)?), 0),
+            Fault::StuckAt0 | Fault::StuckAt1 => (None, 0),
+        };
+
+        Ok(InjectedFault {
+            fault,
+            network: faulted,
+            noise_state: AtomicU64::new(seed),
+        })
+    }
+
+    pub(crate) fn fault(&self) -> &Fault {
+        &self.fault
+    }
+
+    /// Output of the faulty gate; `predict` runs a network on (possibly noisy) inputs
+    pub(crate) fn compute<P>(&self, network: &Network, inputs: Vec<f64>, predict: P) -> f64
+    where
+        P: Fn(&Network, Vec<f64>) -> f64,
+    {
+        match self.fault {
+            Fault::StuckAt0 => 0.0,
+            Fault::StuckAt1 => 1.0,
+            Fault::InputNoise { std_dev, .. } => {
+                let mut rng = SeededRng::new(self.noise_state.load(Ordering::Relaxed));
+                let noisy = inputs
+                    .iter()
+                    .map(|input| input + rng.gaussian(std_dev))
+                    .collect();
+                self.noise_state.store(rng.state(), Ordering::Relaxed);
+                predict(network, noisy)
+            }
+            Fault::WeightNoise { .. } | Fault::DropNeuron(_) => {
+                predict(self.network.as_ref().unwrap(), inputs)
+            }
+        }
+    }
+}
+
+/// Copy of the network with Gaussian noise added to every weight
+fn with_weight_noise(network: &Network, std_dev: f64, seed: u64) -> Network {
+    let mut rng = SeededRng::new(seed);
+    let layers = network
+        .layers()
+        .iter()
+        .map(|layer| {
+            Matrix::new(
+                layer
+                    .data
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|weight| weight + rng.gaussian(std_dev))
+                            .collect()
+                    })
+                    .collect(),
+            )
+        })
+        .collect();
+    Network::from_matrices(layers)
+}
+
+/// Copy of the network where a first-layer hidden neuron is disconnected from the next layer
+fn without_neuron(network: &Network, neuron: usize) -> Result<Network, FaultError> {
+    let mut layers = network.layers().to_vec();
+    let hidden = layers.first().map_or(0, |layer| layer.data.len());
+    if layers.len() < 2 || neuron >= hidden {
+        return Err(FaultError::InvalidNeuron {
+            neuron,
+            hidden: if layers.len() < 2 { 0 } else { hidden },
+        });
+    }
+
+    for row in &mut layers[1].data {
+        row[neuron] = 0.0;
+    }
+    Ok(Network::from_matrices(layers))
+}
+
+/// Components whose gates can be addressed by a dotted path for fault injection
+///
+/// Paths name each level of the hierarchy, e.g. `alu.adder2.half_adder1.xor`
+/// on a CPU, or `half_adder1.xor` on a full adder.
+pub trait FaultInjection {
+    /// Find the gate at the given path segments
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate>;
+
+    /// Paths of every gate in the component
+    fn gate_paths(&self) -> Vec<String>;
+
+    /// Inject a fault into the gate at `path`, replacing any fault already there
+    fn inject_fault(&mut self, path: &str, fault: Fault) -> Result<(), FaultError> {
+        let gate = self
+            .gate_mut(&split_path(path))
+            .ok_or_else(|| FaultError::UnknownPath(path.to_string()))?;
+        gate.set_fault(fault)
+    }
+
+    /// Remove the fault from the gate at `path`
+    fn remove_fault(&mut self, path: &str) -> Result<(), FaultError> {
+        let gate = self
+            .gate_mut(&split_path(path))
+            .ok_or_else(|| FaultError::UnknownPath(path.to_string()))?;
+        gate.clear_fault();
+        Ok(())
+    }
+
+    /// Remove every fault in the component
+    fn clear_faults(&mut self) {
+        for path in self.gate_paths() {
+            if let Some(gate) = self.gate_mut(&split_path(&path)) {
+                gate.clear_fault();
+            }
+        }
+    }
+}
+
+impl FaultInjection for LogicGate {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        path.is_empty().then_some(self)
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        vec![String::new()]
+    }
+}
+
+/// Prefix child paths with the name of the child
+pub fn child_paths<T: FaultInjection>(name: &str, child: &T) -> Vec<String> {
+    child
+        .gate_paths()
+        .into_iter()
+        .map(|path| {
+            if path.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}.{}", name, path)
+            }
+        })
+        .collect()
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
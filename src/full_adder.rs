@@ -1,5 +1,11 @@
-use crate::gates::{GateType, LogicGate};
-use crate::half_adder::HalfAdder;
+use std::io;
+
+use crate::faults::{FaultInjection, child_paths};
+use crate::gate_factory::{GateFactory, GateSource, ReferenceGates};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate};
+use crate::half_adder::{HalfAdder, HalfAdderDetail};
+use crate::netlist::{Netlist, NetlistBuilder};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
 
 /// Result of full adder computation
 #[derive(Debug, PartialEq)]
@@ -8,21 +14,60 @@ pub struct FullAdderResult {
     pub carry: bool,
 }
 
+/// Full adder result with the raw output of every gate
+#[derive(Debug, Clone, PartialEq)]
+pub struct FullAdderDetail {
+    pub sum: GateOutput,              // Sum output (XOR of the second half adder)
+    pub carry: GateOutput,            // Carry output (OR gate)
+    pub half_adder1: HalfAdderDetail, // A + B
+    pub half_adder2: HalfAdderDetail, // (A XOR B) + Cin
+}
+
+impl FullAdderDetail {
+    /// Thresholded result
+    pub fn result(&self) -> FullAdderResult {
+        FullAdderResult {
+            sum: self.sum.bit,
+            carry: self.carry.bit,
+        }
+    }
+
+    /// Smallest distance from the decision threshold across all five gates
+    pub fn min_margin(&self) -> f64 {
+        self.half_adder1
+            .min_margin()
+            .min(self.half_adder2.min_margin())
+            .min(self.carry.margin)
+    }
+}
+
 /// A full adder built from two half adders and an OR gate
 /// Full Adder = HalfAdder1(A, B) + HalfAdder2(Sum1, Cin) + OR(Carry1, Carry2)
 #[derive(Debug)]
-pub struct FullAdder {
-    half_adder1: HalfAdder, // A + B
-    half_adder2: HalfAdder, // (A XOR B) + Cin
-    or_gate: LogicGate,     // Carry1 OR Carry2
+pub struct FullAdder<G: Gate = LogicGate> {
+    half_adder1: HalfAdder<G>, // A + B
+    half_adder2: HalfAdder<G>, // (A XOR B) + Cin
+    or_gate: G,                // Carry1 OR Carry2
 }
 
 impl FullAdder {
-    /// Create a new full adder using two half adders and an OR gate
+    /// Create a new full adder using two half adders and an OR gate (gates trained once, shared)
     pub fn new() -> Self {
-        let half_adder1 = HalfAdder::new();
-        let half_adder2 = HalfAdder::new();
-        let or_gate = LogicGate::new(GateType::OR);
+        Self::with_factory(&mut GateFactory::shared())
+    }
+
+    /// Gate-level description: inputs `a`, `b`, `carry_in`, outputs `sum`, `carry`
+    pub fn netlist() -> Netlist {
+        FullAdder::with_factory(&mut ReferenceGates).to_netlist()
+    }
+}
+
+impl<G: Gate> FullAdder<G> {
+    /// Create a full adder using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S) -> Self {
+        let half_adder1 = HalfAdder::with_factory(factory);
+        let half_adder2 = HalfAdder::with_factory(factory);
+        let or_gate = factory.gate(GateType::OR);
 
         FullAdder {
             half_adder1,
@@ -31,28 +76,101 @@ impl FullAdder {
         }
     }
 
+    /// Number of gates: two half adders and the carry OR
+    pub fn gate_count(&self) -> usize {
+        self.half_adder1.gate_count() + self.half_adder2.gate_count() + 1
+    }
+
+    /// Gate-level description of this full adder, one instance per half adder
+    pub fn to_netlist(&self) -> Netlist {
+        let mut builder = NetlistBuilder::new("full_adder");
+        let a = builder.input("a");
+        let b = builder.input("b");
+        let carry_in = builder.input("carry_in");
+
+        let first = builder.instance("half_adder1", &self.half_adder1.to_netlist(), &[a, b]);
+        let second = builder.instance(
+            "half_adder2",
+            &self.half_adder2.to_netlist(),
+            &[first[0], carry_in],
+        );
+        let carry = builder.gate(
+            "or",
+            self.or_gate.gate_type().clone(),
+            &[first[1], second[1]],
+        );
+
+        builder.output("sum", second[0]);
+        builder.output("carry", carry);
+        builder.build().expect("full adder netlist is well formed")
+    }
+
     /// Compute full adder output for three binary inputs (A, B, Carry_in)
     pub fn compute(&self, a: bool, b: bool, carry_in: bool) -> FullAdderResult {
+        self.compute_detailed(a, b, carry_in).result()
+    }
+
+    /// Compute full adder output keeping the raw gate activations
+    pub fn compute_detailed(&self, a: bool, b: bool, carry_in: bool) -> FullAdderDetail {
         // First half adder: A + B
-        let result1 = self.half_adder1.compute(a, b);
+        let result1 = self.half_adder1.compute_detailed(a, b);
 
         // Second half adder: (A XOR B) + Cin
-        let result2 = self.half_adder2.compute(result1.sum, carry_in);
+        let result2 = self.half_adder2.compute_detailed(result1.sum.bit, carry_in);
 
         // Final carry: Carry1 OR Carry2
-        let carry1_f = if result1.carry { 1.0 } else { 0.0 };
-        let carry2_f = if result2.carry { 1.0 } else { 0.0 };
-        let final_carry_output = self.or_gate.compute(vec![carry1_f, carry2_f]);
+        let carry1_f = if result1.carry.bit { 1.0 } else { 0.0 };
+        let carry2_f = if result2.carry.bit { 1.0 } else { 0.0 };
+        let final_carry_output = self.or_gate.compute_detailed(vec![carry1_f, carry2_f]);
 
-        FullAdderResult {
+        FullAdderDetail {
             sum: result2.sum,
-            carry: final_carry_output > 0.5,
+            carry: final_carry_output,
+            half_adder1: result1,
+            half_adder2: result2,
+        }
+    }
+}
+
+impl Persist for FullAdder {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line("full_adder")?;
+        self.half_adder1.write_weights(writer)?;
+        self.half_adder2.write_weights(writer)?;
+        self.or_gate.write_weights(writer)
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        reader.section("full_adder")?;
+        Ok(FullAdder {
+            half_adder1: HalfAdder::read_weights(reader)?,
+            half_adder2: HalfAdder::read_weights(reader)?,
+            or_gate: LogicGate::read_typed(reader, &GateType::OR)?,
+        })
+    }
+}
+
+/// Gates are addressed as `half_adder1.xor`, `half_adder2.and`, `or`, ...
+impl FaultInjection for FullAdder {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        match path.split_first()? {
+            (&"half_adder1", rest) => self.half_adder1.gate_mut(rest),
+            (&"half_adder2", rest) => self.half_adder2.gate_mut(rest),
+            (&"or", rest) => self.or_gate.gate_mut(rest),
+            _ => None,
         }
     }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = child_paths("half_adder1", &self.half_adder1);
+        paths.extend(child_paths("half_adder2", &self.half_adder2));
+        paths.extend(child_paths("or", &self.or_gate));
+        paths
+    }
 }
 
 impl Default for FullAdder {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use crate::gates::{Gate, GateType, LogicGate, ReferenceGate};
+use crate::training::{ConvergenceError, Initialization, TrainingConfig, TrainingReport};
+
+/// Source of gates for building circuits on a given backend
+pub trait GateSource {
+    /// Gate backend produced
+    type Gate: Gate;
+
+    /// Get a gate of the given type
+    fn gate(&mut self, gate_type: GateType) -> Self::Gate;
+}
+
+/// How a `GateFactory` hands out gates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateMode {
+    Shared,      // Train one canonical network per gate type and hand out clones
+    Independent, // Train a fresh network for every gate requested
+}
+
+/// Builds the trained gates used by adders, the ALU and the CPU
+///
+/// In shared mode each `GateType` is trained once and every component gets a
+/// clone of that canonical gate, so building an ALU trains 4 networks instead of 24.
+#[derive(Debug, Clone)]
+pub struct GateFactory {
+    mode: GateMode,
+    config: TrainingConfig,
+    canonical: HashMap<GateType, LogicGate>,
+    reports: Vec<(GateType, TrainingReport)>, // One report per network trained
+}
+
+impl GateFactory {
+    /// Create a factory with the given mode and default training
+    pub fn new(mode: GateMode) -> Self {
+        Self::with_config(mode, TrainingConfig::default())
+    }
+
+    /// Create a factory that trains its gates with the given settings
+    ///
+    /// With random initialization, every network trained gets its own seed
+    /// (`seed`, `seed + 1`, ...) so independent gates differ but stay reproducible.
+    pub fn with_config(mode: GateMode, config: TrainingConfig) -> Self {
+        GateFactory {
+            mode,
+            config,
+            canonical: HashMap::new(),
+            reports: Vec::new(),
+        }
+    }
+
+    /// Create a factory that trains each gate type once
+    pub fn shared() -> Self {
+        Self::new(GateMode::Shared)
+    }
+
+    /// Create a factory that trains every gate independently
+    pub fn independent() -> Self {
+        Self::new(GateMode::Independent)
+    }
+
+    /// Get the factory mode
+    pub fn mode(&self) -> GateMode {
+        self.mode
+    }
+
+    /// Number of networks this factory has trained
+    pub fn trained_count(&self) -> usize {
+        self.reports.len()
+    }
+
+    /// Training report of every network trained, in training order
+    pub fn reports(&self) -> &[(GateType, TrainingReport)] {
+        &self.reports
+    }
+
+    /// Get a trained gate of the given type
+    pub fn gate(&mut self, gate_type: GateType) -> LogicGate {
+        match self.mode {
+            GateMode::Shared => {
+                if let Some(gate) = self.canonical.get(&gate_type) {
+                    return gate.clone();
+                }
+                let gate = self.train(gate_type.clone());
+                self.canonical.insert(gate_type, gate.clone());
+                gate
+            }
+            GateMode::Independent => self.train(gate_type),
+        }
+    }
+
+    /// Get a trained gate of the given type, failing if its truth table is wrong
+    ///
+    /// Training is retried as set by `TrainingConfig::with_retries`. In shared
+    /// mode a canonical gate that passes is reused and one that fails is retrained.
+    pub fn try_gate(&mut self, gate_type: GateType) -> Result<LogicGate, ConvergenceError> {
+        if self.mode == GateMode::Shared
+            && let Some(gate) = self.canonical.get(&gate_type)
+            && gate.failed_rows().is_empty()
+        {
+            return Ok(gate.clone());
+        }
+
+        let config = self.next_config();
+        let (gate, report) = match LogicGate::try_with_config(gate_type.clone(), &config) {
+            Ok(trained) => trained,
+            Err(error) => {
+                self.reports.push((gate_type, error.report.clone()));
+                return Err(error);
+            }
+        };
+        self.reports.push((gate_type.clone(), report));
+        if self.mode == GateMode::Shared {
+            self.canonical.insert(gate_type, gate.clone());
+        }
+        Ok(gate)
+    }
+
+    fn train(&mut self, gate_type: GateType) -> LogicGate {
+        let config = self.next_config();
+        let (gate, report) = LogicGate::with_config(gate_type.clone(), &config);
+        self.reports.push((gate_type, report));
+        gate
+    }
+
+    /// Training settings for the next network, with its own seed when random
+    fn next_config(&self) -> TrainingConfig {
+        let mut config = self.config.clone();
+        if let Initialization::Random { seed } = config.initialization {
+            config.initialization = Initialization::Random {
+                seed: seed.wrapping_add(self.reports.len() as u64),
+            };
+        }
+        config
+    }
+}
+
+impl GateSource for GateFactory {
+    type Gate = LogicGate;
+
+    fn gate(&mut self, gate_type: GateType) -> LogicGate {
+        GateFactory::gate(self, gate_type)
+    }
+}
+
+impl Default for GateFactory {
+    fn default() -> Self {
+        Self::shared()
+    }
+}
+
+/// Source of exact boolean gates, for running circuits without neural networks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReferenceGates;
+
+impl GateSource for ReferenceGates {
+    type Gate = ReferenceGate;
+
+    fn gate(&mut self, gate_type: GateType) -> ReferenceGate {
+        ReferenceGate::new(gate_type)
+    }
+}
@@ -1,97 +1,657 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
 use aspirina::layer::Layer;
 use aspirina::matrix::Matrix;
-use aspirina::neural_network::NeuralNetwork;
+
+use crate::faults::{Fault, FaultError, InjectedFault};
+use crate::network::Network;
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+use crate::training::{
+    ConvergenceError, DEFAULT_SEED, FailedRow, Initialization, TrainingConfig, TrainingReport,
+    random_layers,
+};
+
+/// Boolean function of any number of inputs, stored as one output per input combination
+///
+/// Rows are in binary counting order with the first input as the most
+/// significant bit, so row 0b101 of a 3-input table is inputs `[1, 0, 1]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TruthTable {
+    arity: usize,
+    outputs: Vec<bool>, // 2^arity outputs
+}
+
+impl TruthTable {
+    /// Create a truth table from its outputs in row order
+    pub fn new(arity: usize, outputs: Vec<bool>) -> Result<Self, TruthTableError> {
+        if arity == 0 {
+            return Err(TruthTableError::NoInputs);
+        }
+        if arity >= usize::BITS as usize {
+            return Err(TruthTableError::TooManyInputs(arity));
+        }
+        if outputs.len() != 1 << arity {
+            return Err(TruthTableError::WrongOutputCount {
+                arity,
+                found: outputs.len(),
+            });
+        }
+        Ok(TruthTable { arity, outputs })
+    }
+
+    /// Create a truth table by evaluating a boolean function on every row
+    ///
+    /// Panics if `arity` is 0 or too large for the rows to be counted.
+    pub fn from_fn<F: Fn(&[bool]) -> bool>(arity: usize, function: F) -> Self {
+        assert!(
+            (1..usize::BITS as usize).contains(&arity),
+            "truth table arity {} is out of range",
+            arity
+        );
+        let outputs = (0..1usize << arity)
+            .map(|row| function(&row_inputs(arity, row)))
+            .collect();
+        TruthTable { arity, outputs }
+    }
+
+    /// N-input AND
+    pub fn and(arity: usize) -> Self {
+        Self::from_fn(arity, |inputs| inputs.iter().all(|&bit| bit))
+    }
+
+    /// N-input OR
+    pub fn or(arity: usize) -> Self {
+        Self::from_fn(arity, |inputs| inputs.iter().any(|&bit| bit))
+    }
+
+    /// N-input XOR: true when an odd number of inputs are set
+    pub fn parity(arity: usize) -> Self {
+        Self::from_fn(arity, |inputs| {
+            inputs.iter().filter(|&&bit| bit).count() % 2 == 1
+        })
+    }
+
+    /// True when more than half of the inputs are set
+    pub fn majority(arity: usize) -> Self {
+        Self::from_fn(arity, |inputs| {
+            inputs.iter().filter(|&&bit| bit).count() * 2 > arity
+        })
+    }
+
+    /// 2:1 multiplexer with inputs (select, a, b): a when select is 0, b when select is 1
+    pub fn mux2() -> Self {
+        Self::from_fn(3, |inputs| if inputs[0] { inputs[2] } else { inputs[1] })
+    }
+
+    /// Number of inputs
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    /// Output for the given inputs, one per input of the table
+    pub fn output(&self, inputs: &[bool]) -> Result<bool, TruthTableError> {
+        if inputs.len() != self.arity {
+            return Err(TruthTableError::WrongInputCount {
+                arity: self.arity,
+                found: inputs.len(),
+            });
+        }
+        let row = inputs
+            .iter()
+            .fold(0usize, |row, &bit| (row << 1) | bit as usize);
+        Ok(self.outputs[row])
+    }
+
+    /// Outputs in row order
+    pub fn outputs(&self) -> &[bool] {
+        &self.outputs
+    }
+}
+
+/// Error building or reading a truth table
+#[derive(Debug, Clone, PartialEq)]
+pub enum TruthTableError {
+    NoInputs,                                        // A table needs at least one input
+    TooManyInputs(usize),                            // Too many rows to count
+    WrongOutputCount { arity: usize, found: usize }, // Outputs are not one per row
+    WrongInputCount { arity: usize, found: usize },  // Inputs given to `output`
+}
+
+impl fmt::Display for TruthTableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TruthTableError::NoInputs => write!(f, "truth table needs at least one input"),
+            TruthTableError::TooManyInputs(arity) => {
+                write!(f, "truth table cannot have {} inputs", arity)
+            }
+            TruthTableError::WrongOutputCount { arity, found } => write!(
+                f,
+                "a {}-input truth table needs {} outputs but got {}",
+                arity,
+                1usize << arity,
+                found
+            ),
+            TruthTableError::WrongInputCount { arity, found } => {
+                write!(f, "truth table takes {} inputs but got {}", arity, found)
+            }
+        }
+    }
+}
+
+impl Error for TruthTableError {}
+
+/// Inputs of the given truth table row, first input as the most significant bit
+fn row_inputs(arity: usize, row: usize) -> Vec<bool> {
+    (0..arity)
+        .map(|bit| row & (1 << (arity - 1 - bit)) != 0)
+        .collect()
+}
 
 /// Represents available logic gates used in the neural CPU
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GateType {
     AND,
     OR,
     XOR,
+    NOT, // Single input
+    NAND,
+    NOR,
+    XNOR,
+    Custom(TruthTable), // Any function given by its truth table
+}
+
+impl GateType {
+    /// Number of inputs the gate takes
+    pub fn arity(&self) -> usize {
+        match self {
+            GateType::NOT => 1,
+            GateType::Custom(table) => table.arity(),
+            _ => 2,
+        }
+    }
+
+    /// AND gate with the given number of inputs: the 2-input AND, or a truth table gate
+    pub fn wide_and(arity: usize) -> GateType {
+        match arity {
+            2 => GateType::AND,
+            _ => GateType::Custom(TruthTable::and(arity)),
+        }
+    }
+
+    /// OR gate with the given number of inputs: the 2-input OR, or a truth table gate
+    pub fn wide_or(arity: usize) -> GateType {
+        match arity {
+            2 => GateType::OR,
+            _ => GateType::Custom(TruthTable::or(arity)),
+        }
+    }
+
+    /// Exact boolean value of the gate for the given inputs
+    ///
+    /// Panics if the number of inputs does not match the gate arity.
+    pub fn evaluate(&self, inputs: &[bool]) -> bool {
+        assert_eq!(
+            inputs.len(),
+            self.arity(),
+            "{} takes {} inputs",
+            self,
+            self.arity()
+        );
+        match self {
+            GateType::AND => inputs.iter().all(|&bit| bit),
+            GateType::OR => inputs.iter().any(|&bit| bit),
+            GateType::XOR => inputs.iter().filter(|&&bit| bit).count() % 2 == 1,
+            GateType::NOT => !inputs[0],
+            GateType::NAND => !GateType::AND.evaluate(inputs),
+            GateType::NOR => !GateType::OR.evaluate(inputs),
+            GateType::XNOR => !GateType::XOR.evaluate(inputs),
+            GateType::Custom(table) => table.output(inputs).unwrap(),
+        }
+    }
+
+    /// Every input combination with its expected output, in binary counting order
+    pub fn truth_table(&self) -> Vec<(Vec<bool>, bool)> {
+        let arity = self.arity();
+        (0..1usize << arity)
+            .map(|row| {
+                // First input is the most significant bit: 00, 01, 10, 11
+                let inputs = row_inputs(arity, row);
+                let output = self.evaluate(&inputs);
+                (inputs, output)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for GateType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GateType::AND => write!(f, "AND"),
+            GateType::OR => write!(f, "OR"),
+            GateType::XOR => write!(f, "XOR"),
+            GateType::NOT => write!(f, "NOT"),
+            GateType::NAND => write!(f, "NAND"),
+            GateType::NOR => write!(f, "NOR"),
+            GateType::XNOR => write!(f, "XNOR"),
+            GateType::Custom(table) => {
+                // TABLE<arity>:<outputs>, e.g. TABLE3:00010111 for 3-input majority
+                let outputs: String = table
+                    .outputs()
+                    .iter()
+                    .map(|&bit| if bit { '1' } else { '0' })
+                    .collect();
+                write!(f, "TABLE{}:{}", table.arity(), outputs)
+            }
+        }
+    }
+}
+
+impl FromStr for GateType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "AND" => Ok(GateType::AND),
+            "OR" => Ok(GateType::OR),
+            "XOR" => Ok(GateType::XOR),
+            "NOT" => Ok(GateType::NOT),
+            "NAND" => Ok(GateType::NAND),
+            "NOR" => Ok(GateType::NOR),
+            "XNOR" => Ok(GateType::XNOR),
+            _ => parse_table(name).ok_or_else(|| format!("unknown gate type '{}'", name)),
+        }
+    }
+}
+
+/// Parse the `TABLE<arity>:<outputs>` form of a custom gate type
+fn parse_table(name: &str) -> Option<GateType> {
+    let (arity, outputs) = name.strip_prefix("TABLE")?.split_once(':')?;
+    let arity: usize = arity.parse().ok()?;
+    let outputs = outputs
+        .chars()
+        .map(|bit| match bit {
+            '0' => Some(false),
+            '1' => Some(true),
+            _ => None,
+        })
+        .collect::<Option<Vec<bool>>>()?;
+
+    TruthTable::new(arity, outputs).ok().map(GateType::Custom)
+}
+
+/// Activation above which a gate output is read as 1
+pub const THRESHOLD: f64 = 0.5;
+
+/// Raw output of a gate with the bit it decodes to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GateOutput {
+    pub activation: f64, // Raw network output (0.0-1.0)
+    pub bit: bool,       // activation > THRESHOLD
+    pub margin: f64,     // Distance of the activation from THRESHOLD (0.0-0.5)
+}
+
+impl GateOutput {
+    /// Decode a raw activation
+    pub fn new(activation: f64) -> Self {
+        GateOutput {
+            activation,
+            bit: activation > THRESHOLD,
+            margin: (activation - THRESHOLD).abs(),
+        }
+    }
+}
+
+/// Logic gate backend used by adders, the ALU and the CPU
+///
+/// Implemented by the neural `LogicGate` and by the exact `ReferenceGate`, so
+/// the same circuit can run on trained networks or on plain boolean logic.
+pub trait Gate: fmt::Debug {
+    /// Get the gate type
+    fn gate_type(&self) -> &GateType;
+
+    /// Compute the gate output (0.0-1.0) for inputs given as 0.0 / 1.0 signals
+    fn compute(&self, inputs: Vec<f64>) -> f64;
+
+    /// Compute the gate output with its distance from the decision threshold
+    fn compute_detailed(&self, inputs: Vec<f64>) -> GateOutput {
+        GateOutput::new(self.compute(inputs))
+    }
+}
+
+/// Exact boolean gate, used as a reference to check neural results against
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferenceGate {
+    gate_type: GateType,
+}
+
+impl ReferenceGate {
+    /// Create a reference gate of the given type
+    pub fn new(gate_type: GateType) -> Self {
+        ReferenceGate { gate_type }
+    }
+}
+
+impl Gate for ReferenceGate {
+    fn gate_type(&self) -> &GateType {
+        &self.gate_type
+    }
+
+    fn compute(&self, inputs: Vec<f64>) -> f64 {
+        let bits: Vec<bool> = inputs.iter().map(|&input| input > THRESHOLD).collect();
+        to_signal(self.gate_type.evaluate(&bits))
+    }
 }
 
 /// A neural logic gate that performs boolean operations using trained neural networks
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LogicGate {
     gate_type: GateType,
-    network: NeuralNetwork,
+    network: Network,
+    fault: Option<InjectedFault>, // Injected hardware fault, if any
 }
 
 impl LogicGate {
     /// Creates a new logic gate of the specified type and trains it immediately
     pub fn new(gate_type: GateType) -> Self {
-        let network = match gate_type {
-            GateType::AND => create_and_network(),
-            GateType::OR => create_or_network(),
-            GateType::XOR => create_xor_network(),
+        Self::with_config(gate_type, &TrainingConfig::default()).0
+    }
+
+    /// Creates a gate for any boolean function given as a truth table
+    ///
+    /// The network gets a hidden layer of `max(4, 2 * arity)` neurons and a
+    /// constant bias input, and starts from seeded random weights.
+    pub fn from_truth_table(table: TruthTable) -> Self {
+        Self::new(GateType::Custom(table))
+    }
+
+    /// Creates a gate for the given boolean function of `arity` inputs
+    pub fn from_fn<F: Fn(&[bool]) -> bool>(arity: usize, function: F) -> Self {
+        Self::from_truth_table(TruthTable::from_fn(arity, function))
+    }
+
+    /// Creates a logic gate, failing if its trained truth table is wrong
+    pub fn try_new(gate_type: GateType) -> Result<Self, ConvergenceError> {
+        Self::try_with_config(gate_type, &TrainingConfig::default()).map(|(gate, _)| gate)
+    }
+
+    /// Creates and trains a logic gate, checking every truth table row afterwards
+    ///
+    /// Retries from fresh random weights up to `config.retries` times before
+    /// returning the failing rows of the last attempt.
+    pub fn try_with_config(
+        gate_type: GateType,
+        config: &TrainingConfig,
+    ) -> Result<(Self, TrainingReport), ConvergenceError> {
+        let mut attempt = 0;
+        loop {
+            let (gate, report) = Self::with_config(gate_type.clone(), &config.for_attempt(attempt));
+            let failed_rows = gate.failed_rows();
+            attempt += 1;
+
+            if failed_rows.is_empty() {
+                return Ok((gate, report));
+            }
+            if attempt > config.retries {
+                return Err(ConvergenceError {
+                    gate_type,
+                    attempts: attempt,
+                    failed_rows,
+                    report,
+                });
+            }
+        }
+    }
+
+    /// Creates and trains a logic gate with the given training settings
+    pub fn with_config(gate_type: GateType, config: &TrainingConfig) -> (Self, TrainingReport) {
+        let layers = match &config.initialization {
+            Initialization::HandPicked => hand_picked_layers(&gate_type),
+            Initialization::Random { seed } => random_layers(&layer_shapes(&gate_type), *seed),
+            Initialization::Custom(layers) => layers.clone(),
         };
 
-        let gate = LogicGate { gate_type, network };
-        gate.train(10_000); // Train immediately when gate is instantiated
-        gate
+        let mut gate = LogicGate {
+            gate_type,
+            network: Network::new(layers),
+            fault: None,
+        };
+        let report = gate.train(config); // Train immediately when gate is instantiated
+        (gate, report)
     }
 
-    /// Train the gate with appropriate training data
-    fn train(&self, epochs: usize) {
-        let input = Matrix::new(vec![
-            vec![0.0, 0.0],
-            vec![0.0, 1.0],
-            vec![1.0, 0.0],
-            vec![1.0, 1.0],
+    /// Rebuild a gate from already trained weights, skipping training
+    pub fn from_network(gate_type: GateType, network: Network) -> Self {
+        LogicGate {
+            gate_type,
+            network,
+            fault: None,
+        }
+    }
+
+    /// Train the gate on its truth table
+    fn train(&mut self, config: &TrainingConfig) -> TrainingReport {
+        let table = self.gate_type.truth_table();
+        let input = Matrix::new(
+            table
+                .iter()
+                .map(|(inputs, _)| self.with_bias(to_signals(inputs)))
+                .collect(),
+        );
+        let targets = Matrix::new(vec![
+            table.iter().map(|&(_, output)| to_signal(output)).collect(),
         ]);
 
-        let targets = match self.gate_type {
-            GateType::AND => Matrix::new(vec![vec![0.0, 0.0, 0.0, 1.0]]),
-            GateType::OR => Matrix::new(vec![vec![0.0, 1.0, 1.0, 1.0]]),
-            GateType::XOR => Matrix::new(vec![vec![0.0, 1.0, 1.0, 0.0]]),
-        };
+        let mut loss_curve = Vec::with_capacity(config.epochs);
+        for _ in 0..config.epochs {
+            let loss = self.network.train(&input, &targets);
+            loss_curve.push(loss);
 
-        for _ in 0..epochs {
-            self.network.train(input.clone(), targets.clone());
+            if config.target_loss.is_some_and(|target| loss <= target) {
+                break; // Early stopping
+            }
         }
+
+        TrainingReport {
+            loss_curve,
+            accuracy: self.accuracy(),
+        }
+    }
+
+    /// Fraction of truth table rows the gate answers correctly (0.0-1.0)
+    pub fn accuracy(&self) -> f64 {
+        let rows = self.gate_type.truth_table().len();
+        (rows - self.failed_rows().len()) as f64 / rows as f64
+    }
+
+    /// Truth table rows the gate currently answers wrongly, with raw activations
+    pub fn failed_rows(&self) -> Vec<FailedRow> {
+        self.gate_type
+            .truth_table()
+            .into_iter()
+            .filter_map(|(inputs, expected)| {
+                let activation = self.compute(to_signals(&inputs));
+                ((activation > THRESHOLD) != expected).then_some(FailedRow {
+                    inputs,
+                    expected,
+                    activation,
+                })
+            })
+            .collect()
     }
 
     /// Compute the gate output for given inputs
     pub fn compute(&self, inputs: Vec<f64>) -> f64 {
-        let input_matrix = Matrix::new(vec![inputs]);
-        let result = self.network.predict(input_matrix);
-        result.data[0][0]
+        let predict = |network: &Network, inputs: Vec<f64>| {
+            let input_matrix = Matrix::new(vec![self.with_bias(inputs)]);
+            let result = network.predict(input_matrix);
+            result.data[0][0]
+        };
+
+        match &self.fault {
+            Some(fault) => fault.compute(&self.network, inputs, predict),
+            None => predict(&self.network, inputs),
+        }
+    }
+
+    /// Inject a hardware fault, replacing any fault already present
+    ///
+    /// The trained weights are kept; clearing the fault restores the healthy gate.
+    pub fn set_fault(&mut self, fault: Fault) -> Result<(), FaultError> {
+        self.fault = Some(InjectedFault::new(fault, &self.network)?);
+        Ok(())
+    }
+
+    /// Remove the injected fault
+    pub fn clear_fault(&mut self) {
+        self.fault = None;
+    }
+
+    /// Currently injected fault
+    pub fn fault(&self) -> Option<&Fault> {
+        self.fault.as_ref().map(InjectedFault::fault)
+    }
+
+    /// Compute the gate output with its distance from the decision threshold
+    pub fn compute_detailed(&self, inputs: Vec<f64>) -> GateOutput {
+        GateOutput::new(self.compute(inputs))
+    }
+
+    /// Append the constant bias input when the network was built with one
+    fn with_bias(&self, mut inputs: Vec<f64>) -> Vec<f64> {
+        if self.network.input_size() == inputs.len() + 1 {
+            inputs.push(1.0);
+        }
+        inputs
     }
 
     /// Get the gate type
     pub fn gate_type(&self) -> &GateType {
         &self.gate_type
     }
+
+    /// Get the trained network
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Read a gate written by `write_weights`, failing unless it has the given type
+    ///
+    /// Components use this for every gate slot, so a file with the wrong gate
+    /// in a slot is rejected instead of loading and computing wrong results.
+    pub fn read_typed(reader: &mut WeightReader, expected: &GateType) -> io::Result<Self> {
+        let gate_type = read_gate_type(reader)?;
+        if gate_type != *expected {
+            return Err(reader.error(&format!(
+                "expected gate {} but found {}",
+                expected, gate_type
+            )));
+        }
+        let network = reader.network(gate_type.arity())?;
+        Ok(LogicGate::from_network(gate_type, network))
+    }
+}
+
+impl Gate for LogicGate {
+    fn gate_type(&self) -> &GateType {
+        LogicGate::gate_type(self)
+    }
+
+    fn compute(&self, inputs: Vec<f64>) -> f64 {
+        LogicGate::compute(self, inputs)
+    }
+}
+
+impl Persist for LogicGate {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line(&format!("gate {}", self.gate_type))?;
+        writer.network(&self.network)
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        let gate_type = read_gate_type(reader)?;
+        let network = reader.network(gate_type.arity())?;
+        Ok(LogicGate::from_network(gate_type, network))
+    }
+}
+
+/// Read the `gate <TYPE>` line starting a gate
+fn read_gate_type(reader: &mut WeightReader) -> io::Result<GateType> {
+    let fields = reader.tagged("gate")?;
+    match fields.as_slice() {
+        [name] => name
+            .parse()
+            .map_err(|message: String| reader.error(&message)),
+        _ => Err(reader.error("expected a gate type")),
+    }
+}
+
+/// Convert a bit to the 0.0 / 1.0 signal fed to networks
+pub(crate) fn to_signal(bit: bool) -> f64 {
+    if bit { 1.0 } else { 0.0 }
+}
+
+fn to_signals(bits: &[bool]) -> Vec<f64> {
+    bits.iter().map(|&bit| to_signal(bit)).collect()
+}
+
+/// Hand-picked starting layers for each gate type
+fn hand_picked_layers(gate_type: &GateType) -> Vec<Layer> {
+    match gate_type {
+        GateType::AND => create_and_network(),
+        GateType::OR => create_or_network(),
+        GateType::XOR => create_xor_network(),
+        GateType::NOT => create_not_network(),
+        GateType::NAND => create_nand_network(),
+        GateType::NOR => create_nor_network(),
+        GateType::XNOR => create_xnor_network(),
+        GateType::Custom(_) => random_layers(&layer_shapes(gate_type), DEFAULT_SEED),
+    }
+}
+
+/// Layer shapes (neurons, inputs) of the network for each gate type
+fn layer_shapes(gate_type: &GateType) -> Vec<(usize, usize)> {
+    match gate_type {
+        GateType::Custom(table) => {
+            // One extra input carries the constant bias
+            let hidden = (2 * table.arity()).max(4);
+            vec![(hidden, table.arity() + 1), (1, hidden)]
+        }
+        _ => hand_picked_layers(gate_type)
+            .iter()
+            .map(|layer| (layer.matrix.data.len(), layer.matrix.data[0].len()))
+            .collect(),
+    }
 }
 
 // Network creation functions for each gate type
 
-fn create_and_network() -> NeuralNetwork {
-    let layers = vec![
+fn create_and_network() -> Vec<Layer> {
+    vec![
         Layer::new(Matrix::new(vec![
             vec![0.8, 0.8],
             vec![0.6, 0.6],
             vec![-0.3, -0.3],
         ])),
         Layer::new(Matrix::new(vec![vec![1.2, 0.8, -0.5]])),
-    ];
-    NeuralNetwork::new(layers)
+    ]
 }
 
-fn create_or_network() -> NeuralNetwork {
-    let layers = vec![
+fn create_or_network() -> Vec<Layer> {
+    vec![
         Layer::new(Matrix::new(vec![
             vec![1.0, 1.0],
             vec![0.5, 0.5],
             vec![-0.2, -0.2],
         ])),
         Layer::new(Matrix::new(vec![vec![1.5, 1.0, -0.3]])),
-    ];
-    NeuralNetwork::new(layers)
+    ]
 }
 
-fn create_xor_network() -> NeuralNetwork {
-    let layers = vec![
+fn create_xor_network() -> Vec<Layer> {
+    vec![
         Layer::new(Matrix::new(vec![
             vec![0.5, 0.5],
             vec![-0.3, -0.3],
@@ -99,7 +659,52 @@ fn create_xor_network() -> NeuralNetwork {
             vec![-0.6, 0.6],
         ])),
         Layer::new(Matrix::new(vec![vec![0.9, -0.7, 1.2, -0.4]])),
-    ];
-    NeuralNetwork::new(layers)
+    ]
+}
+
+// Inverted gates start from the same hidden layer as their positive
+// counterpart with negated output weights
+
+fn create_not_network() -> Vec<Layer> {
+    vec![
+        Layer::new(Matrix::new(vec![
+            vec![1.5],  // Fires when input is 1
+            vec![-1.5], // Fires when input is 0
+        ])),
+        Layer::new(Matrix::new(vec![vec![-1.0, 1.0]])),
+    ]
+}
+
+fn create_nand_network() -> Vec<Layer> {
+    vec![
+        Layer::new(Matrix::new(vec![
+            vec![0.8, 0.8],
+            vec![0.6, 0.6],
+            vec![-0.3, -0.3],
+        ])),
+        Layer::new(Matrix::new(vec![vec![-1.2, -0.8, 0.5]])),
+    ]
 }
 
+fn create_nor_network() -> Vec<Layer> {
+    vec![
+        Layer::new(Matrix::new(vec![
+            vec![1.0, 1.0],
+            vec![0.5, 0.5],
+            vec![-0.2, -0.2],
+        ])),
+        Layer::new(Matrix::new(vec![vec![-1.5, -1.0, 0.3]])),
+    ]
+}
+
+fn create_xnor_network() -> Vec<Layer> {
+    vec![
+        Layer::new(Matrix::new(vec![
+            vec![0.5, 0.5],
+            vec![-0.3, -0.3],
+            vec![0.8, -0.8],
+            vec![-0.6, 0.6],
+        ])),
+        Layer::new(Matrix::new(vec![vec![-0.9, 0.7, -1.2, 0.4]])),
+    ]
+}
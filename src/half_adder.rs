@@ -1,4 +1,10 @@
-use crate::gates::{GateType, LogicGate};
+use std::io;
+
+use crate::faults::{FaultInjection, child_paths};
+use crate::gate_factory::{GateFactory, GateSource, ReferenceGates};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate};
+use crate::netlist::{Netlist, NetlistBuilder};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
 
 /// Result of half adder computation
 #[derive(Debug, PartialEq)]
@@ -7,36 +13,121 @@ pub struct HalfAdderResult {
     pub carry: bool,
 }
 
+/// Half adder result with the raw output of each gate
+#[derive(Debug, Clone, PartialEq)]
+pub struct HalfAdderDetail {
+    pub sum: GateOutput,   // XOR gate
+    pub carry: GateOutput, // AND gate
+}
+
+impl HalfAdderDetail {
+    /// Thresholded result
+    pub fn result(&self) -> HalfAdderResult {
+        HalfAdderResult {
+            sum: self.sum.bit,
+            carry: self.carry.bit,
+        }
+    }
+
+    /// Smallest distance from the decision threshold across both gates
+    pub fn min_margin(&self) -> f64 {
+        self.sum.margin.min(self.carry.margin)
+    }
+}
+
 /// A half adder that computes sum and carry for two bits
 /// Sum = A XOR B, Carry = A AND B
 #[derive(Debug)]
-pub struct HalfAdder {
-    xor_gate: LogicGate,
-    and_gate: LogicGate,
+pub struct HalfAdder<G: Gate = LogicGate> {
+    xor_gate: G,
+    and_gate: G,
 }
 
 impl HalfAdder {
-    /// Create a new half adder with trained neural gates
+    /// Create a new half adder with trained neural gates (one network per gate type)
     pub fn new() -> Self {
-        let xor_gate = LogicGate::new(GateType::XOR);
-        let and_gate = LogicGate::new(GateType::AND);
+        Self::with_factory(&mut GateFactory::shared())
+    }
+
+    /// Gate-level description: inputs `a`, `b`, outputs `sum`, `carry`
+    pub fn netlist() -> Netlist {
+        HalfAdder::with_factory(&mut ReferenceGates).to_netlist()
+    }
+}
+
+impl<G: Gate> HalfAdder<G> {
+    /// Create a half adder using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S) -> Self {
+        let xor_gate = factory.gate(GateType::XOR);
+        let and_gate = factory.gate(GateType::AND);
 
         HalfAdder { xor_gate, and_gate }
     }
 
+    /// Number of gates: one XOR and one AND
+    pub fn gate_count(&self) -> usize {
+        2
+    }
+
+    /// Gate-level description of this half adder, with cells named like its gate paths
+    pub fn to_netlist(&self) -> Netlist {
+        let mut builder = NetlistBuilder::new("half_adder");
+        let a = builder.input("a");
+        let b = builder.input("b");
+        let sum = builder.gate("xor", self.xor_gate.gate_type().clone(), &[a, b]);
+        let carry = builder.gate("and", self.and_gate.gate_type().clone(), &[a, b]);
+        builder.output("sum", sum);
+        builder.output("carry", carry);
+        builder.build().expect("half adder netlist is well formed")
+    }
+
     /// Compute half adder output for two binary inputs
     pub fn compute(&self, a: bool, b: bool) -> HalfAdderResult {
+        self.compute_detailed(a, b).result()
+    }
+
+    /// Compute half adder output keeping the raw gate activations
+    pub fn compute_detailed(&self, a: bool, b: bool) -> HalfAdderDetail {
         let a_f = if a { 1.0 } else { 0.0 };
         let b_f = if b { 1.0 } else { 0.0 };
 
-        let sum_output = self.xor_gate.compute(vec![a_f, b_f]);
-        let carry_output = self.and_gate.compute(vec![a_f, b_f]);
+        HalfAdderDetail {
+            sum: self.xor_gate.compute_detailed(vec![a_f, b_f]),
+            carry: self.and_gate.compute_detailed(vec![a_f, b_f]),
+        }
+    }
+}
 
-        HalfAdderResult {
-            sum: sum_output > 0.5,
-            carry: carry_output > 0.5,
+impl Persist for HalfAdder {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line("half_adder")?;
+        self.xor_gate.write_weights(writer)?;
+        self.and_gate.write_weights(writer)
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        reader.section("half_adder")?;
+        let xor_gate = LogicGate::read_typed(reader, &GateType::XOR)?;
+        let and_gate = LogicGate::read_typed(reader, &GateType::AND)?;
+        Ok(HalfAdder { xor_gate, and_gate })
+    }
+}
+
+/// Gates are addressed as `xor` and `and`
+impl FaultInjection for HalfAdder {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        match path.split_first()? {
+            (&"xor", rest) => self.xor_gate.gate_mut(rest),
+            (&"and", rest) => self.and_gate.gate_mut(rest),
+            _ => None,
         }
     }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = child_paths("xor", &self.xor_gate);
+        paths.extend(child_paths("and", &self.and_gate));
+        paths
+    }
 }
 
 impl Default for HalfAdder {
@@ -44,4 +135,3 @@ impl Default for HalfAdder {
         Self::new()
     }
 }
-
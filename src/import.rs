@@ -0,0 +1,1133 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::export::VERILOG_KEYWORDS;
+use crate::gates::{GateType, TruthTable};
+use crate::netlist::{Netlist, NetlistBuilder, NetlistError, Wire};
+
+/// Widest gate an imported file may use; every gate is trained on its full truth table,
+/// and wider parity functions do not reliably train
+pub const MAX_GATE_INPUTS: usize = 5;
+
+/// Verilog primitives that can be imported
+const PRIMITIVES: [&str; 8] = ["and", "or", "xor", "nand", "nor", "xnor", "not", "buf"];
+
+/// Error found while reading a circuit file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize, // 1-based line of the file
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        ParseError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Netlist of a combinational BLIF model
+///
+/// Supports `.model`, `.inputs`, `.outputs`, `.names` with its cover rows
+/// (on-set or off-set, `-` for don't care) and `.end`, plus `#` comments and
+/// `\` line continuations. Every `.names` becomes one gate named after the
+/// wire it drives; single-input buffers become plain connections and covers
+/// without inputs become constants. Only one model per file is supported.
+pub fn parse_blif(text: &str) -> Result<Netlist, ParseError> {
+    let module = blif_model(text)?;
+    build_netlist(&module, &[], &HashMap::new())
+}
+
+/// Netlist of the top module of a structural Verilog file
+///
+/// Supports modules with scalar `input`, `output`, `wire`, `supply0` and
+/// `supply1` declarations, the `and`, `or`, `xor`, `nand`, `nor`, `xnor`,
+/// `not` and `buf` primitives (optionally named), `1'b0`/`1'b1` constants
+/// and instances of other modules in the file with named or positional
+/// connections. Named primitives keep their name, unnamed ones are named
+/// after the wire they drive, and `buf` becomes a plain connection. The top
+/// module is the last one that no other module instantiates.
+pub fn parse_verilog(text: &str) -> Result<Netlist, ParseError> {
+    let end_line = text.lines().count().max(1);
+    let mut parser = VerilogParser {
+        tokens: tokenize(text)?,
+        position: 0,
+        end_line,
+    };
+    let mut modules: Vec<Module> = Vec::new();
+    while parser.peek().is_some() {
+        let module = parser.module()?;
+        if modules.iter().any(|other| other.name == module.name) {
+            return Err(ParseError::new(
+                module.line,
+                format!("module '{}' is defined twice", module.name),
+            ));
+        }
+        modules.push(module);
+    }
+
+    let instantiated = |name: &str| {
+        modules.iter().any(|module| {
+            module
+                .items
+                .iter()
+                .any(|item| matches!(item, Item::Instance { module: used, .. } if used == name))
+        })
+    };
+    let top = modules
+        .iter()
+        .rev()
+        .find(|module| !instantiated(&module.name))
+        .or(modules.last())
+        .ok_or_else(|| ParseError::new(end_line, "no module found"))?;
+
+    let mut built = HashMap::new();
+    build_module(&top.name, &modules, &mut built, &mut Vec::new())?;
+    Ok(built.remove(&top.name).unwrap())
+}
+
+/// A wire by name, or a constant
+#[derive(Debug, Clone, PartialEq)]
+enum Net {
+    Wire(String),
+    Constant(bool),
+}
+
+/// Port connections of an instance
+#[derive(Debug, Clone)]
+enum Connections {
+    Named(Vec<(String, Net, usize)>), // Port, net and the line of the connection
+    Positional(Vec<Net>),             // In the order of the module's ports
+}
+
+/// A statement of a module body
+#[derive(Debug, Clone)]
+enum Item {
+    Gate {
+        name: String,
+        gate_type: GateType,
+        output: String,
+        inputs: Vec<Net>,
+        line: usize,
+    },
+    // A wire that is another wire or a constant under a second name
+    Buffer {
+        output: String,
+        input: Net,
+        line: usize,
+    },
+    Instance {
+        name: String,
+        module: String,
+        connections: Connections,
+        line: usize,
+    },
+}
+
+/// Circuit read from a file, before it is turned into a netlist
+#[derive(Debug, Clone)]
+struct Module {
+    name: String,
+    line: usize,
+    ports: Vec<String>,                    // In header order
+    inputs: Vec<(String, usize)>,          // Input ports in header order, with their line
+    outputs: Vec<(String, usize)>,         // Output ports in header order, with their line
+    constants: Vec<(String, bool, usize)>, // Wires tied to 0 or 1
+    items: Vec<Item>,
+}
+
+/// Source lines of the names of a module, to report netlist errors
+#[derive(Debug, Default)]
+struct Lines {
+    used: HashMap<String, usize>,    // First line reading each wire
+    drivers: HashMap<String, usize>, // Line driving each wire
+    cells: HashMap<String, usize>,   // Line of each cell
+}
+
+impl Lines {
+    fn use_wire(&mut self, name: &str, line: usize) {
+        self.used.entry(name.to_string()).or_insert(line);
+    }
+
+    fn drive(&mut self, name: &str, line: usize) -> Result<(), ParseError> {
+        match self.drivers.insert(name.to_string(), line) {
+            Some(_) => Err(ParseError::new(
+                line,
+                NetlistError::MultipleDrivers(name.to_string()).to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Line an error of the netlist builder was caused by
+    fn line_of(&self, error: &NetlistError) -> Option<usize> {
+        match error {
+            NetlistError::Undriven(wire) => self.used.get(wire),
+            NetlistError::MultipleDrivers(wire) => self.drivers.get(wire),
+            NetlistError::DuplicateName(cell) | NetlistError::CombinationalLoop(cell) => {
+                self.cells.get(cell)
+            }
+            NetlistError::WrongInputCount { cell, .. }
+            | NetlistError::WrongOutputCount { cell, .. } => self.cells.get(cell),
+        }
+        .copied()
+    }
+}
+
+/// Gate type computing a truth table, preferring the named types
+fn gate_type(table: TruthTable) -> GateType {
+    [
+        GateType::AND,
+        GateType::OR,
+        GateType::XOR,
+        GateType::NOT,
+        GateType::NAND,
+        GateType::NOR,
+        GateType::XNOR,
+    ]
+    .into_iter()
+    .find(|gate_type| {
+        gate_type.arity() == table.arity()
+            && gate_type
+                .truth_table()
+                .iter()
+                .map(|(_, output)| *output)
+                .eq(table.outputs().iter().copied())
+    })
+    .unwrap_or(GateType::Custom(table))
+}
+
+fn inverted(table: TruthTable) -> TruthTable {
+    TruthTable::from_fn(table.arity(), |inputs| !table.output(inputs).unwrap())
+}
+
+/// Build a module after the modules it instantiates, detecting recursion
+fn build_module(
+    name: &str,
+    modules: &[Module],
+    built: &mut HashMap<String, Netlist>,
+    stack: &mut Vec<String>,
+) -> Result<(), ParseError> {
+    if built.contains_key(name) {
+        return Ok(());
+    }
+    let module = modules.iter().find(|module| module.name == name).unwrap();
+    stack.push(name.to_string());
+    for item in &module.items {
+        if let Item::Instance {
+            module: used, line, ..
+        } = item
+        {
+            if !modules.iter().any(|module| &module.name == used) {
+                return Err(ParseError::new(*line, format!("unknown module '{}'", used)));
+            }
+            if stack.contains(used) {
+                return Err(ParseError::new(
+                    *line,
+                    format!("module '{}' instantiates itself", used),
+                ));
+            }
+            build_module(used, modules, built, stack)?;
+        }
+    }
+    stack.pop();
+    let netlist = build_netlist(module, modules, built)?;
+    built.insert(name.to_string(), netlist);
+    Ok(())
+}
+
+/// Netlist of a module whose instantiated modules are already built
+fn build_netlist(
+    module: &Module,
+    modules: &[Module],
+    built: &HashMap<String, Netlist>,
+) -> Result<Netlist, ParseError> {
+    let mut lines = Lines::default();
+    let mut aliases: HashMap<&str, (&Net, usize)> = HashMap::new();
+
+    // Check every wire has a single driver first, so buffers are covered too
+    for (name, line) in &module.inputs {
+        lines.drive(name, *line)?;
+    }
+    for (name, _, line) in &module.constants {
+        lines.drive(name, *line)?;
+    }
+    let mut instance_ports = Vec::new();
+    for item in &module.items {
+        match item {
+            Item::Gate {
+                name, output, line, ..
+            } => {
+                lines.drive(output, *line)?;
+                lines.cells.insert(name.clone(), *line);
+            }
+            Item::Buffer {
+                output,
+                input,
+                line,
+            } => {
+                lines.drive(output, *line)?;
+                aliases.insert(output, (input, *line));
+            }
+            Item::Instance {
+                name,
+                module: used,
+                connections,
+                line,
+            } => {
+                let definition = modules.iter().find(|module| &module.name == used).unwrap();
+                let (inputs, outputs) = connect(name, definition, connections, *line)?;
+                for output in &outputs {
+                    lines.drive(output, *line)?;
+                }
+                lines.cells.insert(name.clone(), *line);
+                instance_ports.push((inputs, outputs));
+            }
+        }
+    }
+
+    let constants: HashMap<&str, bool> = module
+        .constants
+        .iter()
+        .map(|(name, value, _)| (name.as_str(), *value))
+        .collect();
+    let resolve = |name: &str| -> Result<Net, ParseError> {
+        let mut name = name;
+        for _ in 0..=aliases.len() {
+            if let Some(&value) = constants.get(name) {
+                return Ok(Net::Constant(value));
+            }
+            match aliases.get(name) {
+                Some((Net::Wire(input), _)) => name = input,
+                Some((Net::Constant(value), _)) => return Ok(Net::Constant(*value)),
+                None => return Ok(Net::Wire(name.to_string())),
+            }
+        }
+        let message = format!("wire '{}' is part of a combinational loop", name);
+        Err(ParseError::new(aliases[name].1, message))
+    };
+    let mut wire = |builder: &mut NetlistBuilder, net: &Net, line: usize| {
+        Ok(match net {
+            Net::Wire(name) => {
+                lines.use_wire(name, line);
+                match resolve(name)? {
+                    Net::Wire(name) => {
+                        lines.use_wire(&name, line);
+                        builder.wire(&name)
+                    }
+                    Net::Constant(value) => builder.constant(value),
+                }
+            }
+            Net::Constant(value) => builder.constant(*value),
+        })
+    };
+
+    let mut builder = NetlistBuilder::new(&module.name);
+    for (name, _) in &module.inputs {
+        builder.input(name);
+    }
+    let mut instance_ports = instance_ports.into_iter();
+    for item in &module.items {
+        match item {
+            Item::Gate {
+                name,
+                gate_type,
+                output,
+                inputs,
+                line,
+            } => {
+                let inputs = inputs
+                    .iter()
+                    .map(|net| wire(&mut builder, net, *line))
+                    .collect::<Result<Vec<Wire>, ParseError>>()?;
+                let output = builder.wire(output);
+                builder.gate_into(name, gate_type.clone(), &inputs, output);
+            }
+            Item::Buffer { .. } => {}
+            Item::Instance {
+                name,
+                module: used,
+                line,
+                ..
+            } => {
+                let (inputs, outputs) = instance_ports.next().unwrap();
+                let inputs = inputs
+                    .iter()
+                    .map(|net| wire(&mut builder, net, *line))
+                    .collect::<Result<Vec<Wire>, ParseError>>()?;
+                let outputs: Vec<Wire> = outputs.iter().map(|name| builder.wire(name)).collect();
+                builder.instance_into(name, &built[used], &inputs, &outputs);
+            }
+        }
+    }
+    for (name, line) in &module.outputs {
+        let output = wire(&mut builder, &Net::Wire(name.clone()), *line)?;
+        builder.output(name, output);
+    }
+
+    builder.build().map_err(|error| {
+        let line = lines.line_of(&error).unwrap_or(module.line);
+        ParseError::new(line, error.to_string())
+    })
+}
+
+/// Nets on the input ports and wires on the output ports of an instance
+fn connect(
+    name: &str,
+    definition: &Module,
+    connections: &Connections,
+    line: usize,
+) -> Result<(Vec<Net>, Vec<String>), ParseError> {
+    let mut nets: HashMap<&str, &Net> = HashMap::new();
+    match connections {
+        Connections::Named(named) => {
+            for (port, net, line) in named {
+                if !definition.ports.contains(port) {
+                    return Err(ParseError::new(
+                        *line,
+                        format!("module '{}' has no port '{}'", definition.name, port),
+                    ));
+                }
+                if nets.insert(port, net).is_some() {
+                    return Err(ParseError::new(
+                        *line,
+                        format!("port '{}' of '{}' is connected twice", port, name),
+                    ));
+                }
+            }
+        }
+        Connections::Positional(positional) => {
+            if positional.len() != definition.ports.len() {
+                return Err(ParseError::new(
+                    line,
+                    format!(
+                        "'{}' connects {} ports but module '{}' has {}",
+                        name,
+                        positional.len(),
+                        definition.name,
+                        definition.ports.len()
+                    ),
+                ));
+            }
+            nets.extend(definition.ports.iter().map(String::as_str).zip(positional));
+        }
+    }
+
+    let inputs = definition
+        .inputs
+        .iter()
+        .map(|(port, _)| {
+            nets.get(port.as_str())
+                .map(|&net| net.clone())
+                .ok_or_else(|| {
+                    ParseError::new(
+                        line,
+                        format!("input port '{}' of '{}' is not connected", port, name),
+                    )
+                })
+        })
+        .collect::<Result<_, _>>()?;
+    let outputs = definition
+        .outputs
+        .iter()
+        .map(|(port, _)| match nets.get(port.as_str()) {
+            Some(Net::Wire(wire)) => Ok(wire.clone()),
+            Some(Net::Constant(_)) => Err(ParseError::new(
+                line,
+                format!("output port '{}' of '{}' drives a constant", port, name),
+            )),
+            // Unconnected outputs are named like `NetlistBuilder::instance` names them
+            None => Ok(format!("{}.{}", name, port)),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((inputs, outputs))
+}
+
+/// A `.names` block being read
+#[derive(Debug)]
+struct Cover {
+    inputs: Vec<String>,
+    output: String,
+    line: usize,
+    rows: Vec<(Vec<Option<bool>>, bool)>, // Input plane (`None` for `-`) and output
+}
+
+impl Cover {
+    fn row(&mut self, tokens: &[&str], line: usize) -> Result<(), ParseError> {
+        let (plane, output) = match (self.inputs.len(), tokens) {
+            (0, [output]) => ("", *output),
+            (_, [plane, output]) if !self.inputs.is_empty() => (*plane, *output),
+            (arity, _) => {
+                let expected = if arity == 0 {
+                    "an output value".to_string()
+                } else {
+                    format!("{} input values and an output value", arity)
+                };
+                return Err(ParseError::new(
+                    line,
+                    format!("expected {} but found '{}'", expected, tokens.join(" ")),
+                ));
+            }
+        };
+
+        if plane.len() != self.inputs.len() {
+            return Err(ParseError::new(
+                line,
+                format!(
+                    "expected {} input values but found '{}'",
+                    self.inputs.len(),
+                    plane
+                ),
+            ));
+        }
+        let plane = plane
+            .chars()
+            .map(|value| match value {
+                '0' => Ok(Some(false)),
+                '1' => Ok(Some(true)),
+                '-' => Ok(None),
+                _ => Err(ParseError::new(
+                    line,
+                    format!("invalid input value '{}'", value),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let output = match output {
+            "0" => false,
+            "1" => true,
+            _ => {
+                return Err(ParseError::new(
+                    line,
+                    format!("output value must be 0 or 1 but found '{}'", output),
+                ));
+            }
+        };
+        if self.rows.first().is_some_and(|(_, first)| *first != output) {
+            return Err(ParseError::new(
+                line,
+                "cover mixes rows for outputs 0 and 1",
+            ));
+        }
+        self.rows.push((plane, output));
+        Ok(())
+    }
+
+    /// Add the cover to the model as a gate, connection or constant
+    fn finish(self, module: &mut Module) {
+        let arity = self.inputs.len();
+        // Rows list where the output is 1, or where it is 0 (the off-set)
+        let on_set = self.rows.first().is_none_or(|(_, output)| *output);
+        if arity == 0 || self.rows.is_empty() {
+            let value = !self.rows.is_empty() && on_set;
+            module.constants.push((self.output, value, self.line));
+            return;
+        }
+
+        let covered = |inputs: &[bool]| {
+            self.rows.iter().any(|(plane, _)| {
+                plane
+                    .iter()
+                    .zip(inputs)
+                    .all(|(value, &input)| value.is_none_or(|value| value == input))
+            })
+        };
+        let table = TruthTable::from_fn(arity, |inputs| covered(inputs) == on_set);
+        if table.outputs() == [false, true] {
+            module.items.push(Item::Buffer {
+                output: self.output,
+                input: Net::Wire(self.inputs[0].clone()),
+                line: self.line,
+            });
+        } else {
+            module.items.push(Item::Gate {
+                name: self.output.clone(),
+                gate_type: gate_type(table),
+                output: self.output,
+                inputs: self.inputs.into_iter().map(Net::Wire).collect(),
+                line: self.line,
+            });
+        }
+    }
+}
+
+/// Read the single model of a BLIF file
+fn blif_model(text: &str) -> Result<Module, ParseError> {
+    let mut module: Option<Module> = None;
+    let mut cover: Option<Cover> = None;
+    let mut ended = false;
+
+    // Join continued lines, keeping the line each one starts on
+    let mut statements: Vec<(usize, String)> = Vec::new();
+    let mut continued = false;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim_end();
+        let (line, continues) = match line.strip_suffix('\\') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        match statements.last_mut() {
+            Some((_, statement)) if continued => {
+                statement.push(' ');
+                statement.push_str(line);
+            }
+            _ => statements.push((index + 1, line.to_string())),
+        }
+        continued = continues;
+    }
+
+    for (line, statement) in &statements {
+        let line = *line;
+        let tokens: Vec<&str> = statement.split_whitespace().collect();
+        let Some(&first) = tokens.first() else {
+            continue;
+        };
+        if ended {
+            let message = if first == ".model" {
+                "only one .model per file is supported".to_string()
+            } else {
+                format!("unexpected '{}' after .end", first)
+            };
+            return Err(ParseError::new(line, message));
+        }
+        if !first.starts_with('.') {
+            match cover.as_mut() {
+                Some(cover) => cover.row(&tokens, line)?,
+                None => {
+                    return Err(ParseError::new(
+                        line,
+                        format!("cover row '{}' outside of .names", statement.trim()),
+                    ));
+                }
+            }
+            continue;
+        }
+
+        if let (Some(cover), Some(module)) = (cover.take(), module.as_mut()) {
+            cover.finish(module);
+        }
+        if first == ".model" {
+            if module.is_some() {
+                return Err(ParseError::new(
+                    line,
+                    "only one .model per file is supported",
+                ));
+            }
+            let [_, name] = tokens[..] else {
+                return Err(ParseError::new(line, ".model takes one name"));
+            };
+            module = Some(Module {
+                name: name.to_string(),
+                line,
+                ports: Vec::new(),
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                constants: Vec::new(),
+                items: Vec::new(),
+            });
+            continue;
+        }
+        let Some(module) = module.as_mut() else {
+            return Err(ParseError::new(
+                line,
+                format!("expected .model before {}", first),
+            ));
+        };
+        let names = || tokens[1..].iter().map(|name| (name.to_string(), line));
+        match first {
+            ".inputs" => module.inputs.extend(names()),
+            ".outputs" => module.outputs.extend(names()),
+            ".names" => {
+                let Some((output, inputs)) = tokens[1..].split_last() else {
+                    return Err(ParseError::new(line, ".names needs an output"));
+                };
+                if inputs.len() > MAX_GATE_INPUTS {
+                    return Err(ParseError::new(
+                        line,
+                        format!(
+                            ".names has {} inputs but at most {} are supported",
+                            inputs.len(),
+                            MAX_GATE_INPUTS
+                        ),
+                    ));
+                }
+                cover = Some(Cover {
+                    inputs: inputs.iter().map(|name| name.to_string()).collect(),
+                    output: output.to_string(),
+                    line,
+                    rows: Vec::new(),
+                });
+            }
+            ".end" => ended = true,
+            ".latch" => {
+                return Err(ParseError::new(
+                    line,
+                    ".latch is not supported, only combinational circuits are",
+                ));
+            }
+            _ => {
+                return Err(ParseError::new(
+                    line,
+                    format!("unsupported directive {}", first),
+                ));
+            }
+        }
+    }
+
+    let end_line = text.lines().count().max(1);
+    let mut module = module.ok_or_else(|| ParseError::new(end_line, "no .model found"))?;
+    if let Some(cover) = cover {
+        cover.finish(&mut module);
+    }
+    module.ports = module
+        .inputs
+        .iter()
+        .chain(&module.outputs)
+        .map(|(name, _)| name.clone())
+        .collect();
+    Ok(module)
+}
+
+/// A token of a Verilog file
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),    // Identifier or keyword
+    Escaped(String), // Escaped identifier, without the backslash and never a keyword
+    Constant(bool),
+    Symbol(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Name(name) => write!(f, "'{}'", name),
+            Token::Escaped(name) => write!(f, "'\\{}'", name),
+            Token::Constant(value) => write!(f, "1'b{}", *value as u8),
+            Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+        }
+    }
+}
+
+fn is_keyword(name: &str) -> bool {
+    VERILOG_KEYWORDS
+        .split_whitespace()
+        .any(|keyword| keyword == name)
+}
+
+/// Split Verilog source into tokens with their line, dropping comments
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut index = 0;
+    let take_while = |index: &mut usize, keep: &dyn Fn(char) -> bool| {
+        let start = *index;
+        while *index < chars.len() && keep(chars[*index]) {
+            *index += 1;
+        }
+        chars[start..*index].iter().collect::<String>()
+    };
+
+    while index < chars.len() {
+        let c = chars[index];
+        let next = chars.get(index + 1).copied();
+        if c == '\n' {
+            line += 1;
+            index += 1;
+        } else if c.is_whitespace() {
+            index += 1;
+        } else if c == '/' && next == Some('/') {
+            take_while(&mut index, &|c| c != '\n');
+        } else if c == '/' && next == Some('*') {
+            let start = line;
+            index += 2;
+            loop {
+                match chars.get(index) {
+                    None => return Err(ParseError::new(start, "unterminated comment")),
+                    Some('*') if chars.get(index + 1) == Some(&'/') => break,
+                    Some('\n') => line += 1,
+                    _ => {}
+                }
+                index += 1;
+            }
+            index += 2;
+        } else if c == '\\' {
+            index += 1;
+            let name = take_while(&mut index, &|c| !c.is_whitespace());
+            if name.is_empty() {
+                return Err(ParseError::new(line, "empty escaped identifier"));
+            }
+            tokens.push((Token::Escaped(name), line));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let name = take_while(&mut index, &|c| {
+                c.is_ascii_alphanumeric() || c == '_' || c == '$'
+            });
+            tokens.push((Token::Name(name), line));
+        } else if c.is_ascii_digit() {
+            let literal = take_while(&mut index, &|c| c.is_ascii_alphanumeric() || c == '\'');
+            let value = match literal.as_str() {
+                "0" | "1'b0" | "1'B0" => false,
+                "1" | "1'b1" | "1'B1" => true,
+                _ => {
+                    return Err(ParseError::new(
+                        line,
+                        format!("unsupported constant '{}', expected 1'b0 or 1'b1", literal),
+                    ));
+                }
+            };
+            tokens.push((Token::Constant(value), line));
+        } else if c == '[' {
+            return Err(ParseError::new(line, "vectors are not supported"));
+        } else {
+            // Punctuation the parser has no use for is reported where it appears
+            tokens.push((Token::Symbol(c), line));
+            index += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// Reads modules from the tokens of a Verilog file
+struct VerilogParser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    end_line: usize, // Reported for errors at the end of the file
+}
+
+impl VerilogParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn peek_is(&self, symbol: char) -> bool {
+        self.peek() == Some(&Token::Symbol(symbol))
+    }
+
+    fn peek_is_name(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(Token::Name(found)) if found == name)
+    }
+
+    /// Line of the next token
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end_line, |&(_, line)| line)
+    }
+
+    fn next(&mut self) -> Result<Token, ParseError> {
+        let (token, _) = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| ParseError::new(self.end_line, "unexpected end of file"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), ParseError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Symbol(found) if found == symbol => Ok(()),
+            token => Err(ParseError::new(
+                line,
+                format!("expected '{}' but found {}", symbol, token),
+            )),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Name(name) if !is_keyword(&name) => Ok(name),
+            Token::Escaped(name) => Ok(name),
+            token => Err(ParseError::new(
+                line,
+                format!("expected a name but found {}", token),
+            )),
+        }
+    }
+
+    fn net(&mut self) -> Result<Net, ParseError> {
+        match self.peek() {
+            Some(&Token::Constant(value)) => {
+                self.position += 1;
+                Ok(Net::Constant(value))
+            }
+            _ => Ok(Net::Wire(self.name()?)),
+        }
+    }
+
+    /// Names separated by commas up to a semicolon, with their line
+    fn declared_names(&mut self) -> Result<Vec<(String, usize)>, ParseError> {
+        let mut names = Vec::new();
+        loop {
+            let line = self.line();
+            names.push((self.name()?, line));
+            if self.peek_is(';') {
+                self.position += 1;
+                return Ok(names);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn module(&mut self) -> Result<Module, ParseError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Name(keyword) if keyword == "module" => {}
+            token => {
+                return Err(ParseError::new(
+                    line,
+                    format!("expected 'module' but found {}", token),
+                ));
+            }
+        }
+        let mut module = Module {
+            name: self.name()?,
+            line,
+            ports: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            constants: Vec::new(),
+            items: Vec::new(),
+        };
+        let mut directions: Vec<(String, bool, usize)> = Vec::new(); // Port, is input, line
+
+        // Ports, possibly declared in the header
+        if self.peek_is('(') {
+            self.position += 1;
+            let mut direction = None;
+            while !self.peek_is(')') {
+                if self.peek_is_name("input") || self.peek_is_name("output") {
+                    direction = Some(self.peek_is_name("input"));
+                    self.position += 1;
+                    if self.peek_is_name("wire") {
+                        self.position += 1;
+                    }
+                }
+                let port_line = self.line();
+                let port = self.name()?;
+                if let Some(input) = direction {
+                    directions.push((port.clone(), input, port_line));
+                }
+                module.ports.push(port);
+                if !self.peek_is(')') {
+                    self.expect(',')?;
+                }
+            }
+            self.position += 1;
+        }
+        self.expect(';')?;
+
+        loop {
+            let line = self.line();
+            let token = self.next()?;
+            let keyword = match &token {
+                Token::Name(name) if is_keyword(name) => name.as_str(),
+                Token::Name(name) | Token::Escaped(name) => {
+                    let item = self.instance(name.clone(), line)?;
+                    module.items.push(item);
+                    continue;
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        line,
+                        format!("expected a statement but found {}", token),
+                    ));
+                }
+            };
+            match keyword {
+                "endmodule" => break,
+                "input" | "output" => {
+                    let input = keyword == "input";
+                    if self.peek_is_name("wire") {
+                        self.position += 1;
+                    }
+                    for (name, line) in self.declared_names()? {
+                        directions.push((name, input, line));
+                    }
+                }
+                "wire" => {
+                    self.declared_names()?;
+                }
+                "supply0" | "supply1" => {
+                    let value = keyword == "supply1";
+                    for (name, line) in self.declared_names()? {
+                        module.constants.push((name, value, line));
+                    }
+                }
+                _ if PRIMITIVES.contains(&keyword) => {
+                    let item = self.primitive(keyword, line)?;
+                    module.items.push(item);
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        line,
+                        format!("unsupported statement '{}'", keyword),
+                    ));
+                }
+            }
+        }
+
+        for (name, input, line) in directions {
+            if !module.ports.contains(&name) {
+                return Err(ParseError::new(
+                    line,
+                    format!("'{}' is not a port of module '{}'", name, module.name),
+                ));
+            }
+            if module
+                .inputs
+                .iter()
+                .chain(&module.outputs)
+                .any(|(port, _)| *port == name)
+            {
+                return Err(ParseError::new(
+                    line,
+                    format!("port '{}' is declared twice", name),
+                ));
+            }
+            let ports = if input {
+                &mut module.inputs
+            } else {
+                &mut module.outputs
+            };
+            ports.push((name, line));
+        }
+        for port in &module.ports {
+            if !module
+                .inputs
+                .iter()
+                .chain(&module.outputs)
+                .any(|(name, _)| name == port)
+            {
+                return Err(ParseError::new(
+                    module.line,
+                    format!("port '{}' is not declared as input or output", port),
+                ));
+            }
+        }
+        // Keep ports in header order
+        let position = |name: &String| module.ports.iter().position(|port| port == name);
+        module.inputs.sort_by_key(|(name, _)| position(name));
+        module.outputs.sort_by_key(|(name, _)| position(name));
+        Ok(module)
+    }
+
+    /// A primitive gate: `and [name] (output, input, ...);`
+    fn primitive(&mut self, keyword: &str, line: usize) -> Result<Item, ParseError> {
+        let name = if self.peek_is('(') {
+            None
+        } else {
+            Some(self.name()?)
+        };
+        self.expect('(')?;
+        let mut terminals = vec![self.net()?];
+        while self.peek_is(',') {
+            self.position += 1;
+            terminals.push(self.net()?);
+        }
+        self.expect(')')?;
+        self.expect(';')?;
+
+        let Net::Wire(output) = terminals.remove(0) else {
+            return Err(ParseError::new(
+                line,
+                format!("the output of '{}' must be a wire", keyword),
+            ));
+        };
+        let inputs = terminals;
+        let single = matches!(keyword, "not" | "buf");
+        if single && inputs.len() != 1 {
+            return Err(ParseError::new(
+                line,
+                format!("'{}' takes one input but got {}", keyword, inputs.len()),
+            ));
+        }
+        if !single && !(2..=MAX_GATE_INPUTS).contains(&inputs.len()) {
+            return Err(ParseError::new(
+                line,
+                format!(
+                    "'{}' takes 2 to {} inputs but got {}",
+                    keyword,
+                    MAX_GATE_INPUTS,
+                    inputs.len()
+                ),
+            ));
+        }
+
+        let arity = inputs.len();
+        let table = match keyword {
+            "buf" => {
+                return Ok(Item::Buffer {
+                    output,
+                    input: inputs.into_iter().next().unwrap(),
+                    line,
+                });
+            }
+            "not" => inverted(TruthTable::and(1)),
+            "and" => TruthTable::and(arity),
+            "or" => TruthTable::or(arity),
+            "xor" => TruthTable::parity(arity),
+            "nand" => inverted(TruthTable::and(arity)),
+            "nor" => inverted(TruthTable::or(arity)),
+            _ => inverted(TruthTable::parity(arity)),
+        };
+        Ok(Item::Gate {
+            name: name.unwrap_or_else(|| output.clone()),
+            gate_type: gate_type(table),
+            output,
+            inputs,
+            line,
+        })
+    }
+
+    /// An instance of another module: `module name (.port(net), ...);` or with positional nets
+    fn instance(&mut self, module: String, line: usize) -> Result<Item, ParseError> {
+        let name = self.name()?;
+        self.expect('(')?;
+        let connections = if self.peek_is('.') {
+            let mut named = Vec::new();
+            loop {
+                let line = self.line();
+                self.expect('.')?;
+                let port = self.name()?;
+                self.expect('(')?;
+                named.push((port, self.net()?, line));
+                self.expect(')')?;
+                if self.peek_is(')') {
+                    break;
+                }
+                self.expect(',')?;
+            }
+            Connections::Named(named)
+        } else {
+            let mut positional = Vec::new();
+            while !self.peek_is(')') {
+                positional.push(self.net()?);
+                if !self.peek_is(')') {
+                    self.expect(',')?;
+                }
+            }
+            Connections::Positional(positional)
+        };
+        self.expect(')')?;
+        self.expect(';')?;
+        Ok(Item::Instance {
+            name,
+            module,
+            connections,
+            line,
+        })
+    }
+}
@@ -0,0 +1,253 @@
+use std::io;
+
+use crate::faults::{FaultInjection, child_paths};
+use crate::gate_factory::{GateFactory, GateSource};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate, to_signal};
+use crate::half_adder::{HalfAdder, HalfAdderDetail};
+use crate::netlist::{Netlist, NetlistBuilder};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+
+/// Incrementer result with the raw output of every gate
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncrementerDetail {
+    pub inputs: Vec<GateOutput>,      // Input XOR decrement, LSB first
+    pub stages: Vec<HalfAdderDetail>, // Half adder chain adding 1, LSB first
+    pub outputs: Vec<GateOutput>,     // Sum XOR decrement, LSB first
+}
+
+impl IncrementerDetail {
+    /// Thresholded output bits, LSB first
+    pub fn result_bits(&self) -> Vec<bool> {
+        self.outputs.iter().map(|output| output.bit).collect()
+    }
+
+    /// Carry out of the last half adder: the count wrapped around
+    pub fn carry(&self) -> bool {
+        self.stages.last().is_some_and(|stage| stage.carry.bit)
+    }
+
+    /// Carry out of every half adder, LSB first
+    pub fn carries(&self) -> Vec<bool> {
+        self.stages.iter().map(|stage| stage.carry.bit).collect()
+    }
+
+    /// Smallest distance from the decision threshold across every gate
+    pub fn min_margin(&self) -> f64 {
+        let stages = self
+            .stages
+            .iter()
+            .map(HalfAdderDetail::min_margin)
+            .fold(f64::INFINITY, f64::min);
+        self.inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(|output| output.margin)
+            .fold(stages, f64::min)
+    }
+}
+
+/// An incrementer/decrementer built from a chain of neural half adders
+///
+/// Each half adder adds the carry of the one below, starting with a carry
+/// in of 1. Decrementing uses `x - 1 = NOT(NOT x + 1)`: XOR gates invert the
+/// input and the output when the decrement line is set.
+#[derive(Debug)]
+pub struct Incrementer<G: Gate = LogicGate> {
+    inputs: Vec<G>,                 // Input XOR decrement, one per bit
+    half_adders: Vec<HalfAdder<G>>, // Carry chain, LSB first
+    outputs: Vec<G>,                // Sum XOR decrement, one per bit
+}
+
+impl Incrementer {
+    /// Create an incrementer of the given width with trained neural gates (shared)
+    pub fn new(width: usize) -> Self {
+        Self::with_factory(&mut GateFactory::shared(), width)
+    }
+}
+
+impl<G: Gate> Incrementer<G> {
+    /// Create an incrementer using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S, width: usize) -> Self {
+        assert!(width > 0, "incrementer needs at least one bit");
+
+        Incrementer {
+            inputs: (0..width).map(|_| factory.gate(GateType::XOR)).collect(),
+            half_adders: (0..width)
+                .map(|_| HalfAdder::with_factory(factory))
+                .collect(),
+            outputs: (0..width).map(|_| factory.gate(GateType::XOR)).collect(),
+        }
+    }
+
+    /// Number of bits counted
+    pub fn width(&self) -> usize {
+        self.half_adders.len()
+    }
+
+    /// Number of gates: the input and output XORs and the half adder chain
+    pub fn gate_count(&self) -> usize {
+        let chain: usize = self.half_adders.iter().map(HalfAdder::gate_count).sum();
+        self.inputs.len() + chain + self.outputs.len()
+    }
+
+    /// Gate-level description: inputs `value0..`, `decrement`, outputs `out0..`, `carry`
+    pub fn to_netlist(&self) -> Netlist {
+        let mut builder = NetlistBuilder::new("incrementer");
+        let bits = builder.inputs("value", self.width());
+        let decrement = builder.input("decrement");
+
+        let mut carry = builder.constant(true);
+        let mut outputs = Vec::new();
+        for (bit, ((input, half_adder), output)) in self
+            .inputs
+            .iter()
+            .zip(&self.half_adders)
+            .zip(&self.outputs)
+            .enumerate()
+        {
+            let inverted = builder.gate(
+                &format!("in{}", bit),
+                input.gate_type().clone(),
+                &[bits[bit], decrement],
+            );
+            let stage = builder.instance(
+                &format!("half_adder{}", bit),
+                &half_adder.to_netlist(),
+                &[inverted, carry],
+            );
+            carry = stage[1];
+            outputs.push(builder.gate(
+                &format!("out{}", bit),
+                output.gate_type().clone(),
+                &[stage[0], decrement],
+            ));
+        }
+
+        builder.outputs("out", &outputs);
+        builder.output("carry", carry);
+        builder.build().expect("incrementer netlist is well formed")
+    }
+
+    /// Add 1 to the bits (LSB first), or subtract 1 when `decrement` is set
+    pub fn compute(&self, bits: &[bool], decrement: bool) -> Vec<bool> {
+        self.compute_detailed(bits, decrement).result_bits()
+    }
+
+    /// Count up or down by one keeping the raw gate activations
+    pub fn compute_detailed(&self, bits: &[bool], decrement: bool) -> IncrementerDetail {
+        assert_eq!(bits.len(), self.width(), "input has the wrong width");
+
+        let inputs: Vec<GateOutput> = self
+            .inputs
+            .iter()
+            .zip(bits)
+            .map(|(gate, &bit)| gate.compute_detailed(vec![to_signal(bit), to_signal(decrement)]))
+            .collect();
+
+        // Carry in of the first half adder is the 1 being added
+        let mut carry = true;
+        let stages: Vec<HalfAdderDetail> = self
+            .half_adders
+            .iter()
+            .zip(&inputs)
+            .map(|(half_adder, input)| {
+                let stage = half_adder.compute_detailed(input.bit, carry);
+                carry = stage.carry.bit;
+                stage
+            })
+            .collect();
+
+        let outputs = self
+            .outputs
+            .iter()
+            .zip(&stages)
+            .map(|(gate, stage)| {
+                gate.compute_detailed(vec![to_signal(stage.sum.bit), to_signal(decrement)])
+            })
+            .collect();
+
+        IncrementerDetail {
+            inputs,
+            stages,
+            outputs,
+        }
+    }
+}
+
+impl Persist for Incrementer {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line(&format!("incrementer {}", self.width()))?;
+        for gate in &self.inputs {
+            gate.write_weights(writer)?;
+        }
+        for half_adder in &self.half_adders {
+            half_adder.write_weights(writer)?;
+        }
+        for gate in &self.outputs {
+            gate.write_weights(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        let fields = reader.tagged("incrementer")?;
+        let width: usize = match fields.as_slice() {
+            [width] => width
+                .parse()
+                .ok()
+                .filter(|&width| width > 0)
+                .ok_or_else(|| reader.error("invalid incrementer width"))?,
+            _ => return Err(reader.error("expected an incrementer width")),
+        };
+
+        let inputs = (0..width)
+            .map(|_| LogicGate::read_typed(reader, &GateType::XOR))
+            .collect::<io::Result<_>>()?;
+        let half_adders = (0..width)
+            .map(|_| HalfAdder::read_weights(reader))
+            .collect::<io::Result<_>>()?;
+        let outputs = (0..width)
+            .map(|_| LogicGate::read_typed(reader, &GateType::XOR))
+            .collect::<io::Result<_>>()?;
+        Ok(Incrementer {
+            inputs,
+            half_adders,
+            outputs,
+        })
+    }
+}
+
+/// Gates are addressed as `in0`, `half_adder0.xor`, `half_adder0.and`, `out0`, ...
+impl FaultInjection for Incrementer {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        let (name, rest) = path.split_first()?;
+        if let Some(index) = name.strip_prefix("half_adder") {
+            return self
+                .half_adders
+                .get_mut(index.parse::<usize>().ok()?)?
+                .gate_mut(rest);
+        }
+        let (gates, index) = if let Some(index) = name.strip_prefix("in") {
+            (&mut self.inputs, index)
+        } else if let Some(index) = name.strip_prefix("out") {
+            (&mut self.outputs, index)
+        } else {
+            return None;
+        };
+        gates.get_mut(index.parse::<usize>().ok()?)?.gate_mut(rest)
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for (index, gate) in self.inputs.iter().enumerate() {
+            paths.extend(child_paths(&format!("in{}", index), gate));
+        }
+        for (index, half_adder) in self.half_adders.iter().enumerate() {
+            paths.extend(child_paths(&format!("half_adder{}", index), half_adder));
+        }
+        for (index, gate) in self.outputs.iter().enumerate() {
+            paths.extend(child_paths(&format!("out{}", index), gate));
+        }
+        paths
+    }
+}
@@ -1,6 +1,25 @@
+pub mod network;
+pub mod training;
+pub mod persistence;
+pub mod faults;
 pub mod gates;
+pub mod gate_factory;
+pub mod netlist;
+pub mod circuit;
+pub mod export;
+pub mod import;
 pub mod half_adder;
 pub mod full_adder;
+pub mod carry_lookahead;
+pub mod shifter;
+pub mod multiplier;
+pub mod divider;
+pub mod bcd;
+pub mod incrementer;
+pub mod self_test;
+pub mod mux;
+pub mod decoder;
+pub mod alu_detail;
 pub mod alu;
 pub mod memory;
 pub mod registers;
@@ -28,7 +28,13 @@ fn main() {
     demo_large_arithmetic(100, 55, "ADD", "100 + 55 = 155");
     demo_large_arithmetic(200, 50, "SUB", "200 - 50 = 150");
     demo_large_arithmetic(128, 64, "SUB", "128 - 64 = 64");
-    // demo_large_arithmetic(500, 123, "ADD", "500 + 123 = 623"); TODO: Fix large arithmetic (returning 367 instead of 623)
+    demo_large_arithmetic(500, 123, "ADD", "500 + 123 = 623");
+
+    println!("+------------------+-----------+-----------+-----------+-----------------+");
+
+    // Decimal arithmetic, one BCD digit per nibble
+    demo_decimal_arithmetic(456, 789, "ADD", "Decimal carry");
+    demo_decimal_arithmetic(420, 175, "SUB", "Decimal borrow");
 
     println!("+------------------+-----------+-----------+-----------+-----------------+");
     println!("\n✓ Neural CPU operations completed!");
@@ -65,7 +71,7 @@ fn demo_operation(a: u8, b: u8, op: &str, note: &str) {
     cpu.run();
 
     let result = cpu.memory.read(0xE);
-    let carry = cpu.registers.carry_flag;
+    let carry = cpu.registers.status.carry();
 
     if carry && op == "ADD" {
         let full_result = 16 + result;
@@ -97,98 +103,87 @@ fn demo_operation(a: u8, b: u8, op: &str, note: &str) {
 fn demo_large_arithmetic(a: u16, b: u16, op: &str, note: &str) {
     let mut cpu = CPU::new();
 
-    // Split numbers into high and low nibbles
-    let a_high = ((a >> 4) & 0x0F) as u8;
-    let a_low = (a & 0x0F) as u8;
-    let b_high = ((b >> 4) & 0x0F) as u8;
-    let b_low = (b & 0x0F) as u8;
+    // One nibble per run: memory[F] = memory[D] op memory[E]. The first nibble
+    // uses ADD/SUB, every later one ADC/SBC with the carry flag left by the
+    // previous run, so the carries are chained by the neural CPU itself.
+    let mut result = 0u16;
+    for nibble in 0..4 {
+        let shift = nibble * 4;
+        let opcode = match (op, nibble) {
+            ("ADD", 0) => 0x3, // ADD
+            ("ADD", _) => 0xA, // ADC
+            ("SUB", 0) => 0x4, // SUB
+            ("SUB", _) => 0xB, // SBC
+            _ => 0xF,          // HALT
+        };
+        let program = vec![
+            0x1, 0xD, // LOAD D (nibble of a)
+            opcode, 0xE, // ADD/ADC/SUB/SBC E (nibble of b)
+            0x2, 0xF, // STORE F (nibble of the result)
+            0xF, // HALT
+        ];
 
-    // Store operands in memory
-    cpu.memory.write(0xA, a_high); // A high nibble
-    cpu.memory.write(0xB, a_low); // A low nibble
-    cpu.memory.write(0xC, b_high); // B high nibble
-    cpu.memory.write(0xD, b_low); // B low nibble
+        cpu.load_program(&program);
+        cpu.memory.write(0xD, ((a >> shift) & 0x0F) as u8);
+        cpu.memory.write(0xE, ((b >> shift) & 0x0F) as u8);
+        cpu.restart();
+        cpu.run();
 
-    let program = match op {
-        "ADD" => vec![
-            // Add low nibbles first
-            0x1, 0xB, // LOAD A_low
-            0x3, 0xD, // ADD B_low
-            0x2, 0xF, // STORE result_low
-            // Add high nibbles with potential carry
-            0x1, 0xA, // LOAD A_high
-            0x3, 0xC, // ADD B_high
-            0x2, 0xE, // STORE result_high (may have carry)
-            0xF, // HALT
-        ],
-        "SUB" => vec![
-            // Subtract low nibbles first
-            0x1, 0xB, // LOAD A_low
-            0x4, 0xD, // SUB B_low
-            0x2, 0xF, // STORE result_low
-            // Subtract high nibbles
-            0x1, 0xA, // LOAD A_high
-            0x4, 0xC, // SUB B_high
-            0x2, 0xE, // STORE result_high
-            0xF, // HALT
-        ],
-        _ => vec![0xF], // HALT
-    };
+        result |= (cpu.memory.read(0xF) as u16) << shift;
+    }
 
-    cpu.load_program(&program);
-    cpu.run();
+    println!(
+        "| {:3} {} {:3}        |   0x{:03X}   |   0x{:03X}   |    {:3}    | {:15} |",
+        a,
+        if op == "ADD" { "+" } else { "-" },
+        b,
+        a,
+        b,
+        result,
+        note
+    );
+}
 
-    // Read results (for debugging - we calculate the correct result manually)
-    let _result_high = cpu.memory.read(0xE);
-    let _result_low = cpu.memory.read(0xF);
-
-    // For proper multi-precision arithmetic, we need to simulate the carry
-    // Since our CPU does each nibble independently, we need to recalculate with carry
-    let (low_result, low_carry) = match op {
-        "ADD" => {
-            let sum = a_low + b_low;
-            (sum & 0x0F, sum > 15)
-        }
-        "SUB" => {
-            if a_low >= b_low {
-                (a_low - b_low, false)
-            } else {
-                (16 + a_low - b_low, true) // borrow
-            }
-        }
-        _ => (0, false),
-    };
+fn demo_decimal_arithmetic(a: u16, b: u16, op: &str, note: &str) {
+    let mut cpu = CPU::new();
 
-    let high_result = match op {
-        "ADD" => {
-            let mut sum = a_high + b_high;
-            if low_carry {
-                sum += 1;
-            } // Add carry from low nibble
-            sum
-        }
-        "SUB" => {
-            let mut diff = a_high as i16 - b_high as i16;
-            if low_carry {
-                diff -= 1;
-            } // Subtract borrow
-            (if diff < 0 { diff + 16 } else { diff }) as u8
-        }
-        _ => 0,
-    };
+    // One decimal digit per run: memory[F] = memory[D] op memory[E], then DAA
+    // corrects the binary nibble back to a decimal digit and a decimal carry.
+    let mut result = 0u16;
+    for digit in 0..4 {
+        let scale = 10u16.pow(digit);
+        let opcode = match (op, digit) {
+            ("ADD", 0) => 0x3, // ADD
+            ("ADD", _) => 0xA, // ADC
+            ("SUB", 0) => 0x4, // SUB
+            ("SUB", _) => 0xB, // SBC
+            _ => 0xF,          // HALT
+        };
+        let program = vec![
+            0x1, 0xD, // LOAD D (digit of a)
+            opcode, 0xE, // ADD/ADC/SUB/SBC E (digit of b)
+            0xE, 0x7, // DAA
+            0x2, 0xF, // STORE F (digit of the result)
+            0xF, // HALT
+        ];
 
-    let actual = (high_result as u16) << 4 | (low_result as u16);
+        cpu.load_program(&program);
+        cpu.memory.write(0xD, (a / scale % 10) as u8);
+        cpu.memory.write(0xE, (b / scale % 10) as u8);
+        cpu.restart();
+        cpu.run();
+
+        result += cpu.memory.read(0xF) as u16 * scale;
+    }
 
     println!(
-        "| {:3} {} {:3}         |  {:2},{:2}     |  {:2},{:2}     |    {:3}    | {:15} |",
+        "| BCD {:3} {} {:3}    |    {:3}    |    {:3}    |   {:4}    | {:15} |",
         a,
         if op == "ADD" { "+" } else { "-" },
         b,
-        a_high,
-        a_low,
-        b_high,
-        b_low,
-        actual,
+        a,
+        b,
+        result,
         note
     );
 }
@@ -1,3 +1,10 @@
+use crate::decoder::{Decoder, Demux};
+use crate::faults::{FaultInjection, child_paths};
+use crate::gates::LogicGate;
+
+/// Address bits: 16 cells
+const ADDRESS_BITS: usize = 4;
+
 /// 4-bit Memory Cell that stores a single 4-bit value
 #[derive(Debug, Clone, Copy)]
 pub struct MemoryCell {
@@ -35,9 +42,16 @@ impl Default for MemoryCell {
 }
 
 /// 16 x 4-bit Memory Bank for the neural computer
+///
+/// Addresses go through neural gates: a decoder picks the cell a read comes
+/// from, and a demultiplexer routes the write strobe to the cell a write
+/// goes to. A faulty line can select no cell (reads give 0) or several
+/// (reads OR them together, writes reach all of them).
 #[derive(Debug)]
 pub struct Memory {
     cells: [MemoryCell; 16],
+    read_select: Decoder, // One line per cell read
+    write_select: Demux,  // Write strobe to the addressed cell
 }
 
 impl Memory {
@@ -45,19 +59,30 @@ impl Memory {
     pub fn new() -> Self {
         Memory {
             cells: [MemoryCell::new(); 16],
+            read_select: Decoder::new(ADDRESS_BITS),
+            write_select: Demux::new(ADDRESS_BITS),
         }
     }
 
     /// Read from memory at given address (4-bit address: 0-15)
     pub fn read(&self, address: u8) -> u8 {
-        let addr = (address & 0x0F) as usize; // Ensure 4-bit address
-        self.cells[addr].read()
+        let lines = self.read_select.decode((address & 0x0F) as usize); // Ensure 4-bit address
+        self.cells
+            .iter()
+            .zip(lines)
+            .filter(|(_, selected)| *selected)
+            .fold(0, |value, (cell, _)| value | cell.read())
     }
 
     /// Write to memory at given address
     pub fn write(&mut self, address: u8, value: u8) {
-        let addr = (address & 0x0F) as usize; // Ensure 4-bit address
-        self.cells[addr].write(value);
+        let select: Vec<bool> = (0..ADDRESS_BITS).map(|bit| address >> bit & 1 == 1).collect();
+        let lines = self.write_select.compute(&select, true);
+        for (cell, selected) in self.cells.iter_mut().zip(lines) {
+            if selected {
+                cell.write(value);
+            }
+        }
     }
 
     /// Load program data into memory starting at address 0
@@ -87,6 +112,23 @@ impl Memory {
     }
 }
 
+/// Gates are addressed as `read_select.out3`, `write_select.decoder.not0`, ...
+impl FaultInjection for Memory {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        match path.split_first()? {
+            (&"read_select", rest) => self.read_select.gate_mut(rest),
+            (&"write_select", rest) => self.write_select.gate_mut(rest),
+            _ => None,
+        }
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = child_paths("read_select", &self.read_select);
+        paths.extend(child_paths("write_select", &self.write_select));
+        paths
+    }
+}
+
 impl Default for Memory {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,295 @@
+use std::io;
+
+use crate::faults::{FaultInjection, child_paths};
+use crate::full_adder::{FullAdder, FullAdderDetail};
+use crate::gate_factory::{GateFactory, GateSource};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate, to_signal};
+use crate::netlist::{Netlist, NetlistBuilder, Wire};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+
+/// Array multiplier result with the raw output of every gate
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiplierDetail {
+    pub product: Vec<bool>, // Twice the operand width, LSB first
+    pub partial_products: Vec<Vec<GateOutput>>, // A(j) AND B(i), one row per bit of B
+    pub adders: Vec<Vec<FullAdderDetail>>, // Ripple-carry rows for bits 1.. of B
+}
+
+impl MultiplierDetail {
+    /// Low half of the product (same width as the operands), LSB first
+    pub fn low_bits(&self) -> &[bool] {
+        &self.product[..self.product.len() / 2]
+    }
+
+    /// High half of the product, LSB first
+    pub fn high_bits(&self) -> &[bool] {
+        &self.product[self.product.len() / 2..]
+    }
+
+    /// Smallest distance from the decision threshold across every gate
+    pub fn min_margin(&self) -> f64 {
+        let adders = self
+            .adders
+            .iter()
+            .flatten()
+            .map(FullAdderDetail::min_margin)
+            .fold(f64::INFINITY, f64::min);
+        self.partial_products
+            .iter()
+            .flatten()
+            .map(|output| output.margin)
+            .fold(adders, f64::min)
+    }
+}
+
+/// An array multiplier built from neural AND gates and full adders
+///
+/// Every bit pair A(j) AND B(i) forms a partial product. Row i of partial
+/// products is added to the running sum shifted down by one bit, so each row
+/// of full adders settles one more product bit; the last row gives the high
+/// half of the product.
+#[derive(Debug)]
+pub struct ArrayMultiplier<G: Gate = LogicGate> {
+    and_gates: Vec<Vec<G>>,         // Partial products, one row per bit of B
+    adders: Vec<Vec<FullAdder<G>>>, // Ripple-carry rows adding partial product rows 1..
+}
+
+impl ArrayMultiplier {
+    /// Create a multiplier of the given operand width with trained neural gates (shared)
+    pub fn new(width: usize) -> Self {
+        Self::with_factory(&mut GateFactory::shared(), width)
+    }
+}
+
+impl<G: Gate> ArrayMultiplier<G> {
+    /// Create a multiplier using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S, width: usize) -> Self {
+        assert!(width > 0, "multiplier needs at least one bit");
+
+        let and_gates = (0..width)
+            .map(|_| (0..width).map(|_| factory.gate(GateType::AND)).collect())
+            .collect();
+        let adders = (1..width)
+            .map(|_| {
+                (0..width)
+                    .map(|_| FullAdder::with_factory(factory))
+                    .collect()
+            })
+            .collect();
+
+        ArrayMultiplier { and_gates, adders }
+    }
+
+    /// Number of bits in each operand
+    pub fn width(&self) -> usize {
+        self.and_gates.len()
+    }
+
+    /// Number of gates in the partial product ANDs and the adder rows
+    pub fn gate_count(&self) -> usize {
+        let and_gates: usize = self.and_gates.iter().map(Vec::len).sum();
+        let adder_gates: usize = self
+            .adders
+            .iter()
+            .flatten()
+            .map(FullAdder::gate_count)
+            .sum();
+        and_gates + adder_gates
+    }
+
+    /// Gate-level description: inputs `a0..`, `b0..`, outputs `product0..` (twice as wide)
+    pub fn to_netlist(&self) -> Netlist {
+        let width = self.width();
+        let mut builder = NetlistBuilder::new("multiplier");
+        let a = builder.inputs("a", width);
+        let b = builder.inputs("b", width);
+        let zero = builder.constant(false);
+
+        let partial_products: Vec<Vec<Wire>> = self
+            .and_gates
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(j, gate)| {
+                        let name = format!("and{}_{}", i, j);
+                        builder.gate(&name, gate.gate_type().clone(), &[a[j], b[i]])
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Same running sum as `compute_detailed`, one settled product bit per row
+        let mut product = vec![partial_products[0][0]];
+        let mut running = partial_products[0][1..].to_vec();
+        running.push(zero);
+        for (row, (adders, partials)) in self.adders.iter().zip(&partial_products[1..]).enumerate()
+        {
+            let mut carry = zero;
+            let mut sums = Vec::new();
+            for (j, adder) in adders.iter().enumerate() {
+                let stage = builder.instance(
+                    &format!("adder{}_{}", row + 1, j),
+                    &adder.to_netlist(),
+                    &[running[j], partials[j], carry],
+                );
+                sums.push(stage[0]);
+                carry = stage[1];
+            }
+            product.push(sums[0]);
+            running = sums[1..].to_vec();
+            running.push(carry);
+        }
+        product.extend(running.into_iter().take(2 * width - product.len()));
+
+        builder.outputs("product", &product);
+        builder.build().expect("multiplier netlist is well formed")
+    }
+
+    /// Multiply two numbers given as bits (LSB first) into a product twice as wide
+    pub fn compute(&self, a: &[bool], b: &[bool]) -> Vec<bool> {
+        self.compute_detailed(a, b).product
+    }
+
+    /// Multiply two numbers keeping the raw gate activations
+    pub fn compute_detailed(&self, a: &[bool], b: &[bool]) -> MultiplierDetail {
+        assert_eq!(a.len(), self.width(), "operand A has the wrong width");
+        assert_eq!(b.len(), self.width(), "operand B has the wrong width");
+        let width = self.width();
+
+        // Partial products: row i is A shifted left by i, masked by B(i)
+        let partial_products: Vec<Vec<GateOutput>> = self
+            .and_gates
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(j, gate)| gate.compute_detailed(vec![to_signal(a[j]), to_signal(b[i])]))
+                    .collect()
+            })
+            .collect();
+
+        // Row 0 settles bit 0; the rest of it waits above the next row
+        let mut product = vec![partial_products[0][0].bit];
+        let mut running: Vec<bool> = partial_products[0][1..]
+            .iter()
+            .map(|output| output.bit)
+            .collect();
+        running.push(false);
+
+        let adders = self
+            .adders
+            .iter()
+            .zip(&partial_products[1..])
+            .map(|(row, partials)| {
+                let mut carry = false;
+                let stages: Vec<FullAdderDetail> = row
+                    .iter()
+                    .enumerate()
+                    .map(|(j, adder)| {
+                        let stage = adder.compute_detailed(running[j], partials[j].bit, carry);
+                        carry = stage.carry.bit;
+                        stage
+                    })
+                    .collect();
+
+                // The lowest sum bit is final; the others and the carry move down one bit
+                product.push(stages[0].sum.bit);
+                running = stages[1..].iter().map(|stage| stage.sum.bit).collect();
+                running.push(carry);
+                stages
+            })
+            .collect();
+
+        // The last running sum is the high half
+        product.extend(running.into_iter().take(2 * width - product.len()));
+
+        MultiplierDetail {
+            product,
+            partial_products,
+            adders,
+        }
+    }
+}
+
+impl Persist for ArrayMultiplier {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line(&format!("multiplier {}", self.width()))?;
+        for gate in self.and_gates.iter().flatten() {
+            gate.write_weights(writer)?;
+        }
+        for adder in self.adders.iter().flatten() {
+            adder.write_weights(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        let fields = reader.tagged("multiplier")?;
+        let width: usize = match fields.as_slice() {
+            [width] => width
+                .parse()
+                .ok()
+                .filter(|&width| width > 0)
+                .ok_or_else(|| reader.error("invalid multiplier width"))?,
+            _ => return Err(reader.error("expected a multiplier width")),
+        };
+
+        let and_gates = (0..width)
+            .map(|_| {
+                (0..width)
+                    .map(|_| LogicGate::read_typed(reader, &GateType::AND))
+                    .collect()
+            })
+            .collect::<io::Result<_>>()?;
+        let adders = (1..width)
+            .map(|_| {
+                (0..width)
+                    .map(|_| FullAdder::read_weights(reader))
+                    .collect()
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(ArrayMultiplier { and_gates, adders })
+    }
+}
+
+/// Gates are addressed as `and2_1` for A(1) AND B(2), and `adder2_1` (then the full adder path)
+/// for bit 1 of the adder row adding B(2)
+impl FaultInjection for ArrayMultiplier {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        let (name, rest) = path.split_first()?;
+        if let Some(indices) = name.strip_prefix("and") {
+            let (row, bit) = indices.split_once('_')?;
+            self.and_gates
+                .get_mut(row.parse::<usize>().ok()?)?
+                .get_mut(bit.parse::<usize>().ok()?)?
+                .gate_mut(rest)
+        } else if let Some(indices) = name.strip_prefix("adder") {
+            let (row, bit) = indices.split_once('_')?;
+            self.adders
+                .get_mut(row.parse::<usize>().ok()?.checked_sub(1)?)?
+                .get_mut(bit.parse::<usize>().ok()?)?
+                .gate_mut(rest)
+        } else {
+            None
+        }
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for (row, gates) in self.and_gates.iter().enumerate() {
+            for (bit, gate) in gates.iter().enumerate() {
+                paths.extend(child_paths(&format!("and{}_{}", row, bit), gate));
+            }
+        }
+        for (row, adders) in self.adders.iter().enumerate() {
+            for (bit, adder) in adders.iter().enumerate() {
+                paths.extend(child_paths(&format!("adder{}_{}", row + 1, bit), adder));
+            }
+        }
+        paths
+    }
+}
@@ -0,0 +1,263 @@
+use std::io;
+
+use crate::faults::{FaultInjection, child_paths};
+use crate::gate_factory::{GateFactory, GateSource, ReferenceGates};
+use crate::gates::{Gate, GateOutput, GateType, LogicGate, to_signal};
+use crate::netlist::{Netlist, NetlistBuilder};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+
+/// 2:1 multiplexer result with the raw output of each gate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mux2Detail {
+    pub output: GateOutput,     // OR gate
+    pub not_select: GateOutput, // NOT gate
+    pub and_a: GateOutput,      // A AND NOT select
+    pub and_b: GateOutput,      // B AND select
+}
+
+impl Mux2Detail {
+    /// Smallest distance from the decision threshold across all four gates
+    pub fn min_margin(&self) -> f64 {
+        self.output
+            .margin
+            .min(self.not_select.margin)
+            .min(self.and_a.margin)
+            .min(self.and_b.margin)
+    }
+}
+
+/// A 2:1 multiplexer that passes A when select is 0 and B when select is 1
+/// Output = (A AND NOT S) OR (B AND S)
+#[derive(Debug)]
+pub struct Mux2<G: Gate = LogicGate> {
+    not_gate: G,
+    and_a: G,
+    and_b: G,
+    or_gate: G,
+}
+
+impl Mux2 {
+    /// Create a new 2:1 multiplexer with trained neural gates (one network per gate type)
+    pub fn new() -> Self {
+        Self::with_factory(&mut GateFactory::shared())
+    }
+
+    /// Gate-level description: inputs `select`, `a`, `b`, output `out`
+    pub fn netlist() -> Netlist {
+        Mux2::with_factory(&mut ReferenceGates).to_netlist()
+    }
+}
+
+impl<G: Gate> Mux2<G> {
+    /// Create a 2:1 multiplexer using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S) -> Self {
+        Mux2 {
+            not_gate: factory.gate(GateType::NOT),
+            and_a: factory.gate(GateType::AND),
+            and_b: factory.gate(GateType::AND),
+            or_gate: factory.gate(GateType::OR),
+        }
+    }
+
+    /// Number of gates: one NOT, two AND and one OR
+    pub fn gate_count(&self) -> usize {
+        4
+    }
+
+    /// Gate-level description of this multiplexer, with cells named like its gate paths
+    pub fn to_netlist(&self) -> Netlist {
+        let mut builder = NetlistBuilder::new("mux2");
+        let select = builder.input("select");
+        let a = builder.input("a");
+        let b = builder.input("b");
+        let not_select = builder.gate("not", self.not_gate.gate_type().clone(), &[select]);
+        let and_a = builder.gate("and_a", self.and_a.gate_type().clone(), &[a, not_select]);
+        let and_b = builder.gate("and_b", self.and_b.gate_type().clone(), &[b, select]);
+        let out = builder.gate("or", self.or_gate.gate_type().clone(), &[and_a, and_b]);
+        builder.output("out", out);
+        builder.build().expect("mux2 netlist is well formed")
+    }
+
+    /// Select A (select = false) or B (select = true)
+    pub fn compute(&self, select: bool, a: bool, b: bool) -> bool {
+        self.compute_detailed(select, a, b).output.bit
+    }
+
+    /// Select an input keeping the raw gate activations
+    pub fn compute_detailed(&self, select: bool, a: bool, b: bool) -> Mux2Detail {
+        let not_select = self.not_gate.compute_detailed(vec![to_signal(select)]);
+        let and_a = self
+            .and_a
+            .compute_detailed(vec![to_signal(a), to_signal(not_select.bit)]);
+        let and_b = self
+            .and_b
+            .compute_detailed(vec![to_signal(b), to_signal(select)]);
+        let output = self
+            .or_gate
+            .compute_detailed(vec![to_signal(and_a.bit), to_signal(and_b.bit)]);
+
+        Mux2Detail {
+            output,
+            not_select,
+            and_a,
+            and_b,
+        }
+    }
+}
+
+impl Persist for Mux2 {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line("mux2")?;
+        self.not_gate.write_weights(writer)?;
+        self.and_a.write_weights(writer)?;
+        self.and_b.write_weights(writer)?;
+        self.or_gate.write_weights(writer)
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        reader.section("mux2")?;
+        Ok(Mux2 {
+            not_gate: LogicGate::read_typed(reader, &GateType::NOT)?,
+            and_a: LogicGate::read_typed(reader, &GateType::AND)?,
+            and_b: LogicGate::read_typed(reader, &GateType::AND)?,
+            or_gate: LogicGate::read_typed(reader, &GateType::OR)?,
+        })
+    }
+}
+
+/// Gates are addressed as `not`, `and_a`, `and_b` and `or`
+impl FaultInjection for Mux2 {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        match path.split_first()? {
+            (&"not", rest) => self.not_gate.gate_mut(rest),
+            (&"and_a", rest) => self.and_a.gate_mut(rest),
+            (&"and_b", rest) => self.and_b.gate_mut(rest),
+            (&"or", rest) => self.or_gate.gate_mut(rest),
+            _ => None,
+        }
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = child_paths("not", &self.not_gate);
+        paths.extend(child_paths("and_a", &self.and_a));
+        paths.extend(child_paths("and_b", &self.and_b));
+        paths.extend(child_paths("or", &self.or_gate));
+        paths
+    }
+}
+
+impl Default for Mux2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 4:1 multiplexer result with the outputs of each 2:1 multiplexer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mux4Detail {
+    pub output: GateOutput, // Selected input
+    pub low: Mux2Detail,    // Inputs 0 and 1
+    pub high: Mux2Detail,   // Inputs 2 and 3
+    pub out: Mux2Detail,    // Low or high
+}
+
+impl Mux4Detail {
+    /// Smallest distance from the decision threshold across all three multiplexers
+    pub fn min_margin(&self) -> f64 {
+        self.low
+            .min_margin()
+            .min(self.high.min_margin())
+            .min(self.out.min_margin())
+    }
+}
+
+/// A 4:1 multiplexer built as a tree of three 2:1 multiplexers
+/// Select bit 0 picks within each pair, select bit 1 picks the pair
+#[derive(Debug)]
+pub struct Mux4<G: Gate = LogicGate> {
+    low: Mux2<G>,  // Inputs 0 and 1
+    high: Mux2<G>, // Inputs 2 and 3
+    out: Mux2<G>,  // Low or high
+}
+
+impl Mux4 {
+    /// Create a new 4:1 multiplexer with trained neural gates (gates trained once, shared)
+    pub fn new() -> Self {
+        Self::with_factory(&mut GateFactory::shared())
+    }
+}
+
+impl<G: Gate> Mux4<G> {
+    /// Create a 4:1 multiplexer using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S) -> Self {
+        Mux4 {
+            low: Mux2::with_factory(factory),
+            high: Mux2::with_factory(factory),
+            out: Mux2::with_factory(factory),
+        }
+    }
+
+    /// Select one of four inputs; select bits are LSB first
+    pub fn compute(&self, select: [bool; 2], inputs: [bool; 4]) -> bool {
+        self.compute_detailed(select, inputs).output.bit
+    }
+
+    /// Select an input keeping the raw gate activations
+    pub fn compute_detailed(&self, select: [bool; 2], inputs: [bool; 4]) -> Mux4Detail {
+        let low = self.low.compute_detailed(select[0], inputs[0], inputs[1]);
+        let high = self.high.compute_detailed(select[0], inputs[2], inputs[3]);
+        let out = self
+            .out
+            .compute_detailed(select[1], low.output.bit, high.output.bit);
+
+        Mux4Detail {
+            output: out.output,
+            low,
+            high,
+            out,
+        }
+    }
+}
+
+impl Persist for Mux4 {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line("mux4")?;
+        self.low.write_weights(writer)?;
+        self.high.write_weights(writer)?;
+        self.out.write_weights(writer)
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        reader.section("mux4")?;
+        Ok(Mux4 {
+            low: Mux2::read_weights(reader)?,
+            high: Mux2::read_weights(reader)?,
+            out: Mux2::read_weights(reader)?,
+        })
+    }
+}
+
+/// Gates are addressed as `low.not`, `high.and_b`, `out.or`, ...
+impl FaultInjection for Mux4 {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        match path.split_first()? {
+            (&"low", rest) => self.low.gate_mut(rest),
+            (&"high", rest) => self.high.gate_mut(rest),
+            (&"out", rest) => self.out.gate_mut(rest),
+            _ => None,
+        }
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = child_paths("low", &self.low);
+        paths.extend(child_paths("high", &self.high));
+        paths.extend(child_paths("out", &self.out));
+        paths
+    }
+}
+
+impl Default for Mux4 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
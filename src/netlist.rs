@@ -0,0 +1,461 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+use crate::gates::GateType;
+
+/// A wire of a netlist, as handed out by `NetlistBuilder`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Wire(usize);
+
+impl Wire {
+    /// Position of the wire in `Netlist::wire_names`
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// What a cell of a netlist is
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellKind {
+    Gate(GateType),    // A single gate with one output
+    Instance(Netlist), // A sub-circuit, one output per output port
+}
+
+/// A gate or sub-circuit instance wired into a netlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    pub name: String,
+    pub kind: CellKind,
+    pub inputs: Vec<Wire>,  // In the order of the gate inputs or input ports
+    pub outputs: Vec<Wire>, // The gate output, or one wire per output port
+}
+
+/// Error found while building a netlist
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetlistError {
+    DuplicateName(String),   // Two cells or two ports share a name
+    MultipleDrivers(String), // A wire is driven by more than one input, constant or cell
+    Undriven(String),        // A wire is never driven
+    // A cell got the wrong number of inputs for its gate type or netlist
+    WrongInputCount {
+        cell: String,
+        expected: usize,
+        found: usize,
+    },
+    // A cell got the wrong number of output wires for its netlist
+    WrongOutputCount {
+        cell: String,
+        expected: usize,
+        found: usize,
+    },
+    CombinationalLoop(String), // A cell depends on its own output
+}
+
+impl fmt::Display for NetlistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetlistError::DuplicateName(name) => write!(f, "name '{}' is used twice", name),
+            NetlistError::MultipleDrivers(wire) => {
+                write!(f, "wire '{}' has more than one driver", wire)
+            }
+            NetlistError::Undriven(wire) => write!(f, "wire '{}' is never driven", wire),
+            NetlistError::WrongInputCount {
+                cell,
+                expected,
+                found,
+            } => write!(
+                f,
+                "cell '{}' takes {} inputs but got {}",
+                cell, expected, found
+            ),
+            NetlistError::WrongOutputCount {
+                cell,
+                expected,
+                found,
+            } => write!(
+                f,
+                "cell '{}' drives {} outputs but got {}",
+                cell, expected, found
+            ),
+            NetlistError::CombinationalLoop(cell) => {
+                write!(f, "cell '{}' is part of a combinational loop", cell)
+            }
+        }
+    }
+}
+
+impl Error for NetlistError {}
+
+/// Gate-level description of a combinational circuit
+///
+/// Wires are named, and cells are either gates or instances of other
+/// netlists, so circuits nest the way `FullAdder` nests `HalfAdder`s. Cells
+/// are kept in evaluation order: every cell comes after the cells driving
+/// its inputs. A netlist is only a description; `Circuit` runs it on gates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Netlist {
+    name: String,
+    wires: Vec<String>,           // Wire names, indexed by `Wire`
+    inputs: Vec<Wire>,            // Input ports, named after their wires
+    outputs: Vec<(String, Wire)>, // Output ports and the wires driving them
+    constants: Vec<(Wire, bool)>, // Wires tied to 0 or 1
+    cells: Vec<Cell>,             // In evaluation order
+}
+
+impl Netlist {
+    /// Name of the circuit
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Name of every wire, indexed by `Wire::index`
+    pub fn wire_names(&self) -> &[String] {
+        &self.wires
+    }
+
+    /// Name of the given wire
+    pub fn wire_name(&self, wire: Wire) -> &str {
+        &self.wires[wire.0]
+    }
+
+    /// Input port wires, in port order
+    pub fn inputs(&self) -> &[Wire] {
+        &self.inputs
+    }
+
+    /// Output port names with the wires driving them, in port order
+    pub fn outputs(&self) -> &[(String, Wire)] {
+        &self.outputs
+    }
+
+    /// Names of the input ports
+    pub fn input_names(&self) -> Vec<&str> {
+        self.inputs
+            .iter()
+            .map(|&wire| self.wire_name(wire))
+            .collect()
+    }
+
+    /// Names of the output ports
+    pub fn output_names(&self) -> Vec<&str> {
+        self.outputs.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Wires tied to a constant value
+    pub fn constants(&self) -> &[(Wire, bool)] {
+        &self.constants
+    }
+
+    /// Gates and instances, in evaluation order
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Number of gates, counting every gate inside instances
+    pub fn gate_count(&self) -> usize {
+        self.cells
+            .iter()
+            .map(|cell| match &cell.kind {
+                CellKind::Gate(_) => 1,
+                CellKind::Instance(netlist) => netlist.gate_count(),
+            })
+            .sum()
+    }
+
+    /// Dotted path of every gate in evaluation order, e.g. `half_adder1.xor`
+    pub fn gate_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for cell in &self.cells {
+            match &cell.kind {
+                CellKind::Gate(_) => paths.push(cell.name.clone()),
+                CellKind::Instance(netlist) => paths.extend(
+                    netlist
+                        .gate_paths()
+                        .into_iter()
+                        .map(|path| format!("{}.{}", cell.name, path)),
+                ),
+            }
+        }
+        paths
+    }
+
+    /// Exact boolean outputs for the given inputs, without neural gates
+    pub fn evaluate(&self, inputs: &[bool]) -> Vec<bool> {
+        assert_eq!(
+            inputs.len(),
+            self.inputs.len(),
+            "{} takes {} inputs",
+            self.name,
+            self.inputs.len()
+        );
+
+        let mut values = vec![false; self.wires.len()];
+        for (&wire, &value) in self.inputs.iter().zip(inputs) {
+            values[wire.0] = value;
+        }
+        for &(wire, value) in &self.constants {
+            values[wire.0] = value;
+        }
+        for cell in &self.cells {
+            let cell_inputs: Vec<bool> = cell.inputs.iter().map(|wire| values[wire.0]).collect();
+            match &cell.kind {
+                CellKind::Gate(gate_type) => {
+                    values[cell.outputs[0].0] = gate_type.evaluate(&cell_inputs);
+                }
+                CellKind::Instance(netlist) => {
+                    for (wire, value) in cell.outputs.iter().zip(netlist.evaluate(&cell_inputs)) {
+                        values[wire.0] = value;
+                    }
+                }
+            }
+        }
+        self.outputs
+            .iter()
+            .map(|(_, wire)| values[wire.0])
+            .collect()
+    }
+}
+
+/// Builds a netlist wire by wire
+///
+/// Wires can be used before they are driven (`wire` declares them by name),
+/// so cells can be added in any order; `build` checks every wire has exactly
+/// one driver, sorts the cells into evaluation order and rejects loops.
+#[derive(Debug, Clone)]
+pub struct NetlistBuilder {
+    name: String,
+    wires: Vec<String>,
+    wire_index: HashMap<String, Wire>,
+    driven: Vec<bool>,
+    inputs: Vec<Wire>,
+    outputs: Vec<(String, Wire)>,
+    constants: Vec<(Wire, bool)>,
+    cells: Vec<Cell>,
+    cell_names: HashSet<String>,
+    errors: Vec<NetlistError>, // Reported by `build`, first one wins
+}
+
+impl NetlistBuilder {
+    /// Start a netlist with the given circuit name
+    pub fn new(name: &str) -> Self {
+        NetlistBuilder {
+            name: name.to_string(),
+            wires: Vec::new(),
+            wire_index: HashMap::new(),
+            driven: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            constants: Vec::new(),
+            cells: Vec::new(),
+            cell_names: HashSet::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// The wire with this name, declared undriven if it does not exist yet
+    pub fn wire(&mut self, name: &str) -> Wire {
+        if let Some(&wire) = self.wire_index.get(name) {
+            return wire;
+        }
+        let wire = Wire(self.wires.len());
+        self.wires.push(name.to_string());
+        self.wire_index.insert(name.to_string(), wire);
+        self.driven.push(false);
+        wire
+    }
+
+    /// Add an input port
+    pub fn input(&mut self, name: &str) -> Wire {
+        let wire = self.wire(name);
+        self.drive(wire);
+        self.inputs.push(wire);
+        wire
+    }
+
+    /// Add one input port per bit, named `name0`, `name1`, ... (LSB first)
+    pub fn inputs(&mut self, name: &str, width: usize) -> Vec<Wire> {
+        (0..width)
+            .map(|bit| self.input(&format!("{}{}", name, bit)))
+            .collect()
+    }
+
+    /// A wire tied to 0 or 1
+    pub fn constant(&mut self, value: bool) -> Wire {
+        if let Some(&(wire, _)) = self.constants.iter().find(|&&(_, tied)| tied == value) {
+            return wire;
+        }
+        let wire = self.wire(if value { "const1" } else { "const0" });
+        self.drive(wire);
+        self.constants.push((wire, value));
+        wire
+    }
+
+    /// Add a gate driving a wire named after it
+    pub fn gate(&mut self, name: &str, gate_type: GateType, inputs: &[Wire]) -> Wire {
+        let output = self.wire(name);
+        self.gate_into(name, gate_type, inputs, output);
+        output
+    }
+
+    /// Add a gate driving an existing wire
+    pub fn gate_into(&mut self, name: &str, gate_type: GateType, inputs: &[Wire], output: Wire) {
+        self.check_inputs(name, gate_type.arity(), inputs.len());
+        self.drive(output);
+        self.add_cell(Cell {
+            name: name.to_string(),
+            kind: CellKind::Gate(gate_type),
+            inputs: inputs.to_vec(),
+            outputs: vec![output],
+        });
+    }
+
+    /// Add an instance of another netlist; its outputs drive wires named `name.port`
+    pub fn instance(&mut self, name: &str, netlist: &Netlist, inputs: &[Wire]) -> Vec<Wire> {
+        let outputs: Vec<Wire> = netlist
+            .output_names()
+            .iter()
+            .map(|port| self.wire(&format!("{}.{}", name, port)))
+            .collect();
+        self.instance_into(name, netlist, inputs, &outputs);
+        outputs
+    }
+
+    /// Add an instance of another netlist driving existing wires, one per output port
+    pub fn instance_into(
+        &mut self,
+        name: &str,
+        netlist: &Netlist,
+        inputs: &[Wire],
+        outputs: &[Wire],
+    ) {
+        self.check_inputs(name, netlist.inputs().len(), inputs.len());
+        if outputs.len() != netlist.outputs().len() {
+            self.errors.push(NetlistError::WrongOutputCount {
+                cell: name.to_string(),
+                expected: netlist.outputs().len(),
+                found: outputs.len(),
+            });
+        }
+        for &wire in outputs {
+            self.drive(wire);
+        }
+        self.add_cell(Cell {
+            name: name.to_string(),
+            kind: CellKind::Instance(netlist.clone()),
+            inputs: inputs.to_vec(),
+            outputs: outputs.to_vec(),
+        });
+    }
+
+    /// Add an output port driven by the given wire
+    pub fn output(&mut self, name: &str, wire: Wire) {
+        if self.outputs.iter().any(|(port, _)| port == name) {
+            self.errors
+                .push(NetlistError::DuplicateName(name.to_string()));
+        }
+        self.outputs.push((name.to_string(), wire));
+    }
+
+    /// Add one output port per wire, named `name0`, `name1`, ...
+    pub fn outputs(&mut self, name: &str, wires: &[Wire]) {
+        for (bit, &wire) in wires.iter().enumerate() {
+            self.output(&format!("{}{}", name, bit), wire);
+        }
+    }
+
+    /// Check the wiring and put the cells in evaluation order
+    pub fn build(self) -> Result<Netlist, NetlistError> {
+        if let Some(error) = self.errors.into_iter().next() {
+            return Err(error);
+        }
+        if let Some(wire) = self.driven.iter().position(|&driven| !driven) {
+            return Err(NetlistError::Undriven(self.wires[wire].clone()));
+        }
+
+        // Kahn's algorithm, taking the earliest ready cell first to keep the order given
+        let mut driver = vec![None; self.wires.len()];
+        for (index, cell) in self.cells.iter().enumerate() {
+            for wire in &cell.outputs {
+                driver[wire.0] = Some(index);
+            }
+        }
+        let mut waiting: Vec<usize> = vec![0; self.cells.len()];
+        let mut users: Vec<Vec<usize>> = vec![Vec::new(); self.cells.len()];
+        for (index, cell) in self.cells.iter().enumerate() {
+            let sources: HashSet<usize> = cell.inputs.iter().filter_map(|w| driver[w.0]).collect();
+            waiting[index] = sources.len();
+            for source in sources {
+                users[source].push(index);
+            }
+        }
+
+        let mut ready: BTreeSet<usize> = (0..self.cells.len())
+            .filter(|&index| waiting[index] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.cells.len());
+        while let Some(index) = ready.pop_first() {
+            order.push(index);
+            for &user in &users[index] {
+                waiting[user] -= 1;
+                if waiting[user] == 0 {
+                    ready.insert(user);
+                }
+            }
+        }
+        if let Some(mut stuck) = (0..self.cells.len()).find(|&index| waiting[index] > 0) {
+            // Every stuck cell waits on another one, so walking back ends up going round the loop
+            let mut seen = HashSet::new();
+            while seen.insert(stuck) {
+                stuck = self.cells[stuck]
+                    .inputs
+                    .iter()
+                    .filter_map(|wire| driver[wire.0])
+                    .find(|&source| waiting[source] > 0)
+                    .expect("a stuck cell waits on another stuck cell");
+            }
+            return Err(NetlistError::CombinationalLoop(
+                self.cells[stuck].name.clone(),
+            ));
+        }
+
+        let mut cells: Vec<Option<Cell>> = self.cells.into_iter().map(Some).collect();
+        Ok(Netlist {
+            name: self.name,
+            wires: self.wires,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            constants: self.constants,
+            cells: order
+                .into_iter()
+                .map(|index| cells[index].take().unwrap())
+                .collect(),
+        })
+    }
+
+    fn drive(&mut self, wire: Wire) {
+        if self.driven[wire.0] {
+            self.errors
+                .push(NetlistError::MultipleDrivers(self.wires[wire.0].clone()));
+        }
+        self.driven[wire.0] = true;
+    }
+
+    fn check_inputs(&mut self, cell: &str, expected: usize, found: usize) {
+        if expected != found {
+            self.errors.push(NetlistError::WrongInputCount {
+                cell: cell.to_string(),
+                expected,
+                found,
+            });
+        }
+    }
+
+    fn add_cell(&mut self, cell: Cell) {
+        if !self.cell_names.insert(cell.name.clone()) {
+            self.errors
+                .push(NetlistError::DuplicateName(cell.name.clone()));
+        }
+        self.cells.push(cell);
+    }
+}
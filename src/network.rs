@@ -0,0 +1,102 @@
+use aspirina::calc::Calc;
+use aspirina::layer::Layer;
+use aspirina::matrix::Matrix;
+
+/// Feed-forward sigmoid network whose weights can be inspected and restored
+///
+/// Uses the same forward pass and backpropagation rule as
+/// `aspirina::neural_network::NeuralNetwork`, but owns its weight matrices
+/// so trained gates can be saved, cloned and rebuilt without training.
+#[derive(Debug, Clone)]
+pub struct Network {
+    layers: Vec<Matrix>, // One weight matrix per layer (neurons x inputs)
+}
+
+impl Network {
+    /// Create a network from its layers
+    pub fn new(layers: Vec<Layer>) -> Self {
+        Network {
+            layers: layers.into_iter().map(|layer| layer.matrix).collect(),
+        }
+    }
+
+    /// Create a network directly from weight matrices
+    pub fn from_matrices(layers: Vec<Matrix>) -> Self {
+        Network { layers }
+    }
+
+    /// Weight matrices of every layer, input side first
+    pub fn layers(&self) -> &[Matrix] {
+        &self.layers
+    }
+
+    /// Number of inputs the first layer expects
+    pub fn input_size(&self) -> usize {
+        self.layers
+            .first()
+            .and_then(|layer| layer.data.first())
+            .map_or(0, |row| row.len())
+    }
+
+    /// Run one training step over the whole batch, returning the mean squared error before the update
+    pub fn train(&mut self, input: &Matrix, targets: &Matrix) -> f64 {
+        let forwarded = self.forward(input);
+        let mut error = targets.transpose() - forwarded.last().unwrap().clone();
+        let loss = mean_squared(&error);
+
+        for idx in (0..self.layers.len()).rev() {
+            let input_to_layer = if idx == 0 {
+                input.clone()
+            } else {
+                forwarded[idx - 1].clone()
+            };
+
+            let delta = Matrix::naive_multiply(forwarded[idx].derivative(), error.clone());
+
+            if idx > 0 {
+                // Propagate error through the weights before adjusting them
+                error = delta.clone() * self.layers[idx].clone();
+            }
+
+            let adjustment = input_to_layer.transpose() * delta;
+            self.layers[idx] = self.layers[idx].clone() + adjustment.transpose();
+        }
+
+        loss
+    }
+
+    /// Compute the output layer activations for the given input rows
+    pub fn predict(&self, input: Matrix) -> Matrix {
+        self.forward(&input).pop().unwrap()
+    }
+
+    /// Forward pass returning the activations of every layer
+    fn forward(&self, input: &Matrix) -> Vec<Matrix> {
+        let mut forwarded = Vec::with_capacity(self.layers.len());
+        let mut input = input.clone();
+
+        for layer in &self.layers {
+            input = activate(input * layer.transpose());
+            forwarded.push(input.clone());
+        }
+
+        forwarded
+    }
+}
+
+/// Mean of the squared entries of an error matrix
+fn mean_squared(error: &Matrix) -> f64 {
+    let values: Vec<f64> = error.data.iter().flatten().copied().collect();
+    values.iter().map(|value| value * value).sum::<f64>() / values.len() as f64
+}
+
+/// Apply the sigmoid activation element-wise
+fn activate(matrix: Matrix) -> Matrix {
+    Matrix::new(
+        matrix
+            .data
+            .iter()
+            .map(|row| row.iter().map(Calc::sigmoid).collect())
+            .collect(),
+    )
+}
@@ -0,0 +1,239 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use aspirina::matrix::Matrix;
+
+use crate::network::Network;
+
+/// Magic word at the start of every weights file
+pub const FORMAT_MAGIC: &str = "synapse-weights";
+
+/// Current version of the weights file format
+pub const FORMAT_VERSION: u32 = 12;
+
+/// Components whose trained weights can be saved and restored without training
+///
+/// Files are plain text: a `synapse-weights <version>` header followed by one
+/// section per component, nested in the same order the components are built.
+pub trait Persist: Sized {
+    /// Write this component's section
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()>;
+
+    /// Rebuild the component from its section
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self>;
+
+    /// Write the weights, with header, to any writer
+    fn write_to<W: Write>(&self, output: W) -> io::Result<()> {
+        let mut writer = WeightWriter::new(output);
+        writer.line(&format!("{} {}", FORMAT_MAGIC, FORMAT_VERSION))?;
+        self.write_weights(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Read weights, with header, from any reader
+    fn read_from<R: Read>(input: R) -> io::Result<Self> {
+        let mut reader = WeightReader::new(BufReader::new(input))?;
+        reader.header()?;
+        let component = Self::read_weights(&mut reader)?;
+        reader.end()?;
+        Ok(component)
+    }
+
+    /// Save the trained weights to a file
+    fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    /// Load a component from a weights file without training
+    fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(File::open(path)?)
+    }
+}
+
+/// Line-oriented writer for weights files
+pub struct WeightWriter<'a> {
+    output: Box<dyn Write + 'a>,
+}
+
+impl<'a> WeightWriter<'a> {
+    fn new<W: Write + 'a>(output: W) -> Self {
+        WeightWriter {
+            output: Box::new(output),
+        }
+    }
+
+    /// Write a single line
+    pub fn line(&mut self, text: &str) -> io::Result<()> {
+        writeln!(self.output, "{}", text)
+    }
+
+    /// Write every layer of a network
+    pub fn network(&mut self, network: &Network) -> io::Result<()> {
+        self.line(&format!("network {}", network.layers().len()))?;
+        for layer in network.layers() {
+            let cols = layer.data.first().map_or(0, |row| row.len());
+            self.line(&format!("layer {} {}", layer.data.len(), cols))?;
+            for row in &layer.data {
+                // f64 Display is the shortest representation that round-trips exactly
+                let values: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+                self.line(&values.join(" "))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+/// Line-oriented reader for weights files, tracking line numbers for errors
+pub struct WeightReader {
+    lines: Vec<String>,
+    position: usize,
+}
+
+impl WeightReader {
+    fn new<R: BufRead>(input: R) -> io::Result<Self> {
+        let lines = input.lines().collect::<io::Result<Vec<_>>>()?;
+        Ok(WeightReader { lines, position: 0 })
+    }
+
+    /// Build an `InvalidData` error pointing at the current line
+    pub fn error(&self, message: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("weights line {}: {}", self.position, message),
+        )
+    }
+
+    /// Read the next line
+    pub fn line(&mut self) -> io::Result<&str> {
+        if self.position >= self.lines.len() {
+            return Err(self.error("unexpected end of file"));
+        }
+        self.position += 1;
+        Ok(self.lines[self.position - 1].trim())
+    }
+
+    /// Read a line that must start with `tag`, returning the remaining fields
+    pub fn tagged(&mut self, tag: &str) -> io::Result<Vec<String>> {
+        let line = self.line()?.to_string();
+        let mut fields = line.split_whitespace();
+        if fields.next() != Some(tag) {
+            return Err(self.error(&format!("expected '{}', found '{}'", tag, line)));
+        }
+        Ok(fields.map(str::to_string).collect())
+    }
+
+    /// Read a section marker line
+    pub fn section(&mut self, tag: &str) -> io::Result<()> {
+        let fields = self.tagged(tag)?;
+        if !fields.is_empty() {
+            return Err(self.error(&format!("unexpected fields after '{}'", tag)));
+        }
+        Ok(())
+    }
+
+    /// Read a network written by `WeightWriter::network` for a gate with `inputs` inputs
+    ///
+    /// The first layer may take one more input, the constant bias.
+    pub fn network(&mut self, inputs: usize) -> io::Result<Network> {
+        let count = self.counts("network", 1)?[0];
+        if count == 0 {
+            return Err(self.error("network has no layers"));
+        }
+        let mut layers = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            let shape = self.counts("layer", 2)?;
+            let (rows, cols) = (shape[0], shape[1]);
+            match layers.last().map(|layer: &Matrix| layer.data.len()) {
+                Some(previous) if previous != cols => {
+                    return Err(self.error(&format!(
+                        "layer expects {} inputs but previous layer has {} neurons",
+                        cols, previous
+                    )));
+                }
+                None if cols != inputs && cols != inputs + 1 => {
+                    return Err(self.error(&format!(
+                        "layer expects {} inputs but the gate has {}",
+                        cols, inputs
+                    )));
+                }
+                _ => {}
+            }
+            if layers.len() + 1 == count && rows != 1 {
+                return Err(self.error(&format!(
+                    "last layer has {} neurons but a gate has one output",
+                    rows
+                )));
+            }
+            let mut data = Vec::with_capacity(rows);
+
+            for _ in 0..rows {
+                let row = self
+                    .line()?
+                    .split_whitespace()
+                    .map(str::parse::<f64>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| self.error("invalid weight value"))?;
+                if row.len() != cols {
+                    return Err(self.error(&format!(
+                        "expected {} weights, found {}",
+                        cols,
+                        row.len()
+                    )));
+                }
+                data.push(row);
+            }
+
+            layers.push(Matrix::new(data));
+        }
+
+        Ok(Network::from_matrices(layers))
+    }
+
+    /// Read a tagged line holding exactly `expected` unsigned numbers
+    fn counts(&mut self, tag: &str, expected: usize) -> io::Result<Vec<usize>> {
+        let fields = self.tagged(tag)?;
+        if fields.len() != expected {
+            return Err(self.error(&format!("expected {} numbers", expected)));
+        }
+        fields
+            .iter()
+            .map(|field| field.parse().map_err(|_| self.error("invalid number")))
+            .collect()
+    }
+
+    fn header(&mut self) -> io::Result<()> {
+        let fields = self.tagged(FORMAT_MAGIC)?;
+        let version: u32 = match fields.as_slice() {
+            [version] => version
+                .parse()
+                .map_err(|_| self.error("invalid format version"))?,
+            _ => return Err(self.error("missing format version")),
+        };
+        if version != FORMAT_VERSION {
+            return Err(self.error(&format!(
+                "unsupported format version {} (expected {})",
+                version, FORMAT_VERSION
+            )));
+        }
+        Ok(())
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        let rest = self.lines[self.position..]
+            .iter()
+            .position(|line| !line.trim().is_empty());
+        match rest {
+            Some(offset) => {
+                self.position += offset + 1;
+                Err(self.error("unexpected trailing data"))
+            }
+            None => Ok(()),
+        }
+    }
+}
@@ -58,6 +58,94 @@ impl Default for Register4Bit {
     }
 }
 
+/// Status flags packed into one register, one bit per flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusRegister {
+    bits: u8, // --SH VNCZ
+}
+
+impl StatusRegister {
+    pub const ZERO: u8 = 1 << 0; // Last result was zero
+    pub const CARRY: u8 = 1 << 1; // Carry out of the MSB (no borrow after a subtraction)
+    pub const NEGATIVE: u8 = 1 << 2; // MSB of the last result was set
+    pub const OVERFLOW: u8 = 1 << 3; // Last arithmetic result overflowed as a signed number
+    pub const HALF_CARRY: u8 = 1 << 4; // Carry out of bit 3, the low BCD digit
+    pub const SUBTRACT: u8 = 1 << 5; // Last arithmetic instruction subtracted (for decimal adjust)
+
+    /// Create a status register with every flag clear
+    pub fn new() -> Self {
+        StatusRegister { bits: 0 }
+    }
+
+    /// Create a status register from packed flag bits
+    pub fn from_bits(bits: u8) -> Self {
+        StatusRegister {
+            bits: bits & 0x3F, // Six flags
+        }
+    }
+
+    /// Packed flag bits
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// Check whether every flag in the mask is set
+    pub fn get(&self, flag: u8) -> bool {
+        self.bits & flag == flag
+    }
+
+    /// Set or clear the flags in the mask
+    pub fn set(&mut self, flag: u8, value: bool) {
+        if value {
+            self.bits |= flag;
+        } else {
+            self.bits &= !flag;
+        }
+        self.bits &= 0x3F; // Six flags
+    }
+
+    /// Clear every flag
+    pub fn clear(&mut self) {
+        self.bits = 0;
+    }
+
+    /// Zero flag
+    pub fn zero(&self) -> bool {
+        self.get(Self::ZERO)
+    }
+
+    /// Carry flag
+    pub fn carry(&self) -> bool {
+        self.get(Self::CARRY)
+    }
+
+    /// Negative (sign) flag
+    pub fn negative(&self) -> bool {
+        self.get(Self::NEGATIVE)
+    }
+
+    /// Signed overflow flag
+    pub fn overflow(&self) -> bool {
+        self.get(Self::OVERFLOW)
+    }
+
+    /// Half-carry (auxiliary carry) flag
+    pub fn half_carry(&self) -> bool {
+        self.get(Self::HALF_CARRY)
+    }
+
+    /// Subtract flag: whether decimal adjust should correct a difference rather than a sum
+    pub fn subtract(&self) -> bool {
+        self.get(Self::SUBTRACT)
+    }
+}
+
+impl Default for StatusRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// CPU Register set for 4-bit computer
 #[derive(Debug)]
 pub struct CPURegisters {
@@ -70,9 +158,11 @@ pub struct CPURegisters {
     /// Instruction Register - holds current instruction being executed
     pub instruction_register: Register4Bit,
 
-    /// Status flags
-    pub zero_flag: bool,  // Set when last operation resulted in zero
-    pub carry_flag: bool, // Set when last operation had carry/overflow
+    /// High Register - holds the high nibble of the last MUL product
+    pub high: Register4Bit,
+
+    /// Status register - zero, carry, negative, overflow, half-carry and BCD subtract flags
+    pub status: StatusRegister,
 }
 
 impl CPURegisters {
@@ -82,8 +172,8 @@ impl CPURegisters {
             accumulator: Register4Bit::new(),
             program_counter: Register4Bit::new(),
             instruction_register: Register4Bit::new(),
-            zero_flag: false,
-            carry_flag: false,
+            high: Register4Bit::new(),
+            status: StatusRegister::new(),
         }
     }
 
@@ -92,14 +182,15 @@ impl CPURegisters {
         self.accumulator.clear();
         self.program_counter.clear();
         self.instruction_register.clear();
-        self.zero_flag = false;
-        self.carry_flag = false;
+        self.high.clear();
+        self.status.clear();
     }
 
-    /// Update flags based on ALU result
+    /// Update zero, negative and carry from a 4-bit value; the other flags are kept
     pub fn update_flags(&mut self, value: u8, carry: bool) {
-        self.zero_flag = (value & 0x0F) == 0;
-        self.carry_flag = carry;
+        self.status.set(StatusRegister::ZERO, (value & 0x0F) == 0);
+        self.status.set(StatusRegister::NEGATIVE, value & 0x08 != 0);
+        self.status.set(StatusRegister::CARRY, carry);
     }
 
     /// Display register state for debugging
@@ -120,10 +211,15 @@ impl CPURegisters {
             self.instruction_register.read(),
             self.instruction_register.read()
         );
+        println!("HI: 0x{:X} ({})", self.high.read(), self.high.read());
         println!(
-            "Flags: Z={} C={}",
-            if self.zero_flag { "1" } else { "0" },
-            if self.carry_flag { "1" } else { "0" }
+            "Flags: Z={} C={} N={} V={} H={} S={}",
+            self.status.zero() as u8,
+            self.status.carry() as u8,
+            self.status.negative() as u8,
+            self.status.overflow() as u8,
+            self.status.half_carry() as u8,
+            self.status.subtract() as u8
         );
     }
 }
@@ -0,0 +1,461 @@
+use crate::alu::{ALU, ALUOperation, MAX_DECIMAL_WIDTH};
+use crate::alu_detail::{ALUDetail, ALUResult, AdderDetail};
+use crate::bcd::DIGIT_BITS;
+use crate::gates::Gate;
+use crate::training::SeededRng;
+
+/// Widest ALU tested on every operand pair, which is 2^(2 x width) runs per operation
+pub const EXHAUSTIVE_MAX_WIDTH: usize = 8;
+
+/// Random operands drawn for a wider ALU, on top of the corner cases
+const SAMPLED_OPERANDS: usize = 8;
+
+/// Seed of the random operands, so a wide self-test is repeatable
+const SAMPLE_SEED: u64 = 0x5E1F_7E57;
+
+/// Result of running every operation on every operand pair
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfTestReport {
+    pub width: usize,                      // ALU width tested
+    pub exhaustive: bool,                  // Every operand pair, or a sample for a wide ALU
+    pub checked: usize,                    // Operations run and checked
+    pub mismatches: Vec<SelfTestMismatch>, // Every run that disagreed with integer arithmetic
+}
+
+impl SelfTestReport {
+    /// Whether every run matched
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Adder stage named by the first mismatch that went through the adder
+    pub fn first_faulty_stage(&self) -> Option<&str> {
+        self.mismatches
+            .iter()
+            .find_map(|mismatch| mismatch.stage.as_deref())
+    }
+}
+
+/// One run whose result or flags disagreed with integer arithmetic
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfTestMismatch {
+    pub operation: ALUOperation,
+    pub a: u64,
+    pub b: u64,
+    pub expected: ALUResult,
+    pub actual: ALUResult,
+    pub expected_extra: u64, // High word of a product or remainder of a division (else 0)
+    pub actual_extra: u64,
+    pub first_wrong_bit: Option<usize>, // Lowest result bit that differs (None if only flags do)
+    pub stage: Option<String>, // Fault path of the adder stage that went wrong first, e.g. `adder2`
+}
+
+/// Every operation, once for each combination of its flag inputs
+pub fn every_operation() -> Vec<ALUOperation> {
+    let mut operations = vec![
+        ALUOperation::Add,
+        ALUOperation::Subtract,
+        ALUOperation::And,
+        ALUOperation::Or,
+        ALUOperation::Xor,
+        ALUOperation::Not,
+        ALUOperation::Compare,
+        ALUOperation::Multiply,
+        ALUOperation::Divide,
+        ALUOperation::Modulo,
+        ALUOperation::Increment,
+        ALUOperation::Decrement,
+        ALUOperation::ShiftLeft,
+        ALUOperation::ShiftRight,
+        ALUOperation::RotateLeft,
+        ALUOperation::RotateRight,
+    ];
+    for carry in [false, true] {
+        operations.push(ALUOperation::AddWithCarry(carry));
+        operations.push(ALUOperation::SubtractWithBorrow(carry));
+        operations.push(ALUOperation::RotateLeftThroughCarry(carry));
+        operations.push(ALUOperation::RotateRightThroughCarry(carry));
+        for half_carry in [false, true] {
+            for subtract in [false, true] {
+                operations.push(ALUOperation::DecimalAdjust {
+                    carry,
+                    half_carry,
+                    subtract,
+                });
+            }
+        }
+    }
+    operations
+}
+
+impl<G: Gate> ALU<G> {
+    /// Run every operation and check it against integer arithmetic
+    ///
+    /// ALUs up to `EXHAUSTIVE_MAX_WIDTH` bits run every operand pair; wider
+    /// ones run corner cases and a fixed random sample of operands.
+    pub fn self_test(&self) -> SelfTestReport {
+        run(self)
+    }
+}
+
+/// Run every operation on every operand (B fixed at 0 where it is ignored)
+///
+/// Operations the ALU has no unit for are skipped, and decimal adjust is only
+/// checked on values BCD arithmetic can leave in A.
+pub fn run<G: Gate>(alu: &ALU<G>) -> SelfTestReport {
+    let width = alu.width();
+    let operands = operands(width);
+
+    let mut checked = 0;
+    let mut mismatches = Vec::new();
+    for operation in every_operation().into_iter().filter(|op| alu.supports(op)) {
+        let b_values = if uses_b(&operation) {
+            &operands[..]
+        } else {
+            &[0]
+        };
+        for &a in &operands {
+            for &b in b_values {
+                let Some((expected, expected_extra)) = reference(a, b, &operation, width) else {
+                    continue;
+                };
+                checked += 1;
+                let detail = alu.compute_detailed(a, b, operation.clone());
+                let actual_extra = detail.high_word() | detail.remainder();
+                if detail.result == expected && actual_extra == expected_extra {
+                    continue;
+                }
+
+                let wrong_bits = detail.result.result ^ expected.result;
+                mismatches.push(SelfTestMismatch {
+                    first_wrong_bit: (wrong_bits != 0)
+                        .then(|| wrong_bits.trailing_zeros() as usize),
+                    stage: faulty_stage(&detail, a, b, &operation, width, wrong_bits),
+                    operation: operation.clone(),
+                    a,
+                    b,
+                    expected,
+                    actual: detail.result,
+                    expected_extra,
+                    actual_extra,
+                });
+            }
+        }
+    }
+
+    SelfTestReport {
+        width,
+        exhaustive: width <= EXHAUSTIVE_MAX_WIDTH,
+        checked,
+        mismatches,
+    }
+}
+
+/// Every value up to `EXHAUSTIVE_MAX_WIDTH` bits, else corner cases and random values
+fn operands(width: usize) -> Vec<u64> {
+    let mask = u64::MAX >> (64 - width);
+    if width <= EXHAUSTIVE_MAX_WIDTH {
+        return (0..=mask).collect();
+    }
+
+    let msb = 1 << (width - 1);
+    let mut operands = vec![
+        0,
+        1,
+        2,
+        msb - 1,
+        msb,
+        msb + 1,
+        mask - 1,
+        mask,
+        0x5555_5555_5555_5555 & mask,
+        0x9999_9999_9999_9999 & mask, // All nines in BCD
+    ];
+    let mut rng = SeededRng::new(SAMPLE_SEED);
+    operands.extend((0..SAMPLED_OPERANDS).map(|_| rng.next_u64() & mask));
+    operands.sort_unstable();
+    operands.dedup();
+    operands
+}
+
+/// Result, flags and extra word (high word or remainder) from integer arithmetic
+///
+/// None for a decimal adjust of a value no BCD arithmetic leaves with those flags.
+pub fn reference(
+    a: u64,
+    b: u64,
+    operation: &ALUOperation,
+    width: usize,
+) -> Option<(ALUResult, u64)> {
+    let mask = u64::MAX >> (64 - width);
+    let msb = |value: u64| (value >> (width - 1)) & 1 != 0;
+    let (a, b) = (a & mask, b & mask);
+    let flags = |result: u64, carry: bool, overflow: bool, half_carry: bool| ALUResult {
+        result,
+        carry,
+        zero: result == 0,
+        negative: msb(result),
+        overflow,
+        half_carry,
+    };
+    let sum = |b: u64, carry_in: bool| {
+        let total = a as u128 + b as u128 + carry_in as u128;
+        let result = total as u64 & mask;
+        let half_carry = width >= DIGIT_BITS && (a & 0xF) + (b & 0xF) + carry_in as u64 > 0xF;
+        let overflow = msb(a) == msb(b) && msb(result) != msb(a);
+        flags(result, total > mask as u128, overflow, half_carry)
+    };
+
+    let result = match *operation {
+        ALUOperation::Add => (sum(b, false), 0),
+        ALUOperation::AddWithCarry(carry) => (sum(b, carry), 0),
+        ALUOperation::Subtract | ALUOperation::Compare => (sum(!b & mask, true), 0),
+        ALUOperation::SubtractWithBorrow(carry) => (sum(!b & mask, carry), 0),
+        ALUOperation::And => (flags(a & b, false, false, false), 0),
+        ALUOperation::Or => (flags(a | b, false, false, false), 0),
+        ALUOperation::Xor => (flags(a ^ b, false, false, false), 0),
+        ALUOperation::Not => (flags(!a & mask, false, false, false), 0),
+        ALUOperation::Multiply => {
+            let product = a as u128 * b as u128;
+            let high = (product >> width) as u64;
+            let mut result = flags(product as u64 & mask, high != 0, false, false);
+            result.zero = product == 0;
+            (result, high)
+        }
+        ALUOperation::Divide | ALUOperation::Modulo => {
+            let quotient = a.checked_div(b).unwrap_or(mask);
+            let remainder = a.checked_rem(b).unwrap_or(a);
+            let value = if *operation == ALUOperation::Divide {
+                quotient
+            } else {
+                remainder
+            };
+            (flags(value, false, b == 0, false), remainder)
+        }
+        ALUOperation::Increment | ALUOperation::Decrement => {
+            let decrement = *operation == ALUOperation::Decrement;
+            let result = if decrement {
+                a.wrapping_sub(1) & mask
+            } else {
+                a.wrapping_add(1) & mask
+            };
+            // The chain adds 1 to A (or to NOT A when counting down)
+            let counted = if decrement { !a & mask } else { a };
+            let half_carry = width >= DIGIT_BITS && counted & 0xF == 0xF;
+            let overflow = msb(a) != msb(result) && msb(result) != decrement;
+            (flags(result, counted == mask, overflow, half_carry), 0)
+        }
+        ALUOperation::ShiftLeft => (flags((a << 1) & mask, msb(a), false, false), 0),
+        ALUOperation::ShiftRight => (flags(a >> 1, a & 1 != 0, false, false), 0),
+        ALUOperation::RotateLeft => {
+            let result = ((a << 1) | msb(a) as u64) & mask;
+            (flags(result, msb(a), false, false), 0)
+        }
+        ALUOperation::RotateRight => {
+            let result = (a >> 1) | ((a & 1) << (width - 1));
+            (flags(result, a & 1 != 0, false, false), 0)
+        }
+        ALUOperation::RotateLeftThroughCarry(carry) => {
+            let result = ((a << 1) | carry as u64) & mask;
+            (flags(result, msb(a), false, false), 0)
+        }
+        ALUOperation::RotateRightThroughCarry(carry) => {
+            let result = (a >> 1) | ((carry as u64) << (width - 1));
+            (flags(result, a & 1 != 0, false, false), 0)
+        }
+        ALUOperation::DecimalAdjust {
+            carry,
+            half_carry,
+            subtract,
+        } => (decimal_adjust(a, carry, half_carry, subtract, width)?, 0),
+    };
+    Some(result)
+}
+
+/// Decimal adjust from decimal arithmetic
+///
+/// Looks for BCD operands whose binary sum or difference leaves A and these
+/// flags, then encodes their decimal sum or difference back as BCD. Every such
+/// pair has the same decimal answer, since the flags give the carry out of
+/// each digit. None if no BCD arithmetic leaves A with these flags.
+fn decimal_adjust(
+    a: u64,
+    carry: bool,
+    half_carry: bool,
+    subtract: bool,
+    width: usize,
+) -> Option<ALUResult> {
+    let mask = u64::MAX >> (64 - width);
+    let negative = |value: u64| (value >> (width - 1)) & 1 != 0;
+    if width > MAX_DECIMAL_WIDTH {
+        return Some(ALUResult {
+            result: a,
+            carry,
+            zero: a == 0,
+            negative: negative(a),
+            overflow: true,
+            half_carry: false,
+        });
+    }
+
+    // Whole digits count in tens; the bits above them count in units of 10^digits
+    let digits = width / DIGIT_BITS;
+    let low_bits = digits * DIGIT_BITS;
+    let scale = 10u64.pow(digits as u32);
+    let modulus = scale << (width - low_bits);
+    let to_decimal = |value: u64| {
+        (0..digits)
+            .rev()
+            .try_fold(value >> low_bits, |decimal, digit| {
+                let nibble = (value >> (digit * DIGIT_BITS)) & 0xF;
+                (nibble <= 9).then_some(decimal * 10 + nibble)
+            })
+    };
+    let to_bcd = |decimal: u64| {
+        (0..digits).fold((decimal / scale) << low_bits, |value, digit| {
+            let nibble = decimal / 10u64.pow(digit as u32) % 10;
+            value | nibble << (digit * DIGIT_BITS)
+        })
+    };
+
+    // A with the carry flag is the binary total of x, y (or NOT y) and the carry in
+    let total = a + ((carry as u64) << width);
+    for x in 0..=mask {
+        let Some(x_decimal) = to_decimal(x) else {
+            continue;
+        };
+        for carry_in in [false, true] {
+            let y_term = match total.checked_sub(x + carry_in as u64) {
+                Some(y_term) if y_term <= mask => y_term,
+                _ => continue,
+            };
+            let y = if subtract { !y_term & mask } else { y_term };
+            let Some(y_decimal) = to_decimal(y) else {
+                continue;
+            };
+            let operation = if subtract {
+                ALUOperation::SubtractWithBorrow(carry_in)
+            } else {
+                ALUOperation::AddWithCarry(carry_in)
+            };
+            let (binary, _) = reference(x, y, &operation, width)?;
+            if (binary.result, binary.carry, binary.half_carry) != (a, carry, half_carry) {
+                continue;
+            }
+
+            let (decimal, decimal_carry) = if subtract {
+                let difference = x_decimal as i64 - y_decimal as i64 - !carry_in as i64;
+                (
+                    difference.rem_euclid(modulus as i64) as u64,
+                    difference >= 0,
+                )
+            } else {
+                let sum = x_decimal + y_decimal + carry_in as u64;
+                (sum % modulus, sum >= modulus)
+            };
+            let result = to_bcd(decimal);
+            return Some(ALUResult {
+                result,
+                carry: decimal_carry,
+                zero: result == 0,
+                negative: negative(result),
+                overflow: false,
+                half_carry: false,
+            });
+        }
+    }
+    None
+}
+
+/// Whether the operation reads operand B
+fn uses_b(operation: &ALUOperation) -> bool {
+    matches!(
+        operation,
+        ALUOperation::Add
+            | ALUOperation::Subtract
+            | ALUOperation::And
+            | ALUOperation::Or
+            | ALUOperation::Xor
+            | ALUOperation::Compare
+            | ALUOperation::Multiply
+            | ALUOperation::Divide
+            | ALUOperation::Modulo
+            | ALUOperation::AddWithCarry(_)
+            | ALUOperation::SubtractWithBorrow(_)
+    )
+}
+
+/// Fault path of the adder stage that went wrong first
+///
+/// Each stage is checked against its own inputs, so a wrong carry is blamed
+/// on the stage that produced it rather than every stage it rippled through.
+/// If every stage added its inputs correctly the inputs were wrong, and the
+/// inverter feeding the first wrong bit is named instead.
+fn faulty_stage(
+    detail: &ALUDetail,
+    a: u64,
+    b: u64,
+    operation: &ALUOperation,
+    width: usize,
+    wrong_bits: u64,
+) -> Option<String> {
+    let adder = detail.adder.as_ref()?;
+    let (subtract, carry_in) = match *operation {
+        ALUOperation::Add => (false, false),
+        ALUOperation::AddWithCarry(carry) => (false, carry),
+        ALUOperation::Subtract | ALUOperation::Compare => (true, true),
+        ALUOperation::SubtractWithBorrow(carry) => (true, carry),
+        _ => return None,
+    };
+
+    let a_bits: Vec<bool> = (0..width).map(|bit| (a >> bit) & 1 != 0).collect();
+    let b_bits: Vec<bool> = if subtract {
+        detail.inverters.iter().map(|output| output.bit).collect()
+    } else {
+        (0..width).map(|bit| (b >> bit) & 1 != 0).collect()
+    };
+
+    let mut carry = carry_in;
+    let mut first_bit = 0;
+    match adder {
+        AdderDetail::RippleCarry(stages) => {
+            for (index, stage) in stages.iter().enumerate() {
+                let (x, y) = (a_bits[index], b_bits[index]);
+                let sum = x ^ y ^ carry;
+                let carry_out = (x && y) || (carry && (x ^ y));
+                if stage.sum.bit != sum || stage.carry.bit != carry_out {
+                    return Some(format!("adder{}", index));
+                }
+                carry = stage.carry.bit;
+            }
+        }
+        AdderDetail::CarryLookahead(blocks) => {
+            for (index, block) in blocks.iter().enumerate() {
+                let bits = first_bit..first_bit + block.sums.len();
+                let value = |bits_of: &[bool]| {
+                    bits_of[bits.clone()]
+                        .iter()
+                        .rev()
+                        .fold(0u64, |value, &bit| (value << 1) | bit as u64)
+                };
+                let total = value(&a_bits) + value(&b_bits) + carry as u64;
+                let expected_carry = total >> block.sums.len() != 0;
+                let sums = block
+                    .sum_bits()
+                    .iter()
+                    .rev()
+                    .fold(0u64, |value, &bit| (value << 1) | bit as u64);
+                let block_mask = (1u64 << block.sums.len()) - 1;
+                if sums != total & block_mask || block.carry() != expected_carry {
+                    return Some(format!("lookahead{}", index));
+                }
+                carry = block.carry();
+                first_bit = bits.end;
+            }
+        }
+    }
+
+    // The adder was right about its inputs: blame the inverter of the first wrong bit
+    let bit = wrong_bits.trailing_zeros() as usize;
+    (subtract && bit < width).then(|| format!("not{}", bit))
+}
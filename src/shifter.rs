@@ -0,0 +1,264 @@
+use std::io;
+
+use crate::faults::{FaultInjection, child_paths};
+use crate::gate_factory::{GateFactory, GateSource};
+use crate::gates::{Gate, LogicGate};
+use crate::mux::{Mux2, Mux2Detail};
+use crate::netlist::{Netlist, NetlistBuilder, Wire};
+use crate::persistence::{Persist, WeightReader, WeightWriter};
+
+/// Direction bits move in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShiftDirection {
+    Left,  // Towards the MSB (x2)
+    Right, // Towards the LSB (/2)
+}
+
+/// What enters the bit position left empty by a shift
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShiftFill {
+    Zero,   // Logical shift
+    Rotate, // The bit shifted out at the other end
+    Carry,  // The carry in (rotate through carry)
+}
+
+/// Shifter result with the raw gate outputs of every multiplexer
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShifterDetail {
+    pub bits: Vec<Mux2Detail>, // Output bits, LSB first
+    pub carry: Mux2Detail,     // Bit shifted out
+    pub fill: Mux2Detail,      // Bit shifted in
+    pub rotate: Mux2Detail,    // Bit shifted in when not going through the carry
+}
+
+impl ShifterDetail {
+    /// Thresholded output bits, LSB first
+    pub fn result_bits(&self) -> Vec<bool> {
+        self.bits.iter().map(|mux| mux.output.bit).collect()
+    }
+
+    /// Smallest distance from the decision threshold across every multiplexer
+    pub fn min_margin(&self) -> f64 {
+        self.bits
+            .iter()
+            .chain([&self.carry, &self.fill, &self.rotate])
+            .map(Mux2Detail::min_margin)
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// A shifter built from 2:1 multiplexers of neural gates
+///
+/// Each output bit is a multiplexer choosing between its two neighbours, so
+/// the select line sets the direction. Three more multiplexers pick the bit
+/// shifted out (the carry) and the bit shifted in (zero, the carry out for a
+/// rotate, or the carry in for a rotate through carry).
+#[derive(Debug)]
+pub struct Shifter<G: Gate = LogicGate> {
+    bits: Vec<Mux2<G>>, // One multiplexer per output bit: right neighbour or left neighbour
+    carry: Mux2<G>,     // LSB or MSB, whichever is shifted out
+    rotate: Mux2<G>,    // Zero or the bit shifted out
+    fill: Mux2<G>,      // Rotate output or the carry in
+}
+
+impl Shifter {
+    /// Create a shifter of the given width with trained neural gates (shared)
+    pub fn new(width: usize) -> Self {
+        Self::with_factory(&mut GateFactory::shared(), width)
+    }
+}
+
+impl<G: Gate> Shifter<G> {
+    /// Create a shifter using gates from the given source
+    pub fn with_factory<S: GateSource<Gate = G>>(factory: &mut S, width: usize) -> Self {
+        assert!(width > 0, "shifter needs at least one bit");
+
+        Shifter {
+            bits: (0..width).map(|_| Mux2::with_factory(factory)).collect(),
+            carry: Mux2::with_factory(factory),
+            rotate: Mux2::with_factory(factory),
+            fill: Mux2::with_factory(factory),
+        }
+    }
+
+    /// Number of bits shifted
+    pub fn width(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Number of gates: a multiplexer per bit, plus the carry, rotate and fill selectors
+    pub fn gate_count(&self) -> usize {
+        (self.bits.len() + 3) * self.carry.gate_count()
+    }
+
+    /// Gate-level description of this shifter
+    ///
+    /// Inputs are the bits `in0..`, the control lines `left`, `rotating` and
+    /// `through_carry` (`compute` decodes them from the direction and fill), and
+    /// `carry_in`; outputs are `out0..` and the bit shifted out, `carry`.
+    pub fn to_netlist(&self) -> Netlist {
+        let width = self.width();
+        let mut builder = NetlistBuilder::new("shifter");
+        let bits = builder.inputs("in", width);
+        let left = builder.input("left");
+        let rotating = builder.input("rotating");
+        let through_carry = builder.input("through_carry");
+        let carry_in = builder.input("carry_in");
+        let zero = builder.constant(false);
+
+        let mut mux = |name: &str, mux: &Mux2<G>, select: Wire, a: Wire, b: Wire| {
+            builder.instance(name, &mux.to_netlist(), &[select, a, b])[0]
+        };
+        let carry = mux("carry", &self.carry, left, bits[0], bits[width - 1]);
+        let rotate = mux("rotate", &self.rotate, rotating, zero, carry);
+        let fill = mux("fill", &self.fill, through_carry, rotate, carry_in);
+        let outputs: Vec<Wire> = self
+            .bits
+            .iter()
+            .enumerate()
+            .map(|(bit, selector)| {
+                let from_above = if bit + 1 < width { bits[bit + 1] } else { fill };
+                let from_below = if bit > 0 { bits[bit - 1] } else { fill };
+                mux(
+                    &format!("mux{}", bit),
+                    selector,
+                    left,
+                    from_above,
+                    from_below,
+                )
+            })
+            .collect();
+
+        builder.outputs("out", &outputs);
+        builder.output("carry", carry);
+        builder.build().expect("shifter netlist is well formed")
+    }
+
+    /// Shift bits (LSB first) by one position, returning the new bits and the bit shifted out
+    pub fn compute(
+        &self,
+        bits: &[bool],
+        direction: ShiftDirection,
+        fill: ShiftFill,
+        carry_in: bool,
+    ) -> (Vec<bool>, bool) {
+        let detail = self.compute_detailed(bits, direction, fill, carry_in);
+        (detail.result_bits(), detail.carry.output.bit)
+    }
+
+    /// Shift bits keeping the raw gate activations of every multiplexer
+    pub fn compute_detailed(
+        &self,
+        bits: &[bool],
+        direction: ShiftDirection,
+        fill: ShiftFill,
+        carry_in: bool,
+    ) -> ShifterDetail {
+        assert_eq!(bits.len(), self.width(), "input has the wrong width");
+        let width = self.width();
+
+        // Control lines decoded from the operation
+        let left = direction == ShiftDirection::Left;
+        let rotating = fill == ShiftFill::Rotate;
+        let through_carry = fill == ShiftFill::Carry;
+
+        // Bit shifted out: the LSB going right, the MSB going left
+        let carry = self.carry.compute_detailed(left, bits[0], bits[width - 1]);
+        let rotate = self
+            .rotate
+            .compute_detailed(rotating, false, carry.output.bit);
+        let fill = self
+            .fill
+            .compute_detailed(through_carry, rotate.output.bit, carry_in);
+
+        // Each output takes its right neighbour (going right) or left neighbour (going left)
+        let outputs = self
+            .bits
+            .iter()
+            .enumerate()
+            .map(|(bit, mux)| {
+                let from_above = if bit + 1 < width {
+                    bits[bit + 1]
+                } else {
+                    fill.output.bit
+                };
+                let from_below = if bit > 0 {
+                    bits[bit - 1]
+                } else {
+                    fill.output.bit
+                };
+                mux.compute_detailed(left, from_above, from_below)
+            })
+            .collect();
+
+        ShifterDetail {
+            bits: outputs,
+            carry,
+            fill,
+            rotate,
+        }
+    }
+}
+
+impl Persist for Shifter {
+    fn write_weights(&self, writer: &mut WeightWriter) -> io::Result<()> {
+        writer.line(&format!("shifter {}", self.width()))?;
+        for mux in self
+            .bits
+            .iter()
+            .chain([&self.carry, &self.rotate, &self.fill])
+        {
+            mux.write_weights(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_weights(reader: &mut WeightReader) -> io::Result<Self> {
+        let fields = reader.tagged("shifter")?;
+        let width: usize = match fields.as_slice() {
+            [width] => width
+                .parse()
+                .ok()
+                .filter(|&width| width > 0)
+                .ok_or_else(|| reader.error("invalid shifter width"))?,
+            _ => return Err(reader.error("expected a shifter width")),
+        };
+
+        let bits = (0..width)
+            .map(|_| Mux2::read_weights(reader))
+            .collect::<io::Result<_>>()?;
+        Ok(Shifter {
+            bits,
+            carry: Mux2::read_weights(reader)?,
+            rotate: Mux2::read_weights(reader)?,
+            fill: Mux2::read_weights(reader)?,
+        })
+    }
+}
+
+/// Gates are addressed by multiplexer (`mux0`, `mux1`, ..., `carry`, `rotate`, `fill`)
+/// and then by gate, e.g. `mux0.and_b` or `fill.or`
+impl FaultInjection for Shifter {
+    fn gate_mut(&mut self, path: &[&str]) -> Option<&mut LogicGate> {
+        match path.split_first()? {
+            (&"carry", rest) => self.carry.gate_mut(rest),
+            (&"rotate", rest) => self.rotate.gate_mut(rest),
+            (&"fill", rest) => self.fill.gate_mut(rest),
+            (name, rest) => {
+                let index: usize = name.strip_prefix("mux")?.parse().ok()?;
+                self.bits.get_mut(index)?.gate_mut(rest)
+            }
+        }
+    }
+
+    fn gate_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for (index, mux) in self.bits.iter().enumerate() {
+            paths.extend(child_paths(&format!("mux{}", index), mux));
+        }
+        paths.extend(child_paths("carry", &self.carry));
+        paths.extend(child_paths("rotate", &self.rotate));
+        paths.extend(child_paths("fill", &self.fill));
+        paths
+    }
+}
@@ -0,0 +1,217 @@
+use std::error::Error;
+use std::fmt;
+
+use aspirina::layer::Layer;
+use aspirina::matrix::Matrix;
+
+use crate::gates::GateType;
+
+/// Seed used when random weights are needed but no seed was configured
+pub(crate) const DEFAULT_SEED: u64 = 0x5EED;
+
+/// Starting weights used before a gate is trained
+#[derive(Debug, Clone)]
+pub enum Initialization {
+    HandPicked,           // The hand-tuned weights for each gate type
+    Random { seed: u64 }, // Uniform weights in [-1, 1] from a seeded generator
+    Custom(Vec<Layer>),   // Caller-provided starting layers
+}
+
+/// Settings for training a neural gate
+#[derive(Debug, Clone)]
+pub struct TrainingConfig {
+    pub epochs: usize,                  // Maximum number of training epochs
+    pub initialization: Initialization, // Starting weights
+    pub target_loss: Option<f64>,       // Stop early once the loss drops to this value
+    pub retries: usize, // Extra attempts from fresh random weights if training fails
+}
+
+impl TrainingConfig {
+    /// Default settings: 10,000 epochs from the hand-picked weights, no early stopping
+    pub fn new() -> Self {
+        TrainingConfig {
+            epochs: 10_000,
+            initialization: Initialization::HandPicked,
+            target_loss: None,
+            retries: 0,
+        }
+    }
+
+    /// Set the maximum number of epochs
+    pub fn with_epochs(mut self, epochs: usize) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    /// Start from random weights generated with the given seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.initialization = Initialization::Random { seed };
+        self
+    }
+
+    /// Start from the given layers
+    pub fn with_initial_layers(mut self, layers: Vec<Layer>) -> Self {
+        self.initialization = Initialization::Custom(layers);
+        self
+    }
+
+    /// Stop training once the mean squared error reaches `loss`
+    pub fn with_target_loss(mut self, loss: f64) -> Self {
+        self.target_loss = Some(loss);
+        self
+    }
+
+    /// Re-initialise and retrain up to `retries` more times when the truth table is wrong
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Settings for the given attempt (0 is the first)
+    ///
+    /// Retries start from random weights seeded with `seed + attempt`, using the
+    /// configured seed when there is one.
+    pub fn for_attempt(&self, attempt: usize) -> TrainingConfig {
+        let mut config = self.clone();
+        if attempt > 0 {
+            let seed = match self.initialization {
+                Initialization::Random { seed } => seed,
+                _ => DEFAULT_SEED,
+            };
+            config.initialization = Initialization::Random {
+                seed: seed.wrapping_add(attempt as u64),
+            };
+        }
+        config
+    }
+}
+
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of training a single gate
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingReport {
+    pub loss_curve: Vec<f64>, // Mean squared error at each epoch
+    pub accuracy: f64,        // Fraction of truth table rows answered correctly (0.0-1.0)
+}
+
+impl TrainingReport {
+    /// Number of epochs actually run
+    pub fn epochs(&self) -> usize {
+        self.loss_curve.len()
+    }
+
+    /// Loss after the last epoch
+    pub fn final_loss(&self) -> Option<f64> {
+        self.loss_curve.last().copied()
+    }
+
+    /// Check if every truth table row is answered correctly
+    pub fn converged(&self) -> bool {
+        self.accuracy == 1.0
+    }
+}
+
+/// A truth table row a trained gate answers wrongly
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedRow {
+    pub inputs: Vec<bool>, // Input combination
+    pub expected: bool,    // Expected output
+    pub activation: f64,   // Raw network output (compared against 0.5)
+}
+
+/// Error returned when a gate still has a wrong truth table after every attempt
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvergenceError {
+    pub gate_type: GateType,
+    pub attempts: usize,             // Number of times the gate was trained
+    pub failed_rows: Vec<FailedRow>, // Wrong rows of the last attempt
+    pub report: TrainingReport,      // Report of the last attempt
+}
+
+impl fmt::Display for ConvergenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} gate did not converge after {} attempt(s):",
+            self.gate_type, self.attempts
+        )?;
+        for row in &self.failed_rows {
+            let inputs: Vec<&str> = row
+                .inputs
+                .iter()
+                .map(|&bit| if bit { "1" } else { "0" })
+                .collect();
+            write!(
+                f,
+                " [{}] expected {} got {:.4};",
+                inputs.join(" "),
+                row.expected as u8,
+                row.activation
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConvergenceError {}
+
+/// Build layers with the given shapes (neurons, inputs) and random weights in [-1, 1]
+pub fn random_layers(shapes: &[(usize, usize)], seed: u64) -> Vec<Layer> {
+    let mut rng = SeededRng::new(seed);
+    shapes
+        .iter()
+        .map(|&(neurons, inputs)| {
+            let data = (0..neurons)
+                .map(|_| (0..inputs).map(|_| rng.uniform(-1.0, 1.0)).collect())
+                .collect();
+            Layer::new(Matrix::new(data))
+        })
+        .collect()
+}
+
+/// Small deterministic random generator (SplitMix64) for reproducible experiments
+#[derive(Debug, Clone)]
+pub(crate) struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        SeededRng { state: seed }
+    }
+
+    /// Current state; a generator created from it continues the same sequence
+    pub(crate) fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in [0, 1)
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform value in [low, high)
+    pub(crate) fn uniform(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    /// Normally distributed value with mean 0 (Box-Muller transform)
+    pub(crate) fn gaussian(&mut self, std_dev: f64) -> f64 {
+        let u1 = 1.0 - self.next_f64(); // (0, 1] so ln is finite
+        let u2 = self.next_f64();
+        std_dev * (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
@@ -1,4 +1,33 @@
-use synapse::alu::{ALU, ALUOperation, ALUResult};
+use synapse::alu::{
+    ALU, ALUOperation, ALUResult, ALUUnits, AdderArchitecture, AdderDetail, AdderStats, Comparison,
+};
+use synapse::faults::{Fault, FaultInjection};
+use synapse::gate_factory::{GateFactory, ReferenceGates};
+use synapse::persistence::Persist;
+use synapse::shifter::Shifter;
+
+mod common;
+use common::unit_gate_count;
+
+/// Result and flags of A + B + carry in for an ALU of the given width (below 64 bits)
+fn expected_sum(a: u64, b: u64, carry_in: bool, width: u32) -> ALUResult {
+    let mask = (1 << width) - 1;
+    let sum = a + b + carry_in as u64;
+    let result = sum & mask;
+
+    // Carry into the MSB comes from adding every bit below it
+    let below_msb = mask >> 1;
+    let carry_into_msb = (a & below_msb) + (b & below_msb) + carry_in as u64 > below_msb;
+
+    ALUResult {
+        result,
+        carry: sum > mask,
+        zero: result == 0,
+        negative: result >> (width - 1) & 1 == 1,
+        overflow: carry_into_msb != (sum > mask),
+        half_carry: width >= 4 && (a & 0xF) + (b & 0xF) + carry_in as u64 > 0xF,
+    }
+}
 
 #[test]
 fn test_alu_addition() {
@@ -10,7 +39,10 @@ fn test_alu_addition() {
         ALUResult {
             result: 8,
             carry: false,
-            zero: false
+            zero: false,
+            negative: true,
+            overflow: true,
+            half_carry: false
         }
     );
 
@@ -19,7 +51,10 @@ fn test_alu_addition() {
         ALUResult {
             result: 15,
             carry: false,
-            zero: false
+            zero: false,
+            negative: true,
+            overflow: false,
+            half_carry: false
         }
     );
 
@@ -29,7 +64,10 @@ fn test_alu_addition() {
         ALUResult {
             result: 0,
             carry: true,
-            zero: true
+            zero: true,
+            negative: false,
+            overflow: false,
+            half_carry: true
         }
     );
 
@@ -39,7 +77,10 @@ fn test_alu_addition() {
         ALUResult {
             result: 0,
             carry: false,
-            zero: true
+            zero: true,
+            negative: false,
+            overflow: false,
+            half_carry: false
         }
     );
 }
@@ -54,7 +95,10 @@ fn test_alu_subtraction() {
         ALUResult {
             result: 5,
             carry: true,
-            zero: false
+            zero: false,
+            negative: false,
+            overflow: true,
+            half_carry: true
         }
     );
 
@@ -64,7 +108,10 @@ fn test_alu_subtraction() {
         ALUResult {
             result: 0,
             carry: true,
-            zero: true
+            zero: true,
+            negative: false,
+            overflow: false,
+            half_carry: true
         }
     );
 
@@ -74,7 +121,10 @@ fn test_alu_subtraction() {
         ALUResult {
             result: 14,
             carry: false,
-            zero: false
+            zero: false,
+            negative: true,
+            overflow: false,
+            half_carry: false
         } // -2 = 14 in 4-bit two's complement
     );
 }
@@ -89,7 +139,10 @@ fn test_alu_4bit_masking() {
         ALUResult {
             result: 0,
             carry: true,
-            zero: true
+            zero: true,
+            negative: false,
+            overflow: false,
+            half_carry: true
         }
     );
 
@@ -98,8 +151,400 @@ fn test_alu_4bit_masking() {
         ALUResult {
             result: 1,
             carry: false,
-            zero: false
+            zero: false,
+            negative: false,
+            overflow: false,
+            half_carry: false
+        }
+    );
+}
+
+#[test]
+fn test_alu_detailed_margins() {
+    let alu = ALU::new();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let add = alu.compute_detailed(a, b, ALUOperation::Add);
+            assert_eq!(add.result, alu.compute(a, b, ALUOperation::Add));
+            assert!(add.inverters.is_empty());
+            assert!(add.min_margin() > 0.0);
+
+            // Subtraction also reports the NOT gates inverting B
+            let sub = alu.compute_detailed(a, b, ALUOperation::Subtract);
+            assert_eq!(sub.result, alu.compute(a, b, ALUOperation::Subtract));
+            assert_eq!(sub.inverters.len(), 4);
+            for (bit, inverter) in sub.inverters.iter().enumerate() {
+                assert_eq!(inverter.bit, b & (1 << bit) == 0);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_carry_lookahead_alu() {
+    let alu = ALU::with_architecture(
+        &mut GateFactory::shared(),
+        AdderArchitecture::CarryLookahead,
+    );
+    assert_eq!(alu.architecture(), AdderArchitecture::CarryLookahead);
+    // The adder, one NOT, AND, OR and XOR per bit, then the other units
+    let gate_count = alu.adder_stats().gate_count + 4 * 4 + unit_gate_count(4);
+    assert_eq!(alu.gate_count(), gate_count);
+    assert_eq!(alu.gate_paths().len(), gate_count);
+
+    for a in 0..16u64 {
+        for b in 0..16u64 {
+            let sum = a + b;
+            assert_eq!(
+                alu.compute(a, b, ALUOperation::Add),
+                ALUResult {
+                    result: sum & 0x0F,
+                    carry: sum > 0x0F,
+                    zero: sum & 0x0F == 0,
+                    ..expected_sum(a, b, false, 4)
+                }
+            );
+
+            let difference = a.wrapping_sub(b) & 0x0F;
+            let detail = alu.compute_detailed(a, b, ALUOperation::Subtract);
+            assert_eq!(detail.result.result, difference);
+            assert_eq!(detail.result.carry, a >= b); // No borrow
+            assert!(matches!(detail.adder, Some(AdderDetail::CarryLookahead(_))));
+            assert!(detail.min_margin() > 0.0);
+        }
+    }
+
+    // Saved weights keep the architecture
+    let mut bytes = Vec::new();
+    alu.write_to(&mut bytes).unwrap();
+    let mut loaded = ALU::read_from(bytes.as_slice()).unwrap();
+    assert_eq!(loaded.architecture(), AdderArchitecture::CarryLookahead);
+    assert_eq!(
+        loaded.compute(9, 8, ALUOperation::Add),
+        alu.compute(9, 8, ALUOperation::Add)
+    );
+
+    // Carry out of bit 3 stuck at 1 sets the carry flag
+    loaded
+        .inject_fault("lookahead0.carry3", Fault::StuckAt1)
+        .unwrap();
+    assert!(loaded.compute(0, 0, ALUOperation::Add).carry);
+}
+
+#[test]
+fn test_adder_architecture_stats() {
+    let ripple = AdderArchitecture::RippleCarry.stats(4);
+    let lookahead = AdderArchitecture::CarryLookahead.stats(4);
+
+    // Ripple carry: 5 gates per bit, carry passes through 2 more gates per bit
+    assert_eq!(
+        ripple,
+        AdderStats {
+            gate_count: 20,
+            gate_depth: 9
+        }
+    );
+    // Lookahead: P/G, 10 product terms, carries and sums in 4 levels
+    assert_eq!(
+        lookahead,
+        AdderStats {
+            gate_count: 26,
+            gate_depth: 4
+        }
+    );
+
+    let alu = ALU::new();
+    assert_eq!(alu.adder_stats(), ripple);
+    assert_eq!(alu.gate_paths().len(), ripple.gate_count + 166); // Plus 16 logic, 28 in the shifter, 122 in other units
+}
+
+#[test]
+fn test_wide_alu() {
+    let mut factory = GateFactory::shared();
+    let alu8 = ALU::with_width(&mut factory, 8, AdderArchitecture::RippleCarry);
+    let alu16 = ALU::with_width(&mut factory, 16, AdderArchitecture::RippleCarry);
+    assert_eq!((alu8.width(), alu8.mask()), (8, 0xFF));
+    assert_eq!((alu16.width(), alu16.mask()), (16, 0xFFFF));
+
+    // Same gate types as the 4-bit ALU, so nothing new is trained
+    assert_eq!(factory.trained_count(), 4);
+
+    let pairs = [
+        (200, 55),
+        (200, 56),
+        (0, 0),
+        (255, 255),
+        (17, 240),
+        (128, 1),
+    ];
+    for (a, b) in pairs {
+        let sum = a + b;
+        assert_eq!(
+            alu8.compute(a, b, ALUOperation::Add),
+            ALUResult {
+                result: sum & 0xFF,
+                carry: sum > 0xFF,
+                zero: sum & 0xFF == 0,
+                ..expected_sum(a, b, false, 8)
+            }
+        );
+        let difference = alu8.compute(a, b, ALUOperation::Subtract);
+        assert_eq!(difference.result, a.wrapping_sub(b) & 0xFF);
+        assert_eq!(difference.carry, a >= b);
+    }
+
+    // 16-bit: 500 + 123 and 40000 + 30000 (wraps around)
+    assert_eq!(alu16.compute(500, 123, ALUOperation::Add).result, 623);
+    let wrapped = alu16.compute(40000, 30000, ALUOperation::Add);
+    assert_eq!(wrapped.result, 70000 - 65536);
+    assert!(wrapped.carry);
+    assert_eq!(alu16.compute(1000, 1, ALUOperation::Subtract).result, 999);
+
+    // Half-carry is the carry out of bit 3 at every width, not out of the middle of the word
+    assert!(alu16.compute(0x000F, 1, ALUOperation::Add).half_carry);
+    assert!(!alu16.compute(0x00F0, 0x0010, ALUOperation::Add).half_carry);
+
+    // Inputs are masked to the width
+    assert_eq!(alu8.compute(0x1FF, 1, ALUOperation::Add).result, 0);
+}
+
+#[test]
+fn test_wide_adder_stats() {
+    // Ripple carry delay grows with every bit
+    assert_eq!(
+        AdderArchitecture::RippleCarry.stats(8),
+        AdderStats {
+            gate_count: 40,
+            gate_depth: 17
+        }
+    );
+    // Lookahead chains two 4-bit blocks
+    assert_eq!(
+        AdderArchitecture::CarryLookahead.stats(8),
+        AdderStats {
+            gate_count: 52,
+            gate_depth: 6
         }
     );
 }
 
+#[test]
+fn test_alu_bitwise_logic() {
+    let alu = ALU::new();
+
+    for a in 0..16u64 {
+        for b in 0..16u64 {
+            for (op, expected) in [
+                (ALUOperation::And, a & b),
+                (ALUOperation::Or, a | b),
+                (ALUOperation::Xor, a ^ b),
+                (ALUOperation::Not, !a & 0x0F),
+            ] {
+                let detail = alu.compute_detailed(a, b, op.clone());
+                assert_eq!(
+                    detail.result,
+                    ALUResult {
+                        result: expected,
+                        carry: false,
+                        zero: expected == 0,
+                        negative: expected & 0x08 != 0,
+                        overflow: false,
+                        half_carry: false
+                    },
+                    "{} {:?} {}",
+                    a,
+                    op,
+                    b
+                );
+                // Logic runs through one gate per bit, never the adder
+                assert!(detail.adder.is_none());
+                assert_eq!(detail.logic.len() + detail.inverters.len(), 4);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_alu_shifts_and_rotates() {
+    let alu = ALU::new();
+
+    for a in 0..16u64 {
+        let msb = a & 0x8 != 0;
+        let lsb = a & 0x1 != 0;
+        for carry in [false, true] {
+            for (op, result, carry_out) in [
+                (ALUOperation::ShiftLeft, (a << 1) & 0xF, msb),
+                (ALUOperation::ShiftRight, a >> 1, lsb),
+                (ALUOperation::RotateLeft, ((a << 1) | (a >> 3)) & 0xF, msb),
+                (ALUOperation::RotateRight, (a >> 1) | ((a & 1) << 3), lsb),
+                (
+                    ALUOperation::RotateLeftThroughCarry(carry),
+                    ((a << 1) & 0xF) | carry as u64,
+                    msb,
+                ),
+                (
+                    ALUOperation::RotateRightThroughCarry(carry),
+                    (a >> 1) | ((carry as u64) << 3),
+                    lsb,
+                ),
+            ] {
+                let detail = alu.compute_detailed(a, 0, op.clone());
+                assert_eq!(
+                    detail.result,
+                    ALUResult {
+                        result,
+                        carry: carry_out,
+                        zero: result == 0,
+                        negative: result & 0x08 != 0,
+                        overflow: false,
+                        half_carry: false
+                    },
+                    "{:?} {}",
+                    op,
+                    a
+                );
+                assert!(detail.shifter.is_some());
+                assert!(detail.min_margin() > 0.0);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_alu_compare() {
+    let alu = ALU::new();
+
+    for a in 0..16u64 {
+        for b in 0..16u64 {
+            // Two's complement value of a 4-bit number
+            let signed = |value: u64| ((value as i8) << 4) >> 4;
+            let comparison = alu.compare(a, b);
+            assert_eq!(
+                comparison,
+                Comparison {
+                    equal: a == b,
+                    less: a < b,
+                    greater: a > b,
+                    signed_less: signed(a) < signed(b),
+                    signed_greater: signed(a) > signed(b),
+                },
+                "{} cmp {}",
+                a,
+                b
+            );
+
+            // Flags match the subtraction, which runs on the same adder
+            let detail = alu.compute_detailed(a, b, ALUOperation::Compare);
+            assert_eq!(detail.result, alu.compute(a, b, ALUOperation::Subtract));
+            assert!(detail.adder.is_some());
+        }
+    }
+}
+
+#[test]
+fn test_alu_add_and_subtract_with_carry() {
+    let alu = ALU::new();
+
+    for a in 0..16u64 {
+        for b in 0..16u64 {
+            for carry in [false, true] {
+                assert_eq!(
+                    alu.compute(a, b, ALUOperation::AddWithCarry(carry)),
+                    expected_sum(a, b, carry, 4),
+                    "{} + {} + {}",
+                    a,
+                    b,
+                    carry
+                );
+
+                // Carry clear means the previous word borrowed
+                let borrow = !carry as u64;
+                let difference = alu.compute(a, b, ALUOperation::SubtractWithBorrow(carry));
+                assert_eq!(difference.result, a.wrapping_sub(b + borrow) & 0x0F);
+                assert_eq!(difference.carry, a >= b + borrow);
+                assert_eq!(
+                    difference,
+                    expected_sum(a, !b & 0x0F, carry, 4),
+                    "{} - {} - {}",
+                    a,
+                    b,
+                    borrow
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_alu_multiply() {
+    let alu = ALU::new();
+
+    for a in 0..16u64 {
+        for b in 0..16u64 {
+            let product = a * b;
+            let detail = alu.compute_detailed(a, b, ALUOperation::Multiply);
+            assert_eq!(detail.result.result, product & 0x0F, "{} * {}", a, b);
+            assert_eq!(detail.high_word(), product >> 4, "{} * {}", a, b);
+            assert_eq!(detail.result.carry, product > 0x0F);
+            assert_eq!(detail.result.zero, product == 0);
+            assert!(detail.multiplier.is_some());
+        }
+    }
+    assert_eq!(alu.compute_detailed(3, 5, ALUOperation::Add).high_word(), 0);
+}
+
+#[test]
+fn test_alu_optional_units() {
+    let units = ALUUnits {
+        divider: false,
+        decimal: false,
+        ..ALUUnits::ALL
+    };
+    let alu = ALU::with_units(
+        &mut GateFactory::shared(),
+        4,
+        AdderArchitecture::RippleCarry,
+        units,
+    );
+    assert_eq!(alu.units(), units);
+    assert_eq!(alu.gate_count(), alu.gate_paths().len());
+    let full = ALU::with_factory(&mut ReferenceGates);
+    assert!(alu.gate_count() < full.gate_count());
+    assert!(
+        alu.gate_paths()
+            .iter()
+            .all(|path| !path.starts_with("divider") && !path.starts_with("decimal"))
+    );
+
+    // Operations without their unit leave A unchanged and set overflow
+    assert!(alu.supports(&ALUOperation::Multiply));
+    assert!(!alu.supports(&ALUOperation::Modulo));
+    assert_eq!(alu.compute(3, 5, ALUOperation::Multiply).result, 15);
+    let divided = alu.compute(13, 2, ALUOperation::Divide);
+    assert_eq!(divided.result, 13);
+    assert!(divided.overflow);
+    assert!(alu.self_test().passed());
+
+    // The units are saved with the weights
+    let mut bytes = Vec::new();
+    alu.write_to(&mut bytes).unwrap();
+    let text = String::from_utf8(bytes.clone()).unwrap();
+    assert!(text.contains("\nunits multiplier incrementer\n"));
+    let loaded = ALU::read_from(bytes.as_slice()).unwrap();
+    assert_eq!(loaded.units(), units);
+    assert_eq!(loaded.gate_paths(), alu.gate_paths());
+
+    let bare = ALU::with_units(
+        &mut ReferenceGates,
+        8,
+        AdderArchitecture::CarryLookahead,
+        ALUUnits::NONE,
+    );
+    assert_eq!(
+        bare.gate_count(),
+        bare.adder_stats().gate_count
+            + 4 * 8
+            + Shifter::with_factory(&mut ReferenceGates, 8).gate_count()
+    );
+}
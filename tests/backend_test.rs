@@ -0,0 +1,185 @@
+use synapse::alu::{ALU, ALUOperation, AdderArchitecture};
+use synapse::cpu::CPU;
+use synapse::full_adder::FullAdder;
+use synapse::gate_factory::{GateFactory, ReferenceGates};
+use synapse::gates::{Gate, GateType, ReferenceGate, TruthTable};
+
+fn run_program<G: Gate>(cpu: &mut CPU<G>, program: &[u8], data: &[(u8, u8)]) -> u8 {
+    cpu.reset();
+    for &(addr, value) in data {
+        cpu.memory.write(addr, value);
+    }
+    cpu.load_program(program);
+    cpu.run();
+    cpu.registers.accumulator.read()
+}
+
+#[test]
+fn test_reference_gate_truth_tables() {
+    let gate_types = [
+        GateType::AND,
+        GateType::OR,
+        GateType::XOR,
+        GateType::NOT,
+        GateType::NAND,
+        GateType::NOR,
+        GateType::XNOR,
+        GateType::Custom(TruthTable::majority(3)),
+    ];
+
+    for gate_type in gate_types {
+        let gate = ReferenceGate::new(gate_type.clone());
+        assert_eq!(gate.gate_type(), &gate_type);
+        for (inputs, expected) in gate_type.truth_table() {
+            let signals: Vec<f64> = inputs
+                .iter()
+                .map(|&bit| if bit { 1.0 } else { 0.0 })
+                .collect();
+            let output = gate.compute_detailed(signals);
+            assert_eq!(output.bit, expected, "{} {:?}", gate_type, inputs);
+            assert_eq!(output.margin, 0.5);
+        }
+    }
+}
+
+#[test]
+fn test_reference_full_adder() {
+    let full_adder = FullAdder::with_factory(&mut ReferenceGates);
+
+    for value in 0..8u8 {
+        let (a, b, carry_in) = (value & 1 != 0, value & 2 != 0, value & 4 != 0);
+        let result = full_adder.compute(a, b, carry_in);
+        let total = a as u8 + b as u8 + carry_in as u8;
+        assert_eq!(result.sum, total & 1 != 0);
+        assert_eq!(result.carry, total > 1);
+    }
+}
+
+#[test]
+fn test_alu_backends_agree() {
+    let neural = ALU::with_factory(&mut GateFactory::shared());
+    let reference = ALU::with_factory(&mut ReferenceGates);
+
+    let operations = [
+        ALUOperation::Add,
+        ALUOperation::Subtract,
+        ALUOperation::And,
+        ALUOperation::Or,
+        ALUOperation::Xor,
+        ALUOperation::Not,
+        ALUOperation::Compare,
+        ALUOperation::AddWithCarry(true),
+        ALUOperation::SubtractWithBorrow(false),
+        ALUOperation::Multiply,
+        ALUOperation::Divide,
+        ALUOperation::Modulo,
+        ALUOperation::Increment,
+        ALUOperation::Decrement,
+        ALUOperation::ShiftLeft,
+        ALUOperation::ShiftRight,
+        ALUOperation::RotateLeft,
+        ALUOperation::RotateRight,
+        ALUOperation::RotateLeftThroughCarry(true),
+        ALUOperation::RotateRightThroughCarry(true),
+        ALUOperation::DecimalAdjust {
+            carry: true,
+            half_carry: false,
+            subtract: false,
+        },
+        ALUOperation::DecimalAdjust {
+            carry: false,
+            half_carry: false,
+            subtract: true,
+        },
+    ];
+    for a in 0..16 {
+        for b in 0..16 {
+            for op in operations.clone() {
+                assert_eq!(
+                    neural.compute(a, b, op.clone()),
+                    reference.compute(a, b, op.clone()),
+                    "{} {:?} {}",
+                    a,
+                    op,
+                    b
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_cpu_backends_agree() {
+    let mut neural = CPU::new();
+    let mut reference = CPU::with_factory(&mut ReferenceGates);
+
+    let program = vec![
+        0x1, 0xD, // LOAD D
+        0x3, 0xE, // ADD E
+        0x4, 0xC, // SUB C
+        0x2, 0xB, // STORE B
+        0xF, // HALT
+    ];
+
+    for (x, y, z) in [(5, 3, 2), (15, 1, 0), (0, 0, 1), (9, 9, 9)] {
+        let data = [(0xD, x), (0xE, y), (0xC, z)];
+        assert_eq!(
+            run_program(&mut neural, &program, &data),
+            run_program(&mut reference, &program, &data)
+        );
+        assert_eq!(neural.memory.read(0xB), reference.memory.read(0xB));
+        assert_eq!(
+            (neural.registers.status.zero(), neural.registers.status.carry()),
+            (
+                reference.registers.status.zero(),
+                reference.registers.status.carry()
+            )
+        );
+    }
+
+    // Reference gates always sit exactly half a signal away from the threshold
+    assert_eq!(reference.weakest_margin(), Some(0.5));
+}
+
+#[test]
+fn test_reference_adder_architectures_agree() {
+    let ripple = ALU::with_architecture(&mut ReferenceGates, AdderArchitecture::RippleCarry);
+    let lookahead = ALU::with_architecture(&mut ReferenceGates, AdderArchitecture::CarryLookahead);
+
+    for a in 0..16 {
+        for b in 0..16 {
+            for op in [ALUOperation::Add, ALUOperation::Subtract] {
+                assert_eq!(
+                    ripple.compute(a, b, op.clone()),
+                    lookahead.compute(a, b, op.clone())
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_reference_wide_alus() {
+    for architecture in [
+        AdderArchitecture::RippleCarry,
+        AdderArchitecture::CarryLookahead,
+    ] {
+        // 6 bits: one full lookahead block and one partial block
+        let alu = ALU::with_width(&mut ReferenceGates, 6, architecture);
+        for a in 0..64u64 {
+            for b in 0..64u64 {
+                let sum = alu.compute(a, b, ALUOperation::Add);
+                assert_eq!(sum.result, (a + b) & 0x3F);
+                assert_eq!(sum.carry, a + b > 0x3F);
+
+                let difference = alu.compute(a, b, ALUOperation::Subtract);
+                assert_eq!(difference.result, a.wrapping_sub(b) & 0x3F);
+                assert_eq!(difference.carry, a >= b);
+            }
+        }
+    }
+
+    let alu = ALU::with_width(&mut ReferenceGates, 64, AdderArchitecture::CarryLookahead);
+    let result = alu.compute(u64::MAX, 1, ALUOperation::Add);
+    assert_eq!((result.result, result.carry, result.zero), (0, true, true));
+}
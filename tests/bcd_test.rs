@@ -0,0 +1,205 @@
+use synapse::alu::{ALU, ALUOperation, AdderArchitecture};
+use synapse::bcd::DecimalAdjuster;
+use synapse::faults::{Fault, FaultInjection};
+use synapse::gate_factory::ReferenceGates;
+use synapse::persistence::Persist;
+
+/// Add or subtract two packed BCD numbers on the ALU, then decimal adjust
+fn decimal<G: synapse::gates::Gate>(
+    alu: &ALU<G>,
+    a: u64,
+    b: u64,
+    carry: bool,
+    subtract: bool,
+) -> (u64, bool) {
+    let operation = if subtract {
+        ALUOperation::SubtractWithBorrow(carry)
+    } else {
+        ALUOperation::AddWithCarry(carry)
+    };
+    let binary = alu.compute(a, b, operation);
+    let adjusted = alu.compute(
+        binary.result,
+        0,
+        ALUOperation::DecimalAdjust {
+            carry: binary.carry,
+            half_carry: binary.half_carry,
+            subtract,
+        },
+    );
+    (adjusted.result, adjusted.carry)
+}
+
+fn packed(value: u64) -> u64 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+#[test]
+fn test_decimal_adjust_digits() {
+    let alu = ALU::new();
+
+    for a in 0..10u64 {
+        for b in 0..10u64 {
+            for carry in [false, true] {
+                let sum = a + b + carry as u64;
+                assert_eq!(
+                    decimal(&alu, a, b, carry, false),
+                    (sum % 10, sum >= 10),
+                    "{} + {} + {}",
+                    a,
+                    b,
+                    carry as u8
+                );
+
+                // Carry clear means a borrow in, carry set means no borrow out
+                let difference = a as i64 - b as i64 - !carry as i64;
+                assert_eq!(
+                    decimal(&alu, a, b, carry, true),
+                    (difference.rem_euclid(10) as u64, difference >= 0),
+                    "{} - {} - {}",
+                    a,
+                    b,
+                    !carry as u8
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_decimal_adjust_detail() {
+    let alu = ALU::new();
+
+    // 8 + 7 = 0xF is above 9: 6 is added on the adder
+    let detail = alu.compute_detailed(
+        0xF,
+        0,
+        ALUOperation::DecimalAdjust {
+            carry: false,
+            half_carry: false,
+            subtract: false,
+        },
+    );
+    let digit = &detail.decimal.as_ref().unwrap().digits[0];
+    assert!(digit.over_nine.bit);
+    assert!(digit.adjust.output.bit);
+    assert!(digit.correction.adder.is_some());
+    assert_eq!(detail.result.result, 5);
+    assert!(detail.result.carry);
+    assert!(detail.min_margin() > 0.0);
+
+    // A valid digit without carry is left alone
+    let kept = alu.compute(
+        7,
+        0,
+        ALUOperation::DecimalAdjust {
+            carry: false,
+            half_carry: true,
+            subtract: false,
+        },
+    );
+    assert_eq!((kept.result, kept.carry), (7, false));
+}
+
+#[test]
+fn test_packed_bcd_bytes() {
+    let reference = ALU::with_width(&mut ReferenceGates, 8, AdderArchitecture::RippleCarry);
+
+    for a in 0..100u64 {
+        for b in 0..100u64 {
+            let sum = a + b;
+            assert_eq!(
+                decimal(&reference, packed(a), packed(b), false, false),
+                (packed(sum % 100), sum >= 100),
+                "{} + {}",
+                a,
+                b
+            );
+
+            let difference = a as i64 - b as i64;
+            assert_eq!(
+                decimal(&reference, packed(a), packed(b), true, true),
+                (packed(difference.rem_euclid(100) as u64), difference >= 0),
+                "{} - {}",
+                a,
+                b
+            );
+        }
+    }
+
+    // The neural byte-wide ALU agrees, including a carry rippling through a corrected digit
+    let neural = ALU::with_width(
+        &mut synapse::gate_factory::GateFactory::shared(),
+        8,
+        AdderArchitecture::CarryLookahead,
+    );
+    assert_eq!(decimal(&neural, 0x95, 0x05, false, false), (0x00, true));
+    assert_eq!(decimal(&neural, 0x38, 0x45, false, false), (0x83, false));
+    assert_eq!(decimal(&neural, 0x20, 0x01, true, true), (0x19, true));
+}
+
+#[test]
+fn test_decimal_adjust_widths() {
+    // The half-carry flag is the low digit's carry at any width: 9 + 9 = 18
+    let alu6 = ALU::with_width(&mut ReferenceGates, 6, AdderArchitecture::RippleCarry);
+    assert_eq!(decimal(&alu6, 0x09, 0x09, false, false), (0x18, false));
+    // Its top 2 bits count tens up to 39, so 35 + 5 wraps to 0 with a decimal carry
+    assert_eq!(decimal(&alu6, 0x35, 0x05, false, false), (0x00, true));
+
+    // Wider ALUs cannot know the carries between their middle digits
+    let alu16 = ALU::with_width(&mut ReferenceGates, 16, AdderArchitecture::RippleCarry);
+    let rejected = alu16.compute(
+        0x1299,
+        0,
+        ALUOperation::DecimalAdjust {
+            carry: false,
+            half_carry: false,
+            subtract: false,
+        },
+    );
+    assert_eq!(rejected.result, 0x1299);
+    assert!(rejected.overflow);
+}
+
+#[test]
+fn test_decimal_adjuster_save_load_and_faults() {
+    let mut adjuster = DecimalAdjuster::new(2);
+    let mut bytes = Vec::new();
+    adjuster.write_to(&mut bytes).unwrap();
+    let loaded = DecimalAdjuster::read_from(bytes.as_slice()).unwrap();
+    assert_eq!(loaded.digits(), 2);
+    assert_eq!(loaded.gate_paths(), adjuster.gate_paths());
+
+    // Five decision gates and a select multiplexer per digit
+    assert_eq!(adjuster.gate_paths().len(), 2 * (5 + 4));
+    assert!(
+        adjuster
+            .gate_paths()
+            .contains(&"digit0.select.and_b".to_string())
+    );
+    assert!(
+        adjuster
+            .gate_paths()
+            .contains(&"digit1.over_nine".to_string())
+    );
+    assert!(
+        adjuster
+            .inject_fault("digit2.add", Fault::StuckAt0)
+            .is_err()
+    );
+
+    // Without the "above 9" detection 0xC is left as it is
+    let mut alu = ALU::new();
+    alu.inject_fault("decimal.digit0.over_nine", Fault::StuckAt0)
+        .unwrap();
+    let result = alu.compute(
+        0xC,
+        0,
+        ALUOperation::DecimalAdjust {
+            carry: false,
+            half_carry: false,
+            subtract: false,
+        },
+    );
+    assert_eq!(result.result, 0xC);
+}
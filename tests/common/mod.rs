@@ -0,0 +1,32 @@
+//! Helpers shared by the integration tests
+#![allow(dead_code)]
+
+use synapse::bcd::DecimalAdjuster;
+use synapse::divider::Divider;
+use synapse::gate_factory::ReferenceGates;
+use synapse::incrementer::Incrementer;
+use synapse::multiplier::ArrayMultiplier;
+use synapse::shifter::Shifter;
+
+/// Bits of a value, LSB first
+pub fn bits(value: u64, width: usize) -> Vec<bool> {
+    (0..width).map(|bit| value >> bit & 1 == 1).collect()
+}
+
+/// Value of bits given LSB first
+pub fn value(bits: &[bool]) -> u64 {
+    bits.iter()
+        .rev()
+        .fold(0, |value, &bit| (value << 1) | bit as u64)
+}
+
+/// Gates of the shifter, multiplier, divider, decimal adjust logic and incrementer of an
+/// ALU up to 8 bits wide, counted by each unit
+pub fn unit_gate_count(width: usize) -> usize {
+    let factory = &mut ReferenceGates;
+    Shifter::with_factory(factory, width).gate_count()
+        + ArrayMultiplier::with_factory(factory, width).gate_count()
+        + Divider::with_factory(factory, width).gate_count()
+        + DecimalAdjuster::with_factory(factory, width / 4).gate_count()
+        + Incrementer::with_factory(factory, width).gate_count()
+}
@@ -1,4 +1,6 @@
-use synapse::cpu::{CPU, Instruction};
+use synapse::alu::{ALU, ALUOperation, ALUUnits, AdderArchitecture};
+use synapse::cpu::{CPU, CPUError, Instruction};
+use synapse::gate_factory::ReferenceGates;
 
 #[test]
 fn test_cpu_simple_addition() {
@@ -64,6 +66,64 @@ fn test_cpu_load_store() {
     assert_eq!(cpu.registers.accumulator.read(), 9);
 }
 
+#[test]
+fn test_cpu_with_alu() {
+    let alu = ALU::with_factory(&mut ReferenceGates);
+    let mut cpu = CPU::with_alu(alu).unwrap();
+    cpu.load_program(&[0x5, 0x7, 0x3, 0xE, 0xF]);
+    cpu.memory.write(0xE, 0x5);
+    cpu.run();
+    assert_eq!(cpu.registers.accumulator.read(), 0xC);
+
+    let wide = ALU::with_width(&mut ReferenceGates, 8, AdderArchitecture::RippleCarry);
+    let error = CPU::with_alu(wide).unwrap_err();
+    assert_eq!(error, CPUError::ALUWidth(8));
+    assert_eq!(
+        error.to_string(),
+        "the CPU needs a 4-bit ALU but the ALU is 8 bits wide"
+    );
+
+    let bare = ALU::with_units(
+        &mut ReferenceGates,
+        4,
+        AdderArchitecture::RippleCarry,
+        ALUUnits::NONE,
+    );
+    assert!(matches!(
+        CPU::with_alu(bare),
+        Err(CPUError::MissingUnits(ALUUnits::NONE))
+    ));
+}
+
+#[test]
+fn test_cpu_loads_keep_carry() {
+    let mut cpu = CPU::new();
+
+    // Loads used to clear the carry; they keep it now so ADC and SBC can chain nibbles
+    let program = vec![
+        0x5, 0xF, // LDI 15
+        0x3, 0xD, // ADD D (contains 1), setting the carry
+        0x1, 0xE, // LOAD E (contains 2)
+        0x2, 0xC, // STORE C
+        0x5, 0x9, // LDI 9
+        0xF, // HALT
+    ];
+    cpu.memory.write(0xD, 1);
+    cpu.memory.write(0xE, 2);
+    cpu.load_program(&program);
+
+    for _ in 0..3 {
+        cpu.cycle();
+    }
+    let status = cpu.registers.status;
+    assert!(status.carry() && !status.zero()); // LOAD sets Z from the value, keeps C
+
+    cpu.run();
+    let status = cpu.registers.status;
+    assert_eq!(cpu.registers.accumulator.read(), 9);
+    assert!(status.carry() && !status.zero() && status.negative());
+}
+
 #[test]
 fn test_cpu_overflow() {
     let mut cpu = CPU::new();
@@ -82,11 +142,11 @@ fn test_cpu_overflow() {
 
     // Result should be 1 (17 & 0x0F = 1) with carry flag set
     let low_nibble = cpu.memory.read(0xE);
-    let carry = if cpu.registers.carry_flag { 1 } else { 0 };
+    let carry = if cpu.registers.status.carry() { 1 } else { 0 };
     let full_result = (carry * 16) + low_nibble;
 
     assert_eq!(low_nibble, 1);
-    assert!(cpu.registers.carry_flag);
+    assert!(cpu.registers.status.carry());
     assert_eq!(full_result, 17);
 }
 
@@ -146,3 +206,382 @@ fn test_cpu_program_counter() {
     assert!(cpu.halted);
 }
 
+#[test]
+fn test_cpu_weakest_margins() {
+    let mut cpu = CPU::new();
+
+    let program = vec![
+        0x5, 0x5, // LDI 5
+        0x3, 0xD, // ADD D
+        0x4, 0xD, // SUB D
+        0x2, 0xE, // STORE E
+        0xF, // HALT
+    ];
+
+    cpu.memory.write(0xD, 3);
+    cpu.load_program(&program);
+    cpu.run();
+
+    // Only instructions that use the ALU are tracked, keyed by mnemonic
+    let tracked: Vec<&str> = cpu.weakest_margins.keys().copied().collect();
+    assert_eq!(tracked, vec!["ADD", "SUB"]);
+    assert_eq!(Instruction::ADD(0xD).mnemonic(), tracked[0]);
+
+    let add_margin = cpu.weakest_margins["ADD"];
+    let expected = cpu.alu.compute_detailed(5, 3, ALUOperation::Add).min_margin();
+    assert_eq!(add_margin, expected);
+    assert_eq!(
+        cpu.weakest_margin(),
+        Some(add_margin.min(cpu.weakest_margins["SUB"]))
+    );
+
+    cpu.reset();
+    assert_eq!(cpu.weakest_margin(), None);
+}
+
+#[test]
+fn test_cpu_bitwise_logic() {
+    let mut cpu = CPU::new();
+
+    // 1100 combined with memory[D] = 1010
+    for (opcode, expected) in [(0x6, 0b1000), (0x7, 0b1110), (0x8, 0b0110)] {
+        let program = vec![
+            0x5, 0xC, // LDI 1100
+            opcode, 0xD, // AND/OR/XOR D
+            0xF, // HALT
+        ];
+
+        cpu.reset();
+        cpu.memory.write(0xD, 0b1010);
+        cpu.load_program(&program);
+        cpu.run();
+
+        assert_eq!(cpu.registers.accumulator.read(), expected);
+        assert!(!cpu.registers.status.zero());
+        assert!(!cpu.registers.status.carry());
+    }
+    assert!(cpu.weakest_margins.contains_key("XOR"));
+
+    // XOR with itself clears the accumulator and sets the zero flag
+    cpu.reset();
+    cpu.memory.write(0xD, 0b0101);
+    cpu.load_program(&[0x1, 0xD, 0x8, 0xD, 0xF]); // LOAD D, XOR D, HALT
+    cpu.run();
+    assert_eq!(cpu.registers.accumulator.read(), 0);
+    assert!(cpu.registers.status.zero());
+}
+
+#[test]
+fn test_cpu_shifts_and_rotates() {
+    let mut cpu = CPU::new();
+
+    // 0110 * 2 = 1100, then / 2 = 0110
+    let program = vec![
+        0x5, 0x6, // LDI 6
+        0xE, 0x0, // SHL
+        0x2, 0xD, // STORE D
+        0xE, 0x1, // SHR
+        0xF, // HALT
+    ];
+    cpu.load_program(&program);
+    cpu.run();
+    assert_eq!(cpu.memory.read(0xD), 0b1100);
+    assert_eq!(cpu.registers.accumulator.read(), 0b0110);
+    assert!(!cpu.registers.status.carry());
+
+    // Rotating 1001 left moves the MSB into bit 0 and the carry
+    cpu.reset();
+    cpu.load_program(&[0x5, 0x9, 0xE, 0x2, 0xF]); // LDI 9, ROL, HALT
+    cpu.run();
+    assert_eq!(cpu.registers.accumulator.read(), 0b0011);
+    assert!(cpu.registers.status.carry());
+
+    // Through carry: SHR leaves 1 in carry, RCR moves it into the MSB
+    cpu.reset();
+    cpu.load_program(&[0x5, 0x3, 0xE, 0x1, 0xE, 0x5, 0xF]); // LDI 3, SHR, RCR, HALT
+    cpu.run();
+    assert_eq!(cpu.registers.accumulator.read(), 0b1000);
+    assert!(cpu.registers.status.carry()); // Bit 0 of 0001 shifted out
+    assert!(cpu.weakest_margins.contains_key("RCR"));
+}
+
+#[test]
+fn test_cpu_compare() {
+    let mut cpu = CPU::new();
+
+    // CMP sets Z when equal and C when there is no borrow (A >= B), keeping A
+    for (a, b, zero, carry) in [
+        (5, 5, true, true),
+        (7, 3, false, true),
+        (2, 9, false, false),
+    ] {
+        let program = vec![
+            0x5, a, // LDI a
+            0x9, 0xD, // CMP D
+            0xF, // HALT
+        ];
+
+        cpu.reset();
+        cpu.memory.write(0xD, b);
+        cpu.load_program(&program);
+        cpu.run();
+
+        assert_eq!(cpu.registers.accumulator.read(), a);
+        assert_eq!(cpu.registers.status.zero(), zero, "{} cmp {}", a, b);
+        assert_eq!(cpu.registers.status.carry(), carry, "{} cmp {}", a, b);
+    }
+    assert!(cpu.weakest_margins.contains_key("CMP"));
+}
+
+#[test]
+fn test_cpu_multi_nibble_arithmetic() {
+    let mut cpu = CPU::new();
+
+    // 8-bit sum in one program: low nibble at D + F, high nibble at C + E
+    let program = vec![
+        0x1, 0xD, // LOAD D (low nibble of a)
+        0x3, 0xF, // ADD F (low nibble of b)
+        0x2, 0xD, // STORE D (low nibble of the sum)
+        0x1, 0xC, // LOAD C (high nibble of a), keeping the carry
+        0xA, 0xE, // ADC E (high nibble of b)
+        0xF, // HALT
+    ];
+    for (a, b) in [(0x2Fu8, 0x01u8), (0x9C, 0x48), (0xFF, 0x01), (0x12, 0x34)] {
+        cpu.reset();
+        cpu.memory.write(0xC, a >> 4);
+        cpu.memory.write(0xD, a & 0x0F);
+        cpu.memory.write(0xE, b >> 4);
+        cpu.memory.write(0xF, b & 0x0F);
+        cpu.load_program(&program);
+        cpu.run();
+
+        let sum = a as u16 + b as u16;
+        let high = cpu.registers.accumulator.read() as u16;
+        let low = cpu.memory.read(0xD) as u16;
+        assert_eq!(high << 4 | low, sum & 0xFF, "{} + {}", a, b);
+        assert_eq!(cpu.registers.status.carry(), sum > 0xFF);
+    }
+
+    // 12-bit difference, one nibble per run with the borrow chained by SBC
+    let (a, b) = (0x503u16, 0x1F7u16);
+    let mut difference = 0;
+    cpu.reset();
+    for nibble in 0..3 {
+        let opcode = if nibble == 0 { 0x4 } else { 0xB }; // SUB, then SBC
+        cpu.load_program(&[0x1, 0xD, opcode, 0xE, 0x2, 0xF, 0xF]); // LOAD D, SUB/SBC E, STORE F, HALT
+        cpu.memory.write(0xD, ((a >> (4 * nibble)) & 0x0F) as u8);
+        cpu.memory.write(0xE, ((b >> (4 * nibble)) & 0x0F) as u8);
+        cpu.restart();
+        cpu.run();
+        difference |= (cpu.memory.read(0xF) as u16) << (4 * nibble);
+    }
+    assert_eq!(difference, a - b);
+    assert!(cpu.registers.status.carry()); // No borrow out of the top nibble
+    assert!(cpu.weakest_margins.contains_key("SBC"));
+}
+
+#[test]
+fn test_cpu_signed_flags() {
+    let mut cpu = CPU::new();
+
+    // 7 + 1 overflows as a signed number: 0111 + 0001 = 1000 (-8)
+    cpu.memory.write(0xD, 1);
+    cpu.load_program(&[0x5, 0x7, 0x3, 0xD, 0xF]); // LDI 7, ADD D, HALT
+    cpu.run();
+    let status = cpu.registers.status;
+    assert_eq!(cpu.registers.accumulator.read(), 0b1000);
+    assert!(status.negative());
+    assert!(status.overflow());
+    assert!(!status.half_carry()); // 0111 + 0001 does not carry out of bit 3
+    assert!(!status.carry());
+
+    // CMP of -2 (1110) with 3: N != V means less than as signed numbers
+    for (a, b, signed_less) in [(0b1110, 3, true), (3, 0b1110, false), (0b1000, 1, true)] {
+        cpu.reset();
+        cpu.memory.write(0xD, b);
+        cpu.load_program(&[0x5, a, 0x9, 0xD, 0xF]); // LDI a, CMP D, HALT
+        cpu.run();
+        let status = cpu.registers.status;
+        assert_eq!(
+            status.negative() != status.overflow(),
+            signed_less,
+            "{} cmp {}",
+            a,
+            b
+        );
+        assert_eq!(cpu.registers.accumulator.read(), a);
+    }
+}
+
+#[test]
+fn test_cpu_multiply() {
+    let mut cpu = CPU::new();
+
+    let program = vec![
+        0x1, 0xE, // LOAD E
+        0xC, 0xF, // MUL F
+        0x2, 0xD, // STORE D
+        0xF, // HALT
+    ];
+    for a in 0..16u8 {
+        for b in 0..16u8 {
+            cpu.reset();
+            cpu.memory.write(0xE, a);
+            cpu.memory.write(0xF, b);
+            cpu.load_program(&program);
+            cpu.run();
+
+            let product = a as u16 * b as u16;
+            let low = cpu.memory.read(0xD) as u16;
+            let high = cpu.registers.high.read() as u16;
+            assert_eq!(high << 4 | low, product, "{} * {}", a, b);
+            assert_eq!(cpu.registers.status.carry(), product > 0x0F);
+            assert_eq!(cpu.registers.status.zero(), product == 0);
+        }
+    }
+    assert!(cpu.weakest_margins.contains_key("MUL"));
+}
+
+#[test]
+fn test_cpu_divide_and_modulo() {
+    let mut cpu = CPU::new();
+
+    let program = vec![
+        0x1, 0xE, // LOAD E
+        0xD, 0xF, // DIV F
+        0x2, 0xC, // STORE C
+        0x1, 0xE, // LOAD E
+        0xE, 0x6, 0xF, // MOD F
+        0xF, // HALT
+    ];
+    for a in 0..16u8 {
+        for b in 1..16u8 {
+            cpu.reset();
+            cpu.memory.write(0xE, a);
+            cpu.memory.write(0xF, b);
+            cpu.load_program(&program);
+            cpu.run();
+
+            assert_eq!(cpu.memory.read(0xC), a / b, "{} / {}", a, b);
+            assert_eq!(cpu.registers.high.read(), a % b, "{} % {}", a, b);
+            assert_eq!(cpu.registers.accumulator.read(), a % b, "{} % {}", a, b);
+            assert!(!cpu.registers.status.overflow());
+        }
+    }
+    assert!(cpu.weakest_margins.contains_key("DIV"));
+    assert!(cpu.weakest_margins.contains_key("MOD"));
+
+    // Division by zero leaves the accumulator alone and sets the overflow flag
+    cpu.reset();
+    cpu.memory.write(0xE, 9);
+    cpu.load_program(&[0x1, 0xE, 0xD, 0xF, 0xF]); // LOAD E; DIV F; HALT
+    cpu.run();
+    assert!(cpu.halted);
+    assert_eq!(cpu.registers.accumulator.read(), 9);
+    assert_eq!(cpu.registers.high.read(), 0);
+    assert!(cpu.registers.status.overflow());
+    assert!(!cpu.registers.status.carry());
+}
+
+#[test]
+fn test_cpu_base_conversion() {
+    let mut cpu = CPU::new();
+
+    // One digit per run: memory[D] = value % base, then value / base stays in the accumulator
+    let program = vec![
+        0x2, 0xE, // STORE E (value)
+        0xE, 0x6, 0xF, // MOD F (base)
+        0x2, 0xD, // STORE D (digit)
+        0x1, 0xE, // LOAD E
+        0xD, 0xF, // DIV F
+        0xF, // HALT
+    ];
+    for (value, base, digits) in [
+        (13, 3, vec![1, 1, 1]),
+        (14, 2, vec![0, 1, 1, 1]),
+        (15, 4, vec![3, 3]),
+    ] {
+        cpu.reset();
+        cpu.load_program(&program);
+        cpu.memory.write(0xF, base);
+        cpu.registers.accumulator.write(value);
+
+        let mut converted = Vec::new();
+        while !cpu.registers.accumulator.is_zero() {
+            cpu.restart();
+            cpu.run();
+            converted.push(cpu.memory.read(0xD));
+        }
+        assert_eq!(converted, digits, "{} in base {}", value, base);
+    }
+}
+
+#[test]
+fn test_cpu_decimal_arithmetic() {
+    let mut cpu = CPU::new();
+
+    // One decimal digit per run: memory[F] = memory[D] op memory[E], chained by the carry flag
+    let decimal = |cpu: &mut CPU, a: u16, b: u16, first: u8, chained: u8| -> (u16, bool) {
+        cpu.reset();
+        let mut result = 0;
+        for digit in 0..3 {
+            let scale = 10u16.pow(digit);
+            let opcode = if digit == 0 { first } else { chained };
+            cpu.load_program(&[
+                0x1, 0xD, // LOAD D
+                opcode, 0xE, // ADD/ADC/SUB/SBC E
+                0xE, 0x7, // DAA
+                0x2, 0xF, // STORE F
+                0xF, // HALT
+            ]);
+            cpu.memory.write(0xD, (a / scale % 10) as u8);
+            cpu.memory.write(0xE, (b / scale % 10) as u8);
+            cpu.restart();
+            cpu.run();
+            result += cpu.memory.read(0xF) as u16 * scale;
+        }
+        (result, cpu.registers.status.carry())
+    };
+
+    assert_eq!(decimal(&mut cpu, 456, 789, 0x3, 0xA), (245, true)); // 1245
+    assert_eq!(decimal(&mut cpu, 199, 1, 0x3, 0xA), (200, false));
+    assert_eq!(decimal(&mut cpu, 420, 175, 0x4, 0xB), (245, true));
+    assert_eq!(decimal(&mut cpu, 175, 420, 0x4, 0xB), (755, false)); // Borrow: 1000 - 245
+    assert!(cpu.registers.status.subtract());
+    assert!(cpu.weakest_margins.contains_key("DAA"));
+}
+
+#[test]
+fn test_cpu_subtract_flag_follows_every_flag_write() {
+    let mut cpu = CPU::new();
+
+    // Each instruction after the SUB rewrites the subtract flag
+    for (opcode, subtract) in [
+        (0x3, false), // ADD
+        (0x9, true),  // CMP
+        (0xC, false), // MUL
+        (0xD, false), // DIV
+    ] {
+        cpu.reset();
+        cpu.load_program(&[0x5, 0x6, 0x4, 0xE, opcode, 0xD, 0xF]);
+        cpu.memory.write(0xD, 0x2);
+        cpu.memory.write(0xE, 0x1);
+        cpu.run();
+        assert_eq!(cpu.registers.status.subtract(), subtract, "{:X}", opcode);
+    }
+
+    // MOD by zero and DAA after a sum clear it too
+    cpu.reset();
+    cpu.load_program(&[0x5, 0x6, 0x4, 0xE, 0xE, 0x6, 0xD, 0xF]);
+    cpu.memory.write(0xE, 0x1);
+    cpu.run();
+    assert!(cpu.registers.status.overflow());
+    assert!(!cpu.registers.status.subtract());
+
+    cpu.reset();
+    cpu.load_program(&[0x5, 0x6, 0x4, 0xE, 0x3, 0xE, 0xE, 0x7, 0xF]);
+    cpu.memory.write(0xE, 0x1);
+    cpu.run();
+    assert_eq!(cpu.registers.accumulator.read(), 0x6);
+    assert!(!cpu.registers.status.subtract());
+}
@@ -0,0 +1,111 @@
+use synapse::alu::{ALU, ALUOperation, AdderArchitecture};
+use synapse::divider::Divider;
+use synapse::faults::{Fault, FaultInjection};
+use synapse::gate_factory::ReferenceGates;
+use synapse::persistence::Persist;
+
+#[test]
+fn test_divide_all_4_bit_pairs() {
+    let alu = ALU::new();
+
+    for a in 0..16u64 {
+        for b in 1..16u64 {
+            let detail = alu.compute_detailed(a, b, ALUOperation::Divide);
+            assert_eq!(detail.result.result, a / b, "{} / {}", a, b);
+            assert_eq!(detail.remainder(), a % b, "{} % {}", a, b);
+            assert_eq!(detail.result.zero, a < b);
+            assert!(!detail.result.overflow);
+            assert!(!detail.divide_by_zero());
+            assert_eq!(detail.divider.as_ref().unwrap().steps.len(), 4);
+            assert!(detail.min_margin() > 0.0);
+
+            let modulo = alu.compute(a, b, ALUOperation::Modulo);
+            assert_eq!(modulo.result, a % b, "{} % {}", a, b);
+            assert_eq!(modulo.zero, a % b == 0);
+        }
+    }
+}
+
+#[test]
+fn test_divide_by_zero_sets_overflow() {
+    let alu = ALU::new();
+
+    for a in 0..16u64 {
+        let detail = alu.compute_detailed(a, 0, ALUOperation::Divide);
+        assert!(detail.divide_by_zero());
+        assert!(detail.result.overflow);
+        assert_eq!(detail.result.result, 0xF); // Every trial subtraction succeeds
+        assert_eq!(detail.remainder(), a);
+    }
+    assert!(
+        !alu.compute_detailed(7, 0, ALUOperation::Add)
+            .divide_by_zero()
+    );
+
+    // The flag comes from the divider's own NOR over the divisor bits
+    let mut alu = ALU::new();
+    alu.inject_fault("divider.zero", Fault::StuckAt0).unwrap();
+    let detail = alu.compute_detailed(9, 0, ALUOperation::Divide);
+    assert!(!detail.divide_by_zero());
+    assert!(!detail.result.overflow);
+    alu.clear_faults();
+    assert!(alu.compute(9, 0, ALUOperation::Divide).overflow);
+    alu.inject_fault("divider.nonzero2", Fault::StuckAt1)
+        .unwrap();
+    assert!(!alu.compute(9, 0, ALUOperation::Divide).overflow);
+}
+
+#[test]
+fn test_reference_divider_widths() {
+    for width in [1, 2, 3, 6] {
+        let alu = ALU::with_width(
+            &mut ReferenceGates,
+            width,
+            AdderArchitecture::CarryLookahead,
+        );
+        for a in 0..1u64 << width {
+            for b in 1..1u64 << width {
+                let detail = alu.compute_detailed(a, b, ALUOperation::Divide);
+                assert_eq!(
+                    detail.result.result,
+                    a / b,
+                    "{} / {} ({} bits)",
+                    a,
+                    b,
+                    width
+                );
+                assert_eq!(detail.remainder(), a % b, "{} % {} ({} bits)", a, b, width);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_divider_save_load_and_faults() {
+    let mut divider = Divider::new(3);
+    let mut bytes = Vec::new();
+    divider.write_to(&mut bytes).unwrap();
+    let loaded = Divider::read_from(bytes.as_slice()).unwrap();
+    assert_eq!(loaded.width(), 3);
+    assert_eq!(loaded.gate_paths(), divider.gate_paths());
+
+    // One OR gate for the quotient bit, one four-gate multiplexer per remainder bit,
+    // then the divisor NOR: an OR chain and a NOT
+    let paths = divider.gate_paths();
+    assert_eq!(paths.len(), 1 + 3 * 4 + 2 + 1);
+    assert_eq!(paths[..3], ["quotient", "restore0.not", "restore0.and_a"]);
+    assert_eq!(paths[13..], ["nonzero0", "nonzero1", "zero"]);
+    assert!(
+        divider
+            .inject_fault("restore3.or", Fault::StuckAt0)
+            .is_err()
+    );
+
+    // A stuck quotient gate restores the remainder at every step
+    let mut alu = ALU::new();
+    alu.inject_fault("divider.quotient", Fault::StuckAt0)
+        .unwrap();
+    let detail = alu.compute_detailed(13, 4, ALUOperation::Divide);
+    assert_eq!(detail.result.result, 0);
+    assert_eq!(detail.remainder(), 13);
+}
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use synapse::alu::{ALU, ALUOperation, ALUUnits, AdderArchitecture};
+use synapse::circuit::Circuit;
+use synapse::cpu::CPU;
+use synapse::decoder::Decoder;
+use synapse::export::{to_dot, to_verilog};
+use synapse::full_adder::FullAdder;
+use synapse::gate_factory::ReferenceGates;
+use synapse::gates::{GateType, TruthTable};
+use synapse::half_adder::HalfAdder;
+use synapse::netlist::NetlistBuilder;
+
+mod common;
+use common::{bits, value};
+
+/// Output ports by name after simulating a circuit
+type Outputs = HashMap<String, bool>;
+
+/// Simulate the circuit with its inputs given as words: `("a", 5)` sets `a0` and `a2`;
+/// inputs not given are 0
+fn simulate(circuit: &Circuit, words: &[(&str, u64)]) -> Outputs {
+    let inputs: Vec<bool> = circuit
+        .netlist()
+        .input_names()
+        .iter()
+        .map(|port| {
+            let name = port.trim_end_matches(|c: char| c.is_ascii_digit());
+            let bit: usize = port[name.len()..].parse().unwrap_or(0);
+            words
+                .iter()
+                .find(|(word, _)| *word == name)
+                .is_some_and(|(_, value)| value >> bit & 1 == 1)
+        })
+        .collect();
+    circuit
+        .netlist()
+        .output_names()
+        .into_iter()
+        .map(str::to_string)
+        .zip(circuit.evaluate(&inputs))
+        .collect()
+}
+
+/// Value of the output ports `name0..` of the given width
+fn word(outputs: &Outputs, name: &str, width: usize) -> u64 {
+    value(
+        &(0..width)
+            .map(|bit| outputs[&format!("{}{}", name, bit)])
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Module names in the order they are declared
+fn modules(verilog: &str) -> Vec<&str> {
+    verilog
+        .lines()
+        .filter_map(|line| line.strip_prefix("module "))
+        .map(|line| &line[..line.find('(').unwrap()])
+        .collect()
+}
+
+#[test]
+fn test_half_adder_verilog() {
+    let expected = "\
+module half_adder(a, b, sum, carry);
+  input a;
+  input b;
+  output sum;
+  output carry;
+  wire \\xor ;
+  wire \\and ;
+
+  xor (\\xor , a, b);
+  and (\\and , a, b);
+  buf (sum, \\xor );
+  buf (carry, \\and );
+endmodule
+";
+    assert_eq!(to_verilog(&HalfAdder::netlist()), expected);
+}
+
+#[test]
+fn test_one_module_per_component() {
+    let verilog = to_verilog(&FullAdder::netlist());
+    assert_eq!(modules(&verilog), ["half_adder", "full_adder"]);
+    assert!(verilog.contains(
+        "  half_adder half_adder2(.a(\\half_adder1.sum ), .b(carry_in), \
+         .sum(\\half_adder2.sum ), .carry(\\half_adder2.carry ));"
+    ));
+    assert!(verilog.contains("  or (\\or , \\half_adder1.carry , \\half_adder2.carry );"));
+
+    // The multiplexer is a module like any component; the decoder's 5-input ANDs are primitives
+    let verilog = to_verilog(&CPU::netlist());
+    assert_eq!(
+        modules(&verilog),
+        [
+            "decoder4",
+            "mux2",
+            "half_adder",
+            "full_adder",
+            "shifter",
+            "multiplier",
+            "divider",
+            "digit_corrector",
+            "decimal_adjust",
+            "incrementer",
+            "alu",
+            "cpu_datapath"
+        ]
+    );
+    assert!(verilog.contains("  and (out5, enable, select0, not1, select2, not3);"));
+    assert!(
+        verilog
+            .contains("  mux2 select0(.select(subtract), .a(b0), .b(not0), .out(\\select0.out ));")
+    );
+    assert!(verilog.contains("  supply1 const1;"));
+    assert_eq!(verilog.matches("endmodule").count(), 12);
+}
+
+#[test]
+fn test_table_modules_and_name_clashes() {
+    let majority = GateType::Custom(TruthTable::majority(3));
+    let mut inner = NetlistBuilder::new("block");
+    let inputs: Vec<_> = ["x", "y", "z"]
+        .iter()
+        .map(|name| inner.input(name))
+        .collect();
+    let vote = inner.gate("vote", majority, &inputs);
+    inner.output("vote", vote);
+    let inner = inner.build().unwrap();
+
+    // A different circuit that happens to share the name
+    let mut other = NetlistBuilder::new("block");
+    let x = other.input("x");
+    let out = other.gate("out", GateType::NOT, &[x]);
+    other.output("out", out);
+    let other = other.build().unwrap();
+
+    let mut top = NetlistBuilder::new("top");
+    let inputs = top.inputs("in", 3);
+    let vote = top.instance("first", &inner, &inputs);
+    let again = top.instance("second", &inner, &inputs);
+    let inverted = top.instance("third", &other, &vote);
+    top.output("vote", again[0]);
+    top.output("inverted", inverted[0]);
+    let verilog = to_verilog(&top.build().unwrap());
+
+    assert_eq!(
+        modules(&verilog),
+        ["table3_00010111", "block", "block_2", "top"]
+    );
+    assert!(verilog.contains("  or (out, row3, row5, row6, row7);"));
+    assert!(verilog.contains("  block_2 third(.x(\\first.vote ), .out(\\third.out ));"));
+}
+
+#[test]
+fn test_dot_clusters_follow_hierarchy() {
+    let netlist = FullAdder::netlist();
+    let dot = to_dot(&netlist);
+    assert!(dot.starts_with("digraph \"full_adder\" {\n"));
+    assert!(
+        dot.contains(
+            "  subgraph \"cluster_half_adder1\" {\n    label=\"half_adder1 (half_adder)\";"
+        )
+    );
+    assert!(dot.contains("    \"half_adder2.and\" [label=\"and\\nAND\", shape=box];"));
+    assert!(dot.contains("  \"in:carry_in\" -> \"half_adder2.xor\";"));
+    assert!(dot.contains("  \"or\" -> \"out:carry\";"));
+    assert_eq!(dot.matches("shape=box").count(), netlist.gate_count());
+
+    // Every adder stage is a cluster holding the clusters of its half adders, then the shifter;
+    // each multiplexer (B or NOT B, and those inside the shifter) is a cluster too
+    let alu = ALU::with_units(
+        &mut ReferenceGates,
+        4,
+        AdderArchitecture::RippleCarry,
+        ALUUnits::NONE,
+    );
+    let netlist = alu.to_netlist();
+    let dot = to_dot(&netlist);
+    assert_eq!(dot.matches("subgraph").count(), 4 * 3 + 1 + 4 + (4 + 3));
+    assert!(dot.contains("      \"adder3.half_adder1.xor\" [label=\"xor\\nXOR\", shape=box];"));
+    assert!(dot.contains("    \"select2.or\" [label=\"or\\nOR\", shape=box];"));
+    assert_eq!(dot.matches("shape=box").count(), netlist.gate_count());
+
+    // Per bit: full adder, NOT, multiplexer and the three logic gates; the shifter's
+    // multiplexers; then the output ports
+    let edges: usize = 4 * (5 * 2 + 1 + 7 + 3 * 2) + (4 + 3) * 7;
+    assert_eq!(dot.matches(" -> ").count(), edges + netlist.outputs().len());
+}
+
+#[test]
+fn test_exported_alu_simulates_like_the_alu() {
+    // The Verilog read back runs on freshly trained gates, like a simulator would run it
+    let alu = ALU::new();
+    let circuit = Circuit::from_verilog(&to_verilog(&alu.to_netlist())).unwrap();
+    let run = |words: &[(&str, u64)]| simulate(&circuit, words);
+
+    for a in 0..16 {
+        for b in 0..16 {
+            // Arithmetic on the adder: the carry in and subtract lines come from the operation
+            for (operation, carry_in, subtract) in [
+                (ALUOperation::Add, 0, 0),
+                (ALUOperation::Subtract, 1, 1),
+                (ALUOperation::AddWithCarry(true), 1, 0),
+                (ALUOperation::SubtractWithBorrow(false), 0, 1),
+            ] {
+                let expected = alu.compute(a, b, operation.clone());
+                let outputs = run(&[
+                    ("a", a),
+                    ("b", b),
+                    ("carry_in", carry_in),
+                    ("subtract", subtract),
+                ]);
+                let context = format!("{:?} {} {}", operation, a, b);
+                assert_eq!(word(&outputs, "sum", 4), expected.result, "{}", context);
+                assert_eq!(outputs["carry"], expected.carry, "{}", context);
+            }
+
+            let outputs = run(&[("a", a), ("b", b)]);
+            for (name, operation) in [
+                ("and", ALUOperation::And),
+                ("or", ALUOperation::Or),
+                ("xor", ALUOperation::Xor),
+            ] {
+                assert_eq!(word(&outputs, name, 4), alu.compute(a, b, operation).result);
+            }
+            // The inverters are fed from B
+            assert_eq!(
+                word(&outputs, "not", 4),
+                alu.compute(b, 0, ALUOperation::Not).result
+            );
+            let product = word(&outputs, "product", 8);
+            let expected = alu.compute(a, b, ALUOperation::Multiply);
+            assert_eq!(product & 0xF, expected.result);
+            assert_eq!(product > 0xF, expected.carry);
+
+            // Division repeats the step on the subtractor once per quotient bit
+            let (mut quotient, mut remainder) = (0, 0);
+            for bit in (0..4).rev() {
+                let shifted_out = remainder >> 3;
+                remainder = (remainder << 1 | a >> bit & 1) & 0xF;
+                let step = run(&[
+                    ("a", remainder),
+                    ("b", b),
+                    ("carry_in", 1),
+                    ("subtract", 1),
+                    ("shifted_out", shifted_out),
+                ]);
+                quotient |= (step["quotient"] as u64) << bit;
+                assert_eq!(step["divide_by_zero"], b == 0);
+                remainder = word(&step, "remainder", 4);
+            }
+            assert_eq!(quotient, alu.compute(a, b, ALUOperation::Divide).result);
+            assert_eq!(remainder, alu.compute(a, b, ALUOperation::Modulo).result);
+        }
+
+        for carry in [false, true] {
+            // Shifter controls: left, rotating, through carry
+            for (operation, left, rotating, through_carry) in [
+                (ALUOperation::ShiftLeft, 1, 0, 0),
+                (ALUOperation::ShiftRight, 0, 0, 0),
+                (ALUOperation::RotateLeft, 1, 1, 0),
+                (ALUOperation::RotateRight, 0, 1, 0),
+                (ALUOperation::RotateLeftThroughCarry(carry), 1, 0, 1),
+                (ALUOperation::RotateRightThroughCarry(carry), 0, 0, 1),
+            ] {
+                let expected = alu.compute(a, 0, operation.clone());
+                let outputs = run(&[
+                    ("a", a),
+                    ("carry_in", carry as u64),
+                    ("shift_left", left),
+                    ("shift_rotating", rotating),
+                    ("shift_through_carry", through_carry),
+                ]);
+                assert_eq!(
+                    word(&outputs, "shift", 4),
+                    expected.result,
+                    "{:?}",
+                    operation
+                );
+                assert_eq!(outputs["shift_carry"], expected.carry, "{:?}", operation);
+            }
+
+            // Decimal adjust: the digit decision, then the correction on the adder
+            for subtract in [false, true] {
+                let decision = run(&[
+                    ("a", a),
+                    ("digit_flag", carry as u64),
+                    ("subtract", subtract as u64),
+                ]);
+                let correction = if decision["adjust0"] { 6 } else { 0 };
+                let adjusted = run(&[
+                    ("a", a),
+                    ("b", correction),
+                    ("carry_in", subtract as u64),
+                    ("subtract", subtract as u64),
+                ]);
+                let operation = ALUOperation::DecimalAdjust {
+                    carry,
+                    half_carry: false,
+                    subtract,
+                };
+                let expected = alu.compute(a, 0, operation);
+                assert_eq!(word(&adjusted, "sum", 4), expected.result);
+                assert_eq!(carry || (!subtract && adjusted["carry"]), expected.carry);
+            }
+        }
+
+        for (decrement, operation) in [(0, ALUOperation::Increment), (1, ALUOperation::Decrement)] {
+            let expected = alu.compute(a, 0, operation);
+            let outputs = run(&[("a", a), ("decrement", decrement)]);
+            assert_eq!(word(&outputs, "count", 4), expected.result);
+            assert_eq!(outputs["count_carry"], expected.carry);
+        }
+    }
+}
+
+#[test]
+fn test_exported_decoder_and_datapath_simulate_like_the_components() {
+    let decoder = Decoder::new(4);
+    let circuit = Circuit::from_verilog(&to_verilog(&decoder.to_netlist())).unwrap();
+    for select in 0..16 {
+        let lines = simulate(&circuit, &[("select", select), ("enable", 1)]);
+        assert_eq!(
+            bits(word(&lines, "out", 16), 16),
+            decoder.decode(select as usize)
+        );
+        let lines = simulate(&circuit, &[("select", select)]);
+        assert_eq!(word(&lines, "out", 16), 0);
+    }
+
+    // The datapath decodes the opcode into the ALU controls
+    let cpu = CPU::new();
+    let circuit = Circuit::from_verilog(&to_verilog(&cpu.to_netlist())).unwrap();
+    for (opcode, operation) in [
+        (0x3, ALUOperation::Add),
+        (0x4, ALUOperation::Subtract),
+        (0x9, ALUOperation::Compare),
+        (0xA, ALUOperation::AddWithCarry(true)),
+        (0xB, ALUOperation::SubtractWithBorrow(true)),
+    ] {
+        for a in 0..16 {
+            for b in 0..16 {
+                let outputs = simulate(
+                    &circuit,
+                    &[
+                        ("opcode", opcode),
+                        ("acc", a),
+                        ("operand", b),
+                        ("carry_flag", 1),
+                    ],
+                );
+                let expected = cpu.alu.compute(a, b, operation.clone());
+                assert_eq!(word(&outputs, "sum", 4), expected.result, "{:?}", operation);
+                assert_eq!(outputs["carry"], expected.carry, "{:?}", operation);
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use synapse::cpu::CPU;
+use synapse::faults::{Fault, FaultError, FaultInjection};
+use synapse::full_adder::FullAdder;
+use synapse::gate_factory::ReferenceGates;
+use synapse::gates::{GateType, LogicGate};
+
+mod common;
+use common::unit_gate_count;
+
+fn assert_sync<T: Sync>() {}
+
+fn run_addition(cpu: &mut CPU, a: u8, b: u8) -> u8 {
+    let program = vec![
+        0x5, a, // LDI a
+        0x3, 0xD, // ADD D
+        0x2, 0xE, // STORE E
+        0xF, // HALT
+    ];
+
+    cpu.reset();
+    cpu.memory.write(0xD, b);
+    cpu.load_program(&program);
+    cpu.run();
+    cpu.memory.read(0xE)
+}
+
+#[test]
+fn test_stuck_at_faults() {
+    let mut gate = LogicGate::new(GateType::AND);
+
+    gate.set_fault(Fault::StuckAt1).unwrap();
+    assert_eq!(gate.compute(vec![0.0, 0.0]), 1.0);
+    assert_eq!(gate.fault(), Some(&Fault::StuckAt1));
+
+    gate.set_fault(Fault::StuckAt0).unwrap();
+    assert_eq!(gate.compute(vec![1.0, 1.0]), 0.0);
+
+    // Clearing restores the trained behaviour
+    gate.clear_fault();
+    assert!(gate.compute(vec![1.0, 1.0]) > 0.5);
+    assert_eq!(gate.fault(), None);
+}
+
+#[test]
+fn test_noise_and_dropped_neuron_faults() {
+    let mut gate = LogicGate::new(GateType::XOR);
+    let healthy = gate.compute(vec![0.0, 1.0]);
+
+    // Weight noise is applied once, so repeated computes agree
+    gate.set_fault(Fault::WeightNoise {
+        std_dev: 0.5,
+        seed: 3,
+    })
+    .unwrap();
+    let noisy = gate.compute(vec![0.0, 1.0]);
+    assert_ne!(noisy, healthy);
+    assert_eq!(gate.compute(vec![0.0, 1.0]), noisy);
+
+    // Input noise changes on every compute
+    gate.set_fault(Fault::InputNoise {
+        std_dev: 0.3,
+        seed: 3,
+    })
+    .unwrap();
+    assert_ne!(gate.compute(vec![0.0, 1.0]), gate.compute(vec![0.0, 1.0]));
+
+    // Faulty gates, and the components built from them, can still be shared between threads
+    assert_sync::<LogicGate>();
+    assert_sync::<CPU>();
+    let shared = std::sync::Arc::new(gate.clone());
+    let worker = std::sync::Arc::clone(&shared);
+    let activation = std::thread::spawn(move || worker.compute(vec![0.0, 1.0]))
+        .join()
+        .unwrap();
+    assert!(activation.is_finite());
+
+    gate.set_fault(Fault::DropNeuron(2)).unwrap();
+    assert_ne!(gate.compute(vec![0.0, 1.0]), healthy);
+
+    // XOR has 4 hidden neurons
+    assert_eq!(
+        gate.set_fault(Fault::DropNeuron(4)),
+        Err(FaultError::InvalidNeuron {
+            neuron: 4,
+            hidden: 4
+        })
+    );
+}
+
+#[test]
+fn test_fault_paths() {
+    let mut full_adder = FullAdder::new();
+
+    assert_eq!(
+        full_adder.gate_paths(),
+        vec![
+            "half_adder1.xor",
+            "half_adder1.and",
+            "half_adder2.xor",
+            "half_adder2.and",
+            "or"
+        ]
+    );
+
+    // Carry OR stuck at 1: 0 + 0 + 0 now carries
+    full_adder.inject_fault("or", Fault::StuckAt1).unwrap();
+    assert!(full_adder.compute(false, false, false).carry);
+
+    full_adder.remove_fault("or").unwrap();
+    assert!(!full_adder.compute(false, false, false).carry);
+
+    assert_eq!(
+        full_adder.inject_fault("half_adder3.xor", Fault::StuckAt0),
+        Err(FaultError::UnknownPath("half_adder3.xor".to_string()))
+    );
+}
+
+#[test]
+fn test_cpu_fault_injection_at_runtime() {
+    let mut cpu = CPU::new();
+    // Four full adders, one NOT, AND, OR and XOR per bit, then the other units
+    let full_adder = FullAdder::with_factory(&mut ReferenceGates).gate_count();
+    assert_eq!(
+        cpu.gate_paths().len(),
+        4 * full_adder + 4 * 4 + unit_gate_count(4)
+    );
+
+    // Fault-free run: 5 + 2 = 7
+    assert_eq!(run_addition(&mut cpu, 5, 2), 7);
+    let healthy_flags = (cpu.registers.status.zero(), cpu.registers.status.carry());
+
+    // Sum bit 0 stuck at 0: 5 + 2 = 6
+    cpu.inject_fault("alu.adder0.half_adder2.xor", Fault::StuckAt0)
+        .unwrap();
+    assert_eq!(run_addition(&mut cpu, 5, 2), 6);
+
+    // Carry out of bit 3 stuck at 1 sets the carry flag
+    cpu.inject_fault("alu.adder3.or", Fault::StuckAt1).unwrap();
+    run_addition(&mut cpu, 5, 2);
+    assert!(cpu.registers.status.carry());
+
+    // Switching every fault off restores the fault-free run
+    cpu.clear_faults();
+    assert_eq!(run_addition(&mut cpu, 5, 2), 7);
+    assert_eq!(
+        (cpu.registers.status.zero(), cpu.registers.status.carry()),
+        healthy_flags
+    );
+}
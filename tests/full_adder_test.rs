@@ -95,3 +95,23 @@ fn test_full_adder_arithmetic() {
     }
 }
 
+#[test]
+fn test_full_adder_detailed() {
+    let full_adder = FullAdder::new();
+
+    for a in [false, true] {
+        for b in [false, true] {
+            for carry_in in [false, true] {
+                let detail = full_adder.compute_detailed(a, b, carry_in);
+
+                // Detailed path decodes to the same bits as compute
+                assert_eq!(detail.result(), full_adder.compute(a, b, carry_in));
+                assert_eq!(detail.sum, detail.half_adder2.sum);
+
+                // Margins are distances from the 0.5 threshold
+                assert_eq!(detail.carry.margin, (detail.carry.activation - 0.5).abs());
+                assert!(detail.min_margin() > 0.0 && detail.min_margin() <= 0.5);
+            }
+        }
+    }
+}
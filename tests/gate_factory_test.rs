@@ -0,0 +1,80 @@
+use synapse::alu::{ALU, ALUOperation};
+use synapse::full_adder::FullAdder;
+use synapse::gate_factory::{GateFactory, GateMode};
+use synapse::gates::GateType;
+use synapse::training::TrainingConfig;
+
+#[test]
+fn test_shared_factory_trains_each_gate_type_once() {
+    let mut factory = GateFactory::shared();
+    assert_eq!(factory.mode(), GateMode::Shared);
+
+    // One ALU needs only XOR, AND, OR and NOT gates, its multiplexers included
+    let alu = ALU::with_factory(&mut factory);
+    assert_eq!(factory.trained_count(), 4);
+
+    // Building more components reuses the canonical gates
+    let _cpu_alu = ALU::with_factory(&mut factory);
+    assert_eq!(factory.trained_count(), 4);
+
+    assert_eq!(alu.compute(5, 3, ALUOperation::Add).result, 8);
+    assert_eq!(alu.compute(10, 3, ALUOperation::Subtract).result, 7);
+}
+
+#[test]
+fn test_independent_factory_trains_every_gate() {
+    let mut factory = GateFactory::independent();
+    assert_eq!(factory.mode(), GateMode::Independent);
+
+    // A full adder has 2 XOR, 2 AND and 1 OR gate
+    let _adder = FullAdder::with_factory(&mut factory);
+    assert_eq!(factory.trained_count(), 5);
+}
+
+#[test]
+fn test_shared_and_independent_gates_agree() {
+    let shared = FullAdder::with_factory(&mut GateFactory::shared());
+    let independent = FullAdder::with_factory(&mut GateFactory::independent());
+
+    for a in [false, true] {
+        for b in [false, true] {
+            for carry_in in [false, true] {
+                assert_eq!(
+                    shared.compute(a, b, carry_in),
+                    independent.compute(a, b, carry_in)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_shared_gates_are_identical_clones() {
+    let mut factory = GateFactory::shared();
+    let first = factory.gate(GateType::XOR);
+    let second = factory.gate(GateType::XOR);
+
+    assert_eq!(
+        first.network().layers()[0].data,
+        second.network().layers()[0].data
+    );
+    assert_eq!(factory.trained_count(), 1);
+}
+
+#[test]
+fn test_try_gate_checks_convergence() {
+    let mut factory = GateFactory::shared();
+    let gate = factory.try_gate(GateType::XOR).unwrap();
+    assert!(gate.failed_rows().is_empty());
+    factory.try_gate(GateType::XOR).unwrap();
+    assert_eq!(factory.trained_count(), 1);
+
+    // Untrained gates are rejected instead of being handed out
+    let config = TrainingConfig::default().with_epochs(0);
+    let mut factory = GateFactory::with_config(GateMode::Shared, config);
+    let untrained = factory.gate(GateType::XOR);
+    assert!(!untrained.failed_rows().is_empty());
+    let error = factory.try_gate(GateType::XOR).unwrap_err();
+    assert_eq!(error.gate_type, GateType::XOR);
+    assert_eq!(factory.trained_count(), 2);
+}
@@ -1,4 +1,4 @@
-use synapse::gates::{LogicGate, GateType};
+use synapse::gates::{LogicGate, GateType, TruthTable, TruthTableError};
 
 #[test]
 fn test_and_gate() {
@@ -31,4 +31,87 @@ fn test_xor_gate() {
     assert!(gate.compute(vec![0.0, 1.0]) > 0.5); // 0 XOR 1 = 1
     assert!(gate.compute(vec![1.0, 0.0]) > 0.5); // 1 XOR 0 = 1
     assert!(gate.compute(vec![1.0, 1.0]) < 0.5); // 1 XOR 1 = 0
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_not_gate() {
+    let gate = LogicGate::new(GateType::NOT);
+
+    assert!(gate.compute(vec![0.0]) > 0.5); // NOT 0 = 1
+    assert!(gate.compute(vec![1.0]) < 0.5); // NOT 1 = 0
+}
+
+#[test]
+fn test_inverted_gates() {
+    // NAND, NOR and XNOR must match their truth tables exactly
+    for gate_type in [GateType::NAND, GateType::NOR, GateType::XNOR] {
+        let gate = LogicGate::new(gate_type.clone());
+
+        for (inputs, expected) in gate_type.truth_table() {
+            let signals = inputs.iter().map(|&bit| if bit { 1.0 } else { 0.0 }).collect();
+            assert_eq!(gate.compute(signals) > 0.5, expected, "{} {:?}", gate_type, inputs);
+        }
+    }
+}
+
+#[test]
+fn test_truth_table_gates() {
+    // Wide gates trained from truth tables reproduce every row
+    let tables = [
+        TruthTable::and(3),
+        TruthTable::or(3),
+        TruthTable::majority(3),
+        TruthTable::parity(3),
+        TruthTable::mux2(),
+    ];
+
+    for table in tables {
+        let gate = LogicGate::from_truth_table(table.clone());
+        assert_eq!(gate.gate_type().arity(), 3);
+        assert_eq!(gate.accuracy(), 1.0, "{}", gate.gate_type());
+
+        for (inputs, expected) in gate.gate_type().truth_table() {
+            let signals = inputs.iter().map(|&bit| if bit { 1.0 } else { 0.0 }).collect();
+            assert_eq!(gate.compute(signals) > 0.5, expected);
+            assert_eq!(table.output(&inputs), Ok(expected));
+        }
+    }
+}
+
+#[test]
+fn test_gate_from_fn() {
+    // Implication: a -> b
+    let gate = LogicGate::from_fn(2, |inputs| !inputs[0] || inputs[1]);
+
+    assert!(gate.compute(vec![0.0, 0.0]) > 0.5);
+    assert!(gate.compute(vec![0.0, 1.0]) > 0.5);
+    assert!(gate.compute(vec![1.0, 0.0]) < 0.5);
+    assert!(gate.compute(vec![1.0, 1.0]) > 0.5);
+}
+
+#[test]
+fn test_gate_type_names() {
+    let majority = GateType::Custom(TruthTable::majority(3));
+    assert_eq!(majority.to_string(), "TABLE3:00010111");
+    assert_eq!("TABLE3:00010111".parse::<GateType>(), Ok(majority));
+    assert_eq!("XNOR".parse::<GateType>(), Ok(GateType::XNOR));
+
+    assert!("TABLE3:0101".parse::<GateType>().is_err());
+    assert!("MAYBE".parse::<GateType>().is_err());
+}
+
+#[test]
+fn test_truth_table_errors() {
+    assert_eq!(TruthTable::new(0, vec![true]), Err(TruthTableError::NoInputs));
+    let error = TruthTable::new(2, vec![false, true]).unwrap_err();
+    assert_eq!(error, TruthTableError::WrongOutputCount { arity: 2, found: 2 });
+    assert_eq!(error.to_string(), "a 2-input truth table needs 4 outputs but got 2");
+
+    let table = TruthTable::new(2, vec![false, true, true, false]).unwrap();
+    assert_eq!(table, TruthTable::parity(2));
+    assert_eq!(table.output(&[true, false]), Ok(true));
+    assert_eq!(
+        table.output(&[true, false, true]),
+        Err(TruthTableError::WrongInputCount { arity: 2, found: 3 })
+    );
+}
@@ -0,0 +1,231 @@
+use synapse::alu::ALU;
+use synapse::circuit::{Circuit, ImportError};
+use synapse::cpu::CPU;
+use synapse::export::to_verilog;
+use synapse::faults::FaultInjection;
+use synapse::full_adder::FullAdder;
+use synapse::gate_factory::ReferenceGates;
+use synapse::import::{ParseError, parse_blif, parse_verilog};
+use synapse::netlist::CellKind;
+
+mod common;
+use common::bits;
+
+const FULL_ADDER_BLIF: &str = "\
+# Full adder from two-level covers
+.model full_adder
+.inputs a b carry_in
+.outputs sum carry
+.names a b half
+10 1
+01 1
+.names half carry_in sum
+10 1
+01 1
+# The carry is 0 unless at least two inputs are set (off-set cover)
+.names a b \\
+       carry_in carry
+00- 0
+0-0 0
+-00 0
+.end
+";
+
+#[test]
+fn test_blif_full_adder() {
+    let netlist = parse_blif(FULL_ADDER_BLIF).unwrap();
+    assert_eq!(netlist.name(), "full_adder");
+    assert_eq!(netlist.input_names(), ["a", "b", "carry_in"]);
+    assert_eq!(netlist.output_names(), ["sum", "carry"]);
+    assert_eq!(netlist.gate_paths(), ["half", "sum", "carry"]);
+    assert!(matches!(
+        &netlist.cells()[2].kind,
+        CellKind::Gate(synapse::gates::GateType::Custom(_))
+    ));
+
+    // Trained gates compute the same sums as the hand-wired full adder
+    let circuit = Circuit::from_blif(FULL_ADDER_BLIF).unwrap();
+    let full_adder = FullAdder::new();
+    for row in 0..8u64 {
+        let [a, b, carry_in] = [row & 4 != 0, row & 2 != 0, row & 1 != 0];
+        let expected = full_adder.compute(a, b, carry_in);
+        assert_eq!(
+            circuit.evaluate(&[a, b, carry_in]),
+            [expected.sum, expected.carry]
+        );
+    }
+}
+
+#[test]
+fn test_blif_constants_and_buffers() {
+    let blif = "\
+.model tied
+.inputs x
+.outputs one zero copy inverted
+.names one
+1
+.names zero
+.names x copy
+1 1
+.names copy inverted
+0 1
+.end
+";
+    let netlist = parse_blif(blif).unwrap();
+    // Only the inverter is a gate; the buffer is a plain connection
+    assert_eq!(netlist.gate_paths(), ["inverted"]);
+    assert_eq!(netlist.evaluate(&[false]), [true, false, false, true]);
+    assert_eq!(netlist.evaluate(&[true]), [true, false, true, false]);
+}
+
+#[test]
+fn test_verilog_full_adder() {
+    let verilog = "\
+// Half adders wired into a full adder
+module half(a, b, s, c);
+  input a, b;
+  output s, c;
+  xor x1 (s, a, b);
+  and (c, a, b);
+endmodule
+
+/* The top module is the one nothing instantiates */
+module adder(input x, input y, input cin, output sum, output \\carry.out );
+  wire s1, c1, c2;
+  half first (.a(x), .b(y), .s(s1), .c(c1));
+  half second (s1, cin, sum, c2);
+  or (\\carry.out , c1, c2);
+endmodule
+";
+    let netlist = parse_verilog(verilog).unwrap();
+    assert_eq!(netlist.name(), "adder");
+    assert_eq!(netlist.output_names(), ["sum", "carry.out"]);
+
+    let circuit = Circuit::from_verilog(verilog).unwrap();
+    assert_eq!(
+        circuit.gate_paths(),
+        ["first.x1", "first.c", "second.x1", "second.c", "carry.out"]
+    );
+    for row in 0..8u64 {
+        let inputs = bits(row, 3);
+        let total = inputs.iter().filter(|&&bit| bit).count();
+        assert_eq!(circuit.evaluate(&inputs), [total % 2 == 1, total >= 2]);
+    }
+}
+
+#[test]
+fn test_verilog_wide_xor() {
+    // Gates wider than MAX_GATE_INPUTS would not train, so they are rejected
+    let wide = "module parity(a, b, c, d, e, f, g, h, y);\n  input a, b, c, d, e, f, g, h;\n\
+                  output y;\n  xor (y, a, b, c, d, e, f, g, h);\nendmodule\n";
+    match Circuit::from_verilog(wide) {
+        Err(ImportError::Parse(error)) => assert_eq!(
+            error.to_string(),
+            "line 4: 'xor' takes 2 to 5 inputs but got 8"
+        ),
+        other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+    }
+
+    // The same 8-input XOR as a tree of narrower gates is right on every row
+    let tree = "module parity(a, b, c, d, e, f, g, h, y);\n  input a, b, c, d, e, f, g, h;\n\
+                  output y;\n  wire low, high;\n  xor (low, a, b, c, d);\n\
+                  xor (high, e, f, g, h);\n  xor (y, low, high);\nendmodule\n";
+    let circuit = Circuit::from_verilog(tree).unwrap();
+    for row in 0..256u64 {
+        let inputs = bits(row, 8);
+        assert_eq!(circuit.evaluate(&inputs), [row.count_ones() % 2 == 1]);
+    }
+}
+
+#[test]
+fn test_exported_verilog_round_trips() {
+    // Hierarchy, truth table modules, constants and escaped names all read back
+    for netlist in [ALU::netlist(3), CPU::netlist()] {
+        let imported = parse_verilog(&to_verilog(&netlist)).unwrap();
+        assert_eq!(imported.name(), netlist.name());
+        assert_eq!(imported.input_names(), netlist.input_names());
+        assert_eq!(imported.output_names(), netlist.output_names());
+
+        let width = netlist.inputs().len();
+        for value in 0..1u64 << width {
+            let inputs = bits(value, width);
+            assert_eq!(imported.evaluate(&inputs), netlist.evaluate(&inputs));
+        }
+    }
+
+    let imported = parse_verilog(&to_verilog(&FullAdder::netlist())).unwrap();
+    let circuit = Circuit::with_factory(&mut ReferenceGates, imported);
+    assert_eq!(circuit.evaluate(&[true, true, true]), [true, true]);
+}
+
+#[test]
+fn test_parse_errors_report_lines() {
+    let error = |result: Result<_, ParseError>| result.unwrap_err();
+
+    let blif = ".model m\n.inputs a b\n.outputs y\n.names a b y\n1x1 1\n";
+    let found = error(parse_blif(blif));
+    assert_eq!(found.line, 5);
+    assert_eq!(
+        found.to_string(),
+        "line 5: expected 2 input values but found '1x1'"
+    );
+
+    let blif = ".model m\n.inputs a b\n.outputs y\n.names a b y\n11 1\n00 0\n";
+    assert_eq!(
+        error(parse_blif(blif)).to_string(),
+        "line 6: cover mixes rows for outputs 0 and 1"
+    );
+
+    let blif = ".model m\n.inputs a\n.outputs y\n\n.names a missing y\n11 1\n";
+    assert_eq!(
+        error(parse_blif(blif)).to_string(),
+        "line 5: wire 'missing' is never driven"
+    );
+
+    let blif = ".model m\n.inputs a\n.outputs a\n.names a\n1\n";
+    assert_eq!(
+        error(parse_blif(blif)).to_string(),
+        "line 4: wire 'a' has more than one driver"
+    );
+    assert_eq!(
+        error(parse_blif(".model m\n.latch a b\n")).to_string(),
+        "line 2: .latch is not supported, only combinational circuits are"
+    );
+    assert_eq!(error(parse_blif(".model m\n.end\n.model n\n")).line, 3);
+
+    let verilog = "module m(a, y);\n  input a;\n  output y;\n  and (y, a);\nendmodule\n";
+    assert_eq!(
+        error(parse_verilog(verilog)).to_string(),
+        "line 4: 'and' takes 2 to 5 inputs but got 1"
+    );
+
+    let verilog = "module m(a, y);\n  input a;\n  output y;\n  wire w;\n\
+                   \n  not (w, y);\n  not (y, w);\nendmodule\n";
+    let found = error(parse_verilog(verilog));
+    assert!(found.message.contains("combinational loop"), "{}", found);
+    assert!([6, 7].contains(&found.line));
+
+    let verilog = "module m(a, y);\n  input [3:0] a;\nendmodule\n";
+    assert_eq!(
+        error(parse_verilog(verilog)).to_string(),
+        "line 2: vectors are not supported"
+    );
+
+    let verilog = "module m(a, y);\n  input a;\n  output y;\n  assign y = a;\nendmodule\n";
+    assert_eq!(
+        error(parse_verilog(verilog)).to_string(),
+        "line 4: unsupported statement 'assign'"
+    );
+
+    let verilog = "module m(a, y);\n  input a;\n  output y;\n  adder u (a, y);\nendmodule\n";
+    assert_eq!(
+        error(parse_verilog(verilog)).to_string(),
+        "line 4: unknown module 'adder'"
+    );
+
+    let verilog = "module m(a, y);\n  input a;\n  output y;\n  not (y, a)\nendmodule\n";
+    assert_eq!(
+        error(parse_verilog(verilog)).to_string(),
+        "line 5: expected ';' but found 'endmodule'"
+    );
+}
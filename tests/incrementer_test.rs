@@ -0,0 +1,133 @@
+use synapse::alu::{ALU, ALUOperation};
+use synapse::cpu::{CPU, ProgramCounterMode};
+use synapse::faults::{Fault, FaultInjection};
+use synapse::gate_factory::ReferenceGates;
+use synapse::incrementer::Incrementer;
+use synapse::persistence::Persist;
+
+mod common;
+use common::bits;
+
+#[test]
+fn test_increment_and_decrement_all_nibbles() {
+    let alu = ALU::new();
+
+    for a in 0..16u64 {
+        let up = alu.compute_detailed(a, 0, ALUOperation::Increment);
+        assert_eq!(up.result.result, (a + 1) & 0x0F, "{} + 1", a);
+        assert_eq!(up.result.carry, a == 0xF);
+        assert_eq!(up.result.overflow, a == 0x7); // +7 to -8
+        assert!(up.incrementer.is_some());
+        assert!(up.min_margin() > 0.0);
+
+        let down = alu.compute(a, 0, ALUOperation::Decrement);
+        assert_eq!(down.result, a.wrapping_sub(1) & 0x0F, "{} - 1", a);
+        assert_eq!(down.carry, a == 0);
+        assert_eq!(down.overflow, a == 0x8); // -8 to +7
+        assert_eq!(down.zero, a == 1);
+    }
+}
+
+#[test]
+fn test_reference_incrementer_widths() {
+    for width in [1, 3, 6] {
+        let incrementer = Incrementer::with_factory(&mut ReferenceGates, width);
+        let mask = (1u64 << width) - 1;
+        for value in 0..=mask {
+            assert_eq!(
+                incrementer.compute(&bits(value, width), false),
+                bits((value + 1) & mask, width)
+            );
+            assert_eq!(
+                incrementer.compute(&bits(value, width), true),
+                bits(value.wrapping_sub(1) & mask, width)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_incrementer_save_load_and_faults() {
+    let mut incrementer = Incrementer::new(2);
+    let mut bytes = Vec::new();
+    incrementer.write_to(&mut bytes).unwrap();
+    let loaded = Incrementer::read_from(bytes.as_slice()).unwrap();
+    assert_eq!(
+        loaded.compute_detailed(&[true, false], true),
+        incrementer.compute_detailed(&[true, false], true)
+    );
+
+    // Two input XORs, two half adders and two output XORs
+    assert_eq!(
+        incrementer.gate_paths(),
+        vec![
+            "in0",
+            "in1",
+            "half_adder0.xor",
+            "half_adder0.and",
+            "half_adder1.xor",
+            "half_adder1.and",
+            "out0",
+            "out1"
+        ]
+    );
+
+    // The carry out of bit 0 stuck at 0: 1 + 1 loses its carry
+    incrementer
+        .inject_fault("half_adder0.and", Fault::StuckAt0)
+        .unwrap();
+    assert_eq!(
+        incrementer.compute(&[true, false], false),
+        vec![false, false]
+    );
+}
+
+#[test]
+fn test_cpu_neural_program_counter() {
+    let program = [
+        0x5, 0x7, // LDI 7
+        0xE, 0x8, // INC
+        0xE, 0x8, // INC
+        0xE, 0x9, // DEC
+        0x2, 0xF, // STORE F
+        0xF, // HALT
+    ];
+
+    let mut cpu = CPU::new();
+    cpu.pc_mode = ProgramCounterMode::Neural;
+    cpu.load_program(&program);
+    cpu.run();
+
+    assert_eq!(cpu.memory.read(0xF), 8);
+    assert_eq!(cpu.registers.program_counter.read(), 11);
+    assert!(cpu.weakest_margins["PC"] > 0.0);
+    assert!(cpu.weakest_margins.contains_key("INC"));
+    assert!(cpu.weakest_margins.contains_key("DEC"));
+
+    // The default mode never runs the program counter through the ALU
+    let mut cpu = CPU::new();
+    assert_eq!(cpu.pc_mode, ProgramCounterMode::Register);
+    cpu.load_program(&program);
+    cpu.run();
+    assert_eq!(cpu.memory.read(0xF), 8);
+    assert!(!cpu.weakest_margins.contains_key("PC"));
+}
+
+#[test]
+fn test_cpu_inc_dec_keep_carry() {
+    let mut cpu = CPU::new();
+
+    cpu.memory.write(0xF, 0xF);
+    cpu.load_program(&[
+        0x5, 0x1, // LDI 1
+        0x3, 0xF, // ADD F: 1 + 15 sets carry
+        0xE, 0x9, // DEC: 0 wraps to 15
+        0xF, // HALT
+    ]);
+    cpu.run();
+
+    assert_eq!(cpu.registers.accumulator.read(), 0xF);
+    assert!(cpu.registers.status.carry());
+    assert!(cpu.registers.status.negative());
+    assert!(!cpu.registers.status.zero());
+}
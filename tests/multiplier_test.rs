@@ -0,0 +1,65 @@
+use synapse::faults::{Fault, FaultInjection};
+use synapse::gate_factory::ReferenceGates;
+use synapse::multiplier::ArrayMultiplier;
+use synapse::persistence::Persist;
+
+mod common;
+use common::{bits, value};
+
+#[test]
+fn test_multiplier_all_4_bit_products() {
+    let multiplier = ArrayMultiplier::new(4);
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let detail = multiplier.compute_detailed(&bits(a, 4), &bits(b, 4));
+            assert_eq!(detail.product.len(), 8);
+            assert_eq!(value(&detail.product), a * b, "{} * {}", a, b);
+            assert_eq!(value(detail.low_bits()), (a * b) & 0x0F);
+            assert_eq!(value(detail.high_bits()), (a * b) >> 4);
+            assert!(detail.min_margin() > 0.0);
+        }
+    }
+}
+
+#[test]
+fn test_reference_multiplier_widths() {
+    for width in [1, 2, 3, 6] {
+        let multiplier = ArrayMultiplier::with_factory(&mut ReferenceGates, width);
+        for a in 0..1 << width {
+            for b in 0..1 << width {
+                let product = multiplier.compute(&bits(a, width), &bits(b, width));
+                assert_eq!(product.len(), 2 * width);
+                assert_eq!(value(&product), a * b, "{} * {} ({} bits)", a, b, width);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_multiplier_save_load_and_faults() {
+    let mut multiplier = ArrayMultiplier::new(2);
+    let mut bytes = Vec::new();
+    multiplier.write_to(&mut bytes).unwrap();
+    let loaded = ArrayMultiplier::read_from(bytes.as_slice()).unwrap();
+    assert_eq!(
+        multiplier.compute_detailed(&[true, true], &[true, false]),
+        loaded.compute_detailed(&[true, true], &[true, false])
+    );
+
+    // 4 partial products and one row of 2 full adders
+    assert_eq!(multiplier.gate_paths().len(), 4 + 2 * 5);
+    assert!(multiplier.gate_paths().contains(&"adder1_0.or".to_string()));
+
+    // Partial product A(0) AND B(0) is product bit 0
+    multiplier.inject_fault("and0_0", Fault::StuckAt0).unwrap();
+    assert_eq!(
+        multiplier.compute(&[true, false], &[true, false]),
+        vec![false; 4]
+    );
+    assert!(
+        multiplier
+            .inject_fault("adder0_0.or", Fault::StuckAt0)
+            .is_err()
+    );
+}
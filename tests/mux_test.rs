@@ -0,0 +1,174 @@
+use synapse::alu::{ALU, ALUOperation};
+use synapse::decoder::{Decoder, Demux};
+use synapse::faults::{Fault, FaultInjection};
+use synapse::gate_factory::GateFactory;
+use synapse::memory::Memory;
+use synapse::mux::{Mux2, Mux4};
+use synapse::persistence::Persist;
+
+mod common;
+use common::bits;
+
+#[test]
+fn test_mux2_all_cases() {
+    let mux = Mux2::new();
+
+    for value in 0..8 {
+        let (select, a, b) = (value & 1 != 0, value & 2 != 0, value & 4 != 0);
+        let expected = if select { b } else { a };
+        assert_eq!(
+            mux.compute(select, a, b),
+            expected,
+            "{} {} {}",
+            select,
+            a,
+            b
+        );
+        assert!(mux.compute_detailed(select, a, b).min_margin() > 0.0);
+    }
+}
+
+#[test]
+fn test_mux4_all_cases() {
+    let mux = Mux4::new();
+
+    for select in 0..4 {
+        for inputs in 0..16 {
+            let input_bits: [bool; 4] = bits(inputs, 4).try_into().unwrap();
+            let select_bits = [select & 1 != 0, select & 2 != 0];
+            assert_eq!(
+                mux.compute(select_bits, input_bits),
+                input_bits[select],
+                "select {} inputs {:04b}",
+                select,
+                inputs
+            );
+        }
+    }
+}
+
+#[test]
+fn test_decoder_is_one_hot() {
+    let decoder = Decoder::new(3);
+    assert_eq!(decoder.output_count(), 8);
+
+    for select in 0..8 {
+        let detail = decoder.compute_detailed(&bits(select, 3), true);
+        assert_eq!(detail.selected(), Some(select as usize));
+        assert!(detail.min_margin() > 0.0);
+
+        // Disabled decoders drive no output
+        assert!(
+            decoder
+                .compute(&bits(select, 3), false)
+                .iter()
+                .all(|&line| !line)
+        );
+    }
+}
+
+#[test]
+fn test_demux_routes_data() {
+    let demux = Demux::new(2);
+
+    for select in 0..4 {
+        for data in [false, true] {
+            let outputs = demux.compute(&bits(select, 2), data);
+            for (line, &output) in outputs.iter().enumerate() {
+                assert_eq!(output, data && line as u64 == select);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_mux_selects_alu_operand() {
+    // Four muxes can choose operand B bit by bit; the ALU itself takes B as given
+    let mut factory = GateFactory::shared();
+    let muxes: Vec<Mux2> = (0..4).map(|_| Mux2::with_factory(&mut factory)).collect();
+    let alu = ALU::with_factory(&mut factory);
+
+    let (memory_operand, immediate) = (0b0110u64, 0b0011u64);
+    for use_immediate in [false, true] {
+        let operand = muxes.iter().enumerate().fold(0, |acc, (bit, mux)| {
+            let a = memory_operand >> bit & 1 == 1;
+            let b = immediate >> bit & 1 == 1;
+            acc | (mux.compute(use_immediate, a, b) as u64) << bit
+        });
+        let expected = if use_immediate {
+            immediate
+        } else {
+            memory_operand
+        };
+        assert_eq!(operand, expected);
+        assert_eq!(
+            alu.compute(5, operand, ALUOperation::Add).result,
+            (5 + expected) & 0xF
+        );
+    }
+}
+
+#[test]
+fn test_selectors_save_and_load() {
+    let mux = Mux4::new();
+    let mut bytes = Vec::new();
+    mux.write_to(&mut bytes).unwrap();
+    let loaded = Mux4::read_from(bytes.as_slice()).unwrap();
+    for select in 0..4 {
+        let select_bits = [select & 1 != 0, select & 2 != 0];
+        let inputs = [true, false, true, false];
+        assert_eq!(
+            mux.compute_detailed(select_bits, inputs),
+            loaded.compute_detailed(select_bits, inputs)
+        );
+    }
+
+    let demux = Demux::new(2);
+    let mut bytes = Vec::new();
+    demux.write_to(&mut bytes).unwrap();
+    let loaded = Demux::read_from(bytes.as_slice()).unwrap();
+    assert_eq!(loaded.select_bits(), 2);
+    assert_eq!(
+        demux.compute_detailed(&[true, false], true),
+        loaded.compute_detailed(&[true, false], true)
+    );
+}
+
+#[test]
+fn test_selector_fault_paths() {
+    let mut mux = Mux4::new();
+    assert_eq!(mux.gate_paths().len(), 12);
+    assert!(mux.gate_paths().contains(&"high.and_b".to_string()));
+
+    // Input 3 is routed through the high multiplexer's B side
+    mux.inject_fault("high.and_b", Fault::StuckAt0).unwrap();
+    assert!(!mux.compute([true, true], [false, false, false, true]));
+    assert!(mux.compute([false, false], [true, false, false, false]));
+
+    let mut decoder = Decoder::new(2);
+    assert_eq!(decoder.gate_paths().len(), 6);
+    decoder.inject_fault("out2", Fault::StuckAt1).unwrap();
+    assert_eq!(decoder.decode(0), vec![true, false, true, false]);
+    assert!(decoder.inject_fault("out4", Fault::StuckAt1).is_err());
+}
+
+#[test]
+fn test_decoder_addresses_memory() {
+    let mut memory = Memory::new();
+    assert_eq!(memory.gate_paths().len(), 2 * (4 + 16));
+    memory.write(3, 9);
+    memory.write(5, 6);
+
+    // A dead read line hides its cell; a stuck write line copies every write into its cell
+    memory
+        .inject_fault("read_select.out3", Fault::StuckAt0)
+        .unwrap();
+    assert_eq!(memory.read(3), 0);
+    assert_eq!(memory.read(5), 6);
+    memory
+        .inject_fault("write_select.decoder.out5", Fault::StuckAt1)
+        .unwrap();
+    memory.write(2, 4);
+    assert_eq!(memory.read(2), 4);
+    assert_eq!(memory.read(5), 4);
+}
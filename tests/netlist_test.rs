@@ -0,0 +1,274 @@
+use synapse::alu::{ALU, ALUOperation, ALUUnits, AdderArchitecture};
+use synapse::circuit::Circuit;
+use synapse::cpu::CPU;
+use synapse::decoder::Decoder;
+use synapse::faults::{Fault, FaultInjection};
+use synapse::full_adder::FullAdder;
+use synapse::gate_factory::{GateFactory, ReferenceGates};
+use synapse::gates::{GateType, TruthTable};
+use synapse::half_adder::HalfAdder;
+use synapse::netlist::{CellKind, NetlistBuilder, NetlistError};
+
+mod common;
+use common::{bits, value};
+
+#[test]
+fn test_adder_netlists_match_components() {
+    let half_adder = HalfAdder::netlist();
+    assert_eq!(half_adder.input_names(), ["a", "b"]);
+    assert_eq!(half_adder.output_names(), ["sum", "carry"]);
+    assert_eq!(half_adder.gate_paths(), ["xor", "and"]);
+
+    let netlist = FullAdder::netlist();
+    assert_eq!(netlist.gate_count(), 5);
+    assert!(matches!(netlist.cells()[0].kind, CellKind::Instance(_)));
+
+    // The circuit runs on neural gates and names them like the hand-wired full adder
+    let circuit = Circuit::new(netlist);
+    let full_adder = FullAdder::new();
+    assert_eq!(circuit.gate_paths(), full_adder.gate_paths());
+    for row in 0..8u64 {
+        let [a, b, carry_in] = [row & 4 != 0, row & 2 != 0, row & 1 != 0];
+        let expected = full_adder.compute(a, b, carry_in);
+        let detail = circuit.evaluate_detailed(&[a, b, carry_in]);
+        assert_eq!(detail.outputs, [expected.sum, expected.carry]);
+        assert_eq!(
+            circuit.netlist().evaluate(&[a, b, carry_in]),
+            detail.outputs
+        );
+        assert!(detail.min_margin() > 0.0);
+    }
+}
+
+#[test]
+fn test_alu_netlist_adds_and_subtracts() {
+    let circuit = Circuit::new(ALU::netlist(4));
+    let alu = ALU::new();
+    let port_count = circuit.netlist().inputs().len();
+    assert_eq!(circuit.gate_count(), alu.gate_count() + 4 * 4); // One B multiplexer per bit
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let mut inputs = bits(a, 4);
+            inputs.extend(bits(b, 4));
+
+            for (subtract, operation) in
+                [(false, ALUOperation::Add), (true, ALUOperation::Subtract)]
+            {
+                let expected = alu.compute(a, b, operation);
+                let mut ports = inputs.clone();
+                ports.extend([subtract, subtract]); // Carry in is the +1 of two's complement
+                ports.resize(port_count, false); // Shifter, divider, decimal and counter controls
+                let outputs = circuit.evaluate(&ports);
+                assert_eq!(
+                    value(&outputs[..4]),
+                    expected.result,
+                    "{} {} {}",
+                    a,
+                    b,
+                    subtract
+                );
+                assert_eq!(outputs[4], expected.carry);
+                assert_eq!(value(&outputs[5..9]), a & b);
+                assert_eq!(value(&outputs[9..13]), a | b);
+                assert_eq!(value(&outputs[13..17]), a ^ b);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_netlists_are_generated_from_components() {
+    // Every gate of the ALU is a cell under its fault path; the B multiplexers are the only glue
+    for architecture in [
+        AdderArchitecture::RippleCarry,
+        AdderArchitecture::CarryLookahead,
+    ] {
+        for units in [ALUUnits::ALL, ALUUnits::NONE] {
+            let alu = ALU::with_units(&mut GateFactory::shared(), 8, architecture, units);
+            let netlist = alu.to_netlist();
+            assert_eq!(netlist.gate_count(), alu.gate_count() + 8 * 4);
+
+            let mut paths = netlist.gate_paths();
+            paths.retain(|path| !path.starts_with("select"));
+            paths.sort();
+            let mut expected = alu.gate_paths();
+            expected.sort();
+            assert_eq!(paths, expected, "{:?} {:?}", architecture, units);
+        }
+    }
+
+    let decoder = Decoder::new(3);
+    assert_eq!(decoder.to_netlist().gate_paths(), decoder.gate_paths());
+    assert_eq!(decoder.to_netlist(), Decoder::netlist(3));
+}
+
+#[test]
+fn test_builder_composes_new_circuits() {
+    // A 2-bit ripple-carry adder from full adder instances, with the carry in tied to 0
+    let full_adder = FullAdder::netlist();
+    let mut builder = NetlistBuilder::new("adder2");
+    let a = builder.inputs("a", 2);
+    let b = builder.inputs("b", 2);
+    let zero = builder.constant(false);
+    let low = builder.instance("low", &full_adder, &[a[0], b[0], zero]);
+    let high = builder.instance("high", &full_adder, &[a[1], b[1], low[1]]);
+    builder.outputs("sum", &[low[0], high[0]]);
+    builder.output("carry", high[1]);
+    let netlist = builder.build().unwrap();
+    assert_eq!(netlist.gate_paths()[0], "low.half_adder1.xor");
+
+    let circuit = Circuit::with_factory(&mut ReferenceGates, netlist);
+    for a in 0..4 {
+        for b in 0..4 {
+            let mut inputs = bits(a, 2);
+            inputs.extend(bits(b, 2));
+            assert_eq!(value(&circuit.evaluate(&inputs)), a + b);
+        }
+    }
+
+    // Wires may be used before they are driven; cells are put in evaluation order
+    let mut builder = NetlistBuilder::new("majority");
+    let inputs: Vec<_> = ["x", "y", "z"]
+        .iter()
+        .map(|name| builder.input(name))
+        .collect();
+    let pair = builder.wire("pair");
+    let out = builder.gate("out", GateType::OR, &[pair, inputs[2]]);
+    builder.gate_into(
+        "pair",
+        GateType::Custom(TruthTable::and(2)),
+        &inputs[..2],
+        pair,
+    );
+    builder.output("out", out);
+    let netlist = builder.build().unwrap();
+    assert_eq!(netlist.gate_paths(), ["pair", "out"]);
+    assert_eq!(netlist.evaluate(&[true, true, false]), [true]);
+    assert_eq!(netlist.evaluate(&[true, false, false]), [false]);
+}
+
+#[test]
+fn test_circuit_fault_injection() {
+    let mut circuit = Circuit::new(FullAdder::netlist());
+    circuit
+        .inject_fault("half_adder2.xor", Fault::StuckAt0)
+        .unwrap();
+    assert_eq!(circuit.evaluate(&[true, false, false]), [false, false]);
+    assert!(
+        circuit
+            .inject_fault("half_adder3.xor", Fault::StuckAt0)
+            .is_err()
+    );
+
+    circuit.clear_faults();
+    assert_eq!(circuit.evaluate(&[true, false, false]), [true, false]);
+}
+
+#[test]
+fn test_netlist_errors() {
+    // A cell with no outputs fed by the loop is stuck too, but the loop is reported
+    let mut sink = NetlistBuilder::new("sink");
+    sink.input("a");
+    let sink = sink.build().unwrap();
+    let mut builder = NetlistBuilder::new("loop");
+    let a = builder.input("a");
+    let out = builder.wire("out");
+    builder.instance("sink", &sink, &[out]);
+    let feedback = builder.wire("feedback");
+    builder.gate_into("out", GateType::AND, &[a, feedback], out);
+    builder.gate_into("feedback", GateType::NOT, &[out], feedback);
+    builder.output("out", out);
+    let error = builder.build().unwrap_err();
+    assert_eq!(error, NetlistError::CombinationalLoop("out".to_string()));
+    assert_eq!(
+        error.to_string(),
+        "cell 'out' is part of a combinational loop"
+    );
+
+    let mut builder = NetlistBuilder::new("undriven");
+    let a = builder.input("a");
+    let missing = builder.wire("missing");
+    builder.gate("out", GateType::OR, &[a, missing]);
+    assert_eq!(
+        builder.build(),
+        Err(NetlistError::Undriven("missing".to_string()))
+    );
+
+    let mut builder = NetlistBuilder::new("clash");
+    let a = builder.input("a");
+    builder.gate("a", GateType::NOT, &[a]);
+    assert_eq!(
+        builder.build(),
+        Err(NetlistError::MultipleDrivers("a".to_string()))
+    );
+
+    let mut builder = NetlistBuilder::new("arity");
+    let a = builder.input("a");
+    builder.gate("out", GateType::AND, &[a]);
+    let error = builder.build().unwrap_err();
+    assert_eq!(
+        error,
+        NetlistError::WrongInputCount {
+            cell: "out".to_string(),
+            expected: 2,
+            found: 1
+        }
+    );
+    assert_eq!(error.to_string(), "cell 'out' takes 2 inputs but got 1");
+
+    let mut builder = NetlistBuilder::new("ports");
+    let a = builder.input("a");
+    let carry = builder.wire("carry");
+    builder.instance_into("half", &HalfAdder::netlist(), &[a, a], &[carry]);
+    assert_eq!(
+        builder.build(),
+        Err(NetlistError::WrongOutputCount {
+            cell: "half".to_string(),
+            expected: 2,
+            found: 1
+        })
+    );
+}
+
+#[test]
+fn test_cpu_datapath_netlist() {
+    let netlist = CPU::netlist();
+    let alu = ALU::with_factory(&mut ReferenceGates);
+    assert_eq!(netlist.gate_count(), 20 + 5 + alu.gate_count() + 4 * 4);
+
+    for (opcode, carry_flag, operation) in [
+        (0x3, true, ALUOperation::Add),
+        (0x4, false, ALUOperation::Subtract),
+        (0x9, false, ALUOperation::Compare),
+        (0xA, true, ALUOperation::AddWithCarry(true)),
+        (0xB, false, ALUOperation::SubtractWithBorrow(false)),
+    ] {
+        let alu = ALU::with_factory(&mut ReferenceGates);
+        for (a, b) in [(0, 0), (7, 9), (15, 1), (3, 12)] {
+            let mut inputs = bits(opcode, 4);
+            inputs.extend(bits(a, 4));
+            inputs.extend(bits(b, 4));
+            inputs.push(carry_flag);
+            let outputs = netlist.evaluate(&inputs);
+            let expected = alu.compute(a, b, operation.clone());
+            assert_eq!(
+                value(&outputs[..4]),
+                expected.result,
+                "{:?} {} {}",
+                operation,
+                a,
+                b
+            );
+            assert_eq!(outputs[4], expected.carry);
+        }
+    }
+
+    // The decoder netlist lights one line per opcode, like the decoder
+    let decoder = Decoder::netlist(3);
+    for select in 0..8 {
+        let mut inputs = bits(select, 3);
+        inputs.push(true);
+        assert_eq!(decoder.evaluate(&inputs), bits(1 << select, 8));
+    }
+}
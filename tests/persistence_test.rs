@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use synapse::alu::ALUOperation;
+use synapse::cpu::CPU;
+use synapse::gates::{GateType, LogicGate, TruthTable};
+use synapse::half_adder::HalfAdder;
+use synapse::persistence::{FORMAT_VERSION, Persist};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("synapse-{}-{}.weights", name, std::process::id()))
+}
+
+#[test]
+fn test_gate_save_and_load() {
+    let gate = LogicGate::new(GateType::XOR);
+    let path = temp_path("xor");
+
+    gate.save(&path).unwrap();
+    let loaded = LogicGate::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Loaded weights must give bit-for-bit identical activations
+    for inputs in [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]] {
+        assert_eq!(
+            gate.compute(inputs.to_vec()),
+            loaded.compute(inputs.to_vec())
+        );
+    }
+}
+
+#[test]
+fn test_truth_table_gate_save_and_load() {
+    let gate = LogicGate::from_truth_table(TruthTable::majority(3));
+    let mut bytes = Vec::new();
+    gate.write_to(&mut bytes).unwrap();
+    let loaded = LogicGate::read_from(bytes.as_slice()).unwrap();
+
+    assert_eq!(loaded.gate_type(), gate.gate_type());
+    for (inputs, _) in gate.gate_type().truth_table() {
+        let signals: Vec<f64> = inputs.iter().map(|&bit| if bit { 1.0 } else { 0.0 }).collect();
+        assert_eq!(gate.compute(signals.clone()), loaded.compute(signals));
+    }
+}
+
+#[test]
+fn test_cpu_save_and_load() {
+    let cpu = CPU::new();
+    let path = temp_path("cpu");
+
+    cpu.save(&path).unwrap();
+    let mut loaded = CPU::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Loaded ALU matches the original for every operand pair
+    for a in 0..16 {
+        for b in 0..16 {
+            for op in [ALUOperation::Add, ALUOperation::Subtract] {
+                assert_eq!(
+                    cpu.alu.compute(a, b, op.clone()),
+                    loaded.alu.compute(a, b, op)
+                );
+            }
+        }
+    }
+
+    // And the loaded CPU runs programs
+    let program = vec![
+        0x5, 0x5, // LDI 5
+        0x3, 0xD, // ADD D
+        0x2, 0xE, // STORE E
+        0xF, // HALT
+    ];
+    loaded.memory.write(0xD, 3);
+    loaded.load_program(&program);
+    loaded.run();
+    assert_eq!(loaded.memory.read(0xE), 8);
+}
+
+#[test]
+fn test_load_rejects_invalid_files() {
+    let gate = LogicGate::new(GateType::AND);
+    let mut bytes = Vec::new();
+    gate.write_to(&mut bytes).unwrap();
+    let text = String::from_utf8(bytes).unwrap();
+
+    // Unsupported version
+    let header = format!("synapse-weights {}", FORMAT_VERSION);
+    let future = text.replacen(&header, "synapse-weights 99", 1);
+    let error = LogicGate::read_from(future.as_bytes()).unwrap_err();
+    assert!(error.to_string().contains("unsupported format version 99"));
+
+    // Wrong component type
+    let error = CPU::read_from(text.as_bytes()).unwrap_err();
+    assert!(error.to_string().contains("line 2"));
+
+    // Weights of a 2-input gate loaded as a 4-input one
+    let wider = text.replacen("gate AND", "gate TABLE4:0000000000000001", 1);
+    let error = LogicGate::read_from(wider.as_bytes()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("but the gate has 4"), "{}", error);
+
+    // Truncated file
+    let truncated: String = text.lines().take(4).collect::<Vec<_>>().join("\n");
+    assert!(LogicGate::read_from(truncated.as_bytes()).is_err());
+}
+
+#[test]
+fn test_load_rejects_gates_of_the_wrong_type() {
+    let half_adder = HalfAdder::new();
+    let mut bytes = Vec::new();
+    half_adder.write_to(&mut bytes).unwrap();
+    let text = String::from_utf8(bytes).unwrap();
+    assert!(HalfAdder::read_from(text.as_bytes()).is_ok());
+
+    // An OR network has the same shape as an XOR one, so only the type gives it away
+    let tampered = text.replacen("gate XOR", "gate OR", 1);
+    let error = HalfAdder::read_from(tampered.as_bytes()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(
+        error.to_string().contains("expected gate XOR but found OR"),
+        "{}",
+        error
+    );
+}
@@ -1,4 +1,4 @@
-use synapse::registers::{Register4Bit, CPURegisters};
+use synapse::registers::{Register4Bit, CPURegisters, StatusRegister};
 
 #[test]
 fn test_register_4bit_operations() {
@@ -44,8 +44,8 @@ fn test_cpu_registers() {
     assert_eq!(regs.accumulator.read(), 0);
     assert_eq!(regs.program_counter.read(), 0);
     assert_eq!(regs.instruction_register.read(), 0);
-    assert!(!regs.zero_flag);
-    assert!(!regs.carry_flag);
+    assert!(!regs.status.zero());
+    assert!(!regs.status.carry());
     
     // Test register operations
     regs.accumulator.write(5);
@@ -58,18 +58,46 @@ fn test_cpu_registers() {
     
     // Test flag updates
     regs.update_flags(0, true);
-    assert!(regs.zero_flag);
-    assert!(regs.carry_flag);
+    assert!(regs.status.zero());
+    assert!(regs.status.carry());
     
     regs.update_flags(7, false);
-    assert!(!regs.zero_flag);
-    assert!(!regs.carry_flag);
+    assert!(!regs.status.zero());
+    assert!(!regs.status.carry());
     
     // Test reset
     regs.reset();
     assert_eq!(regs.accumulator.read(), 0);
     assert_eq!(regs.program_counter.read(), 0);
     assert_eq!(regs.instruction_register.read(), 0);
-    assert!(!regs.zero_flag);
-    assert!(!regs.carry_flag);
-}
\ No newline at end of file
+    assert!(!regs.status.zero());
+    assert!(!regs.status.carry());
+}
+
+#[test]
+fn test_status_register() {
+    let mut status = StatusRegister::new();
+    assert_eq!(status.bits(), 0);
+    
+    // Each flag has its own bit
+    status.set(StatusRegister::NEGATIVE, true);
+    status.set(StatusRegister::OVERFLOW, true);
+    assert!(status.negative() && status.overflow());
+    assert!(!status.zero() && !status.carry() && !status.half_carry());
+    assert_eq!(status.bits(), StatusRegister::NEGATIVE | StatusRegister::OVERFLOW);
+    
+    status.set(StatusRegister::OVERFLOW, false);
+    assert_eq!(status, StatusRegister::from_bits(StatusRegister::NEGATIVE));
+    
+    // Only the six flag bits are kept
+    assert_eq!(StatusRegister::from_bits(0xFF).bits(), 0x3F);
+    assert!(StatusRegister::from_bits(StatusRegister::SUBTRACT).subtract());
+    
+    // Value updates set negative from bit 3 and keep overflow and half-carry
+    let mut regs = CPURegisters::new();
+    regs.status.set(StatusRegister::HALF_CARRY, true);
+    regs.update_flags(0b1001, false);
+    assert!(regs.status.negative());
+    assert!(regs.status.half_carry());
+    assert!(!regs.status.zero());
+}
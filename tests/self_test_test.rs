@@ -0,0 +1,159 @@
+use synapse::alu::{ALU, ALUOperation, AdderArchitecture};
+use synapse::cpu::CPU;
+use synapse::faults::{Fault, FaultInjection};
+use synapse::gate_factory::ReferenceGates;
+use synapse::self_test::{every_operation, reference};
+
+#[test]
+fn test_healthy_alu_passes() {
+    let report = ALU::new().self_test();
+
+    assert!(report.passed(), "{:?}", report.mismatches.first());
+    assert_eq!(report.width, 4);
+    assert!(report.exhaustive);
+    // 13 operations read both operands (256 pairs), 11 read A alone (16 values), and the
+    // 8 decimal adjusts only run on the values BCD arithmetic leaves with their flags
+    assert_eq!(every_operation().len(), 32);
+    let decimal: usize = every_operation()
+        .iter()
+        .filter(|operation| matches!(operation, ALUOperation::DecimalAdjust { .. }))
+        .map(|operation| {
+            (0..16)
+                .filter(|&a| reference(a, 0, operation, 4).is_some())
+                .count()
+        })
+        .sum();
+    assert!(decimal > 0 && decimal < 8 * 16);
+    assert_eq!(report.checked, 13 * 256 + 11 * 16 + decimal);
+    assert_eq!(report.first_faulty_stage(), None);
+}
+
+#[test]
+fn test_faulty_adder_stage_is_named() {
+    let mut alu = ALU::new();
+    alu.inject_fault("adder2.half_adder2.xor", Fault::StuckAt0)
+        .unwrap();
+
+    let report = alu.self_test();
+    assert!(!report.passed());
+
+    // 0 + 4 is the first sum that needs bit 2 set
+    let first = &report.mismatches[0];
+    assert_eq!(first.operation, ALUOperation::Add);
+    assert_eq!((first.a, first.b), (0, 4));
+    assert_eq!(first.expected.result, 4);
+    assert_eq!(first.actual.result, 0);
+    assert_eq!(first.first_wrong_bit, Some(2));
+    assert_eq!(first.stage.as_deref(), Some("adder2"));
+    assert_eq!(report.first_faulty_stage(), Some("adder2"));
+
+    // A wrong carry is blamed on the stage that produced it, not the bits it rippled into
+    let mut alu = ALU::new();
+    alu.inject_fault("adder0.or", Fault::StuckAt1).unwrap();
+    let report = alu.self_test();
+    let first = &report.mismatches[0];
+    assert_eq!((first.a, first.b), (0, 0));
+    assert_eq!(first.first_wrong_bit, Some(1));
+    assert_eq!(report.first_faulty_stage(), Some("adder0"));
+}
+
+#[test]
+fn test_faulty_inverter_is_named() {
+    let mut alu = ALU::new();
+    alu.inject_fault("not1", Fault::StuckAt0).unwrap();
+
+    let report = alu.self_test();
+    let first = &report.mismatches[0];
+    assert_eq!(first.operation, ALUOperation::Subtract);
+    assert_eq!(first.stage.as_deref(), Some("not1"));
+
+    // Faults away from the adder are reported without a stage
+    let mut alu = ALU::new();
+    alu.inject_fault("shifter.mux0.or", Fault::StuckAt1)
+        .unwrap();
+    let report = alu.self_test();
+    assert!(!report.passed());
+    assert!(
+        report
+            .mismatches
+            .iter()
+            .all(|mismatch| mismatch.stage.is_none())
+    );
+}
+
+#[test]
+fn test_cpu_power_on_self_test() {
+    let mut cpu = CPU::new();
+    assert!(cpu.power_on_self_test().passed());
+
+    cpu.inject_fault("alu.adder3.or", Fault::StuckAt1).unwrap();
+    let report = cpu.power_on_self_test();
+    assert!(!report.passed());
+    assert_eq!(report.first_faulty_stage(), Some("adder3"));
+}
+
+#[test]
+fn test_reference_alu_widths() {
+    for (width, architecture) in [
+        (1, AdderArchitecture::RippleCarry),
+        (3, AdderArchitecture::CarryLookahead),
+        (6, AdderArchitecture::RippleCarry),
+        (5, AdderArchitecture::CarryLookahead),
+    ] {
+        let report = ALU::with_width(&mut ReferenceGates, width, architecture).self_test();
+        assert!(
+            report.passed(),
+            "{} bits: {:?}",
+            width,
+            report.mismatches.first()
+        );
+    }
+
+    // The reference keeps the high word and remainder next to the result
+    let (product, high) = reference(13, 11, &ALUOperation::Multiply, 4).unwrap();
+    assert_eq!((product.result, high), (143 & 0xF, 143 >> 4));
+    let (quotient, remainder) = reference(13, 0, &ALUOperation::Divide, 4).unwrap();
+    assert_eq!((quotient.result, remainder), (0xF, 13));
+    assert!(quotient.overflow);
+}
+
+#[test]
+fn test_decimal_adjust_reference() {
+    let adjust = |carry, half_carry, subtract| ALUOperation::DecimalAdjust {
+        carry,
+        half_carry,
+        subtract,
+    };
+
+    // 38 + 45 = 0x7D in binary, 83 in decimal
+    let (sum, _) = reference(0x7D, 0, &adjust(false, false, false), 8).unwrap();
+    assert_eq!((sum.result, sum.carry), (0x83, false));
+    // 99 + 99 = 0x132 in binary, 198 in decimal
+    let (sum, _) = reference(0x32, 0, &adjust(true, true, false), 8).unwrap();
+    assert_eq!((sum.result, sum.carry), (0x98, true));
+    // 10 - 1 = 0x0F in binary; a 6-bit ALU counts its top 2 bits in tens
+    let (difference, _) = reference(0x0F, 0, &adjust(true, false, true), 6).unwrap();
+    assert_eq!((difference.result, difference.carry), (0x09, true));
+    // 35 + 5 = 40 wraps a 6-bit ALU, whose BCD range is 0 to 39
+    let (sum, _) = reference(0x3A, 0, &adjust(false, false, false), 6).unwrap();
+    assert_eq!((sum.result, sum.carry), (0x00, true));
+
+    // No BCD sum leaves 0xF with a half-carry
+    assert_eq!(reference(0xF, 0, &adjust(false, true, false), 4), None);
+}
+
+#[test]
+fn test_wide_alus_are_sampled() {
+    for width in [9, 16, 64] {
+        let alu = ALU::with_width(&mut ReferenceGates, width, AdderArchitecture::RippleCarry);
+        let report = alu.self_test();
+        assert!(!report.exhaustive);
+        assert!(
+            report.passed(),
+            "{} bits: {:?}",
+            width,
+            report.mismatches.first()
+        );
+        assert!(report.checked > every_operation().len() * 10);
+    }
+}
@@ -0,0 +1,132 @@
+use aspirina::layer::Layer;
+use aspirina::matrix::Matrix;
+use synapse::alu::{ALU, ALUOperation};
+use synapse::gate_factory::{GateFactory, GateMode};
+use synapse::gates::{GateType, LogicGate};
+use synapse::training::TrainingConfig;
+
+#[test]
+fn test_default_training_report() {
+    let (gate, report) = LogicGate::with_config(GateType::XOR, &TrainingConfig::default());
+
+    assert_eq!(report.epochs(), 10_000);
+    assert!(report.converged());
+    assert_eq!(gate.accuracy(), 1.0);
+
+    // Loss goes down while training
+    assert!(report.final_loss().unwrap() < report.loss_curve[0]);
+}
+
+#[test]
+fn test_seeded_training_is_reproducible() {
+    let config = TrainingConfig::new().with_epochs(2_000).with_seed(42);
+
+    let (first, first_report) = LogicGate::with_config(GateType::AND, &config);
+    let (second, second_report) = LogicGate::with_config(GateType::AND, &config);
+    assert_eq!(first_report, second_report);
+    assert_eq!(
+        first.network().layers()[0].data,
+        second.network().layers()[0].data
+    );
+
+    // A different seed starts from different weights
+    let other = TrainingConfig::new().with_epochs(2_000).with_seed(7);
+    let (_, other_report) = LogicGate::with_config(GateType::AND, &other);
+    assert_ne!(first_report.loss_curve[0], other_report.loss_curve[0]);
+}
+
+#[test]
+fn test_early_stopping_on_target_loss() {
+    let config = TrainingConfig::new().with_target_loss(0.01);
+    let (_, report) = LogicGate::with_config(GateType::OR, &config);
+
+    assert!(report.epochs() < 10_000);
+    assert!(report.final_loss().unwrap() <= 0.01);
+    assert!(report.converged());
+}
+
+#[test]
+fn test_custom_initial_layers() {
+    let layers = vec![
+        Layer::new(Matrix::new(vec![vec![0.1, 0.1], vec![0.2, 0.2]])),
+        Layer::new(Matrix::new(vec![vec![0.3, 0.3]])),
+    ];
+    let config = TrainingConfig::new()
+        .with_epochs(0)
+        .with_initial_layers(layers);
+    let (gate, report) = LogicGate::with_config(GateType::AND, &config);
+
+    // No training: weights are exactly the ones given
+    assert_eq!(report.epochs(), 0);
+    assert_eq!(
+        gate.network().layers()[0].data,
+        vec![vec![0.1, 0.1], vec![0.2, 0.2]]
+    );
+}
+
+#[test]
+fn test_factory_training_experiment() {
+    let config = TrainingConfig::new().with_seed(1);
+    let mut factory = GateFactory::with_config(GateMode::Shared, config);
+    let alu = ALU::with_factory(&mut factory);
+
+    // One report per trained network
+    let reports = factory.reports();
+    assert_eq!(reports.len(), 4);
+    assert!(reports.iter().all(|(_, report)| report.epochs() == 10_000));
+
+    // Seed 1 trains every gate type to full accuracy, so the ALU adds correctly
+    assert!(reports.iter().all(|(_, report)| report.converged()));
+    assert_eq!(alu.compute(5, 3, ALUOperation::Add).result, 8);
+}
+
+fn zero_layers() -> Vec<Layer> {
+    // Symmetric hidden neurons can never learn XOR
+    vec![
+        Layer::new(Matrix::new(vec![vec![0.0, 0.0], vec![0.0, 0.0]])),
+        Layer::new(Matrix::new(vec![vec![0.0, 0.0]])),
+    ]
+}
+
+#[test]
+fn test_try_new_converges() {
+    let gate = LogicGate::try_new(GateType::XOR).unwrap();
+    assert!(gate.failed_rows().is_empty());
+}
+
+#[test]
+fn test_non_convergence_reports_failing_rows() {
+    let config = TrainingConfig::new()
+        .with_epochs(1_000)
+        .with_initial_layers(zero_layers());
+    let error = LogicGate::try_with_config(GateType::XOR, &config).unwrap_err();
+
+    assert_eq!(error.gate_type, GateType::XOR);
+    assert_eq!(error.attempts, 1);
+    assert!(!error.failed_rows.is_empty());
+    for row in &error.failed_rows {
+        assert_eq!(row.expected, GateType::XOR.evaluate(&row.inputs));
+        assert_eq!(row.activation > 0.5, !row.expected);
+    }
+    assert!(error.to_string().contains("XOR gate did not converge"));
+}
+
+#[test]
+fn test_retry_reinitialises_until_converged() {
+    let config = TrainingConfig::new()
+        .with_initial_layers(zero_layers())
+        .with_retries(5);
+    let (gate, _) = LogicGate::try_with_config(GateType::XOR, &config).unwrap();
+    assert_eq!(gate.accuracy(), 1.0);
+}
+
+#[test]
+fn test_retry_gives_up_after_limit() {
+    // No training at all: every attempt keeps its random starting weights
+    let config = TrainingConfig::new()
+        .with_epochs(0)
+        .with_initial_layers(zero_layers())
+        .with_retries(2);
+    let error = LogicGate::try_with_config(GateType::AND, &config).unwrap_err();
+    assert_eq!(error.attempts, 3);
+}